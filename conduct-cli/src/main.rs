@@ -0,0 +1,133 @@
+//! `conduct`: a thin CLI front-end over `conduct-tk`, useful both for
+//! interactive inspection and as a build-script check.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use clap::{Parser as ClapParser, Subcommand};
+use conduct_tk::{
+    bin::{from_binary, to_binary},
+    err::{CodeSource, ConductCache},
+    parser::Parser,
+    tk::Token,
+    validate::Validator,
+    Logos,
+};
+
+#[derive(ClapParser)]
+#[command(name = "conduct", about = "Command-line front-end for the Conduct language")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Dump the raw token stream for a file.
+    Tokens { file: PathBuf },
+    /// Parse and validate a file, printing every diagnostic found.
+    Check { file: PathBuf },
+    /// Parse a file and write its compiled binary form.
+    Compile {
+        file: PathBuf,
+        #[arg(short, long, default_value = "out.cdt")]
+        output: PathBuf,
+    },
+    /// Read a compiled `.cdt` file back for inspection.
+    Decompile { file: PathBuf },
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match cli.command {
+        Command::Tokens { file } => tokens(&file),
+        Command::Check { file } => check(&file),
+        Command::Compile { file, output } => compile(&file, &output),
+        Command::Decompile { file } => decompile(&file),
+    }
+}
+
+fn read_source(file: &Path) -> String {
+    fs::read_to_string(file).unwrap_or_else(|err| {
+        eprintln!("error: couldn't read {}: {err}", file.display());
+        std::process::exit(1);
+    })
+}
+
+fn tokens(file: &Path) -> ExitCode {
+    let src = read_source(file);
+    for token in Token::lexer(&src) {
+        match token {
+            Ok(token) => println!("{token:?}"),
+            Err(()) => println!("<invalid token>"),
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+fn check(file: &Path) -> ExitCode {
+    let src = read_source(file);
+    let lexer = Token::lexer(&src);
+    let parser = Parser::new(CodeSource::File(file.to_path_buf()), lexer);
+    let validator = Validator::from(&parser);
+
+    match parser.then_pipe(validator).finish_pipeline() {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(reports) => {
+            for report in &reports {
+                let _ = report.report().print(ConductCache);
+            }
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn compile(file: &Path, output: &Path) -> ExitCode {
+    let src = read_source(file);
+    let lexer = Token::lexer(&src);
+    let parser = Parser::new(CodeSource::File(file.to_path_buf()), lexer);
+    let validator = Validator::from(&parser);
+
+    let stmts = match parser.then_pipe(validator).finish_pipeline() {
+        Ok(stmts) => stmts,
+        Err(reports) => {
+            for report in &reports {
+                let _ = report.report().print(ConductCache);
+            }
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let bytes = match to_binary(stmts) {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            eprintln!("error: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Err(err) = fs::write(output, bytes) {
+        eprintln!("error: couldn't write {}: {err}", output.display());
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
+fn decompile(file: &Path) -> ExitCode {
+    let bytes = fs::read(file).unwrap_or_else(|err| {
+        eprintln!("error: couldn't read {}: {err}", file.display());
+        std::process::exit(1);
+    });
+    match from_binary(&bytes) {
+        Ok(stmts) => {
+            println!("{stmts:#?}");
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
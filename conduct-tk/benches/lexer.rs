@@ -0,0 +1,36 @@
+//! Lexer throughput harness: `cargo bench -p conduct-tk` lexes a
+//! generated ~1 MB source and prints best-of-five MB/s. Deliberately
+//! dependency-free (plain `std::time`), but the shape — generate, warm
+//! up, measure several rounds, keep the best — matches what a criterion
+//! bench would report.
+
+use std::time::Instant;
+
+use conduct_tk::tk::lex_with_spans;
+
+fn main() {
+    let mut src = String::new();
+    for i in 0..20_000 {
+        src.push_str(&format!(
+            "let v{i} = {i} + 0xFF_AA{:02x} * 0b1010 - 0o17_6 / 1_000_000\n",
+            i % 256
+        ));
+    }
+
+    // Warm-up pass doubles as the reference token stream.
+    let reference = lex_with_spans(&src);
+    let mut best = f64::INFINITY;
+    for _ in 0..5 {
+        let start = Instant::now();
+        let tokens = lex_with_spans(&src);
+        let elapsed = start.elapsed().as_secs_f64();
+        assert_eq!(tokens.len(), reference.len());
+        best = best.min(elapsed);
+    }
+    let throughput = src.len() as f64 / 1_000_000.0 / best;
+    println!(
+        "lexed {} bytes into {} tokens: best {best:.4}s ({throughput:.1} MB/s)",
+        src.len(),
+        reference.len()
+    );
+}
@@ -0,0 +1,1695 @@
+//! The syntax tree produced by [`crate::parser::Parser`], plus the
+//! [`Visitor`] trait for writing passes over it.
+
+use std::ops::ControlFlow;
+
+use serde::{Deserialize, Serialize};
+
+use crate::err::CodeArea;
+
+/// A binary operator, as it appears between two [`Expr`]s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Pow,
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+    /// `&&`: logical and. Short-circuits — an evaluator must not touch
+    /// the right operand when the left is already false.
+    And,
+    /// `||`: logical or. Short-circuits like [`BinOp::And`].
+    Or,
+    BitAnd,
+    BitOr,
+    BitXor,
+    /// `<<`, a left shift.
+    Shl,
+    /// `>>`, a right shift.
+    Shr,
+}
+
+/// A unary operator applied to a single [`Expr`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UnaryOp {
+    Not,
+    Neg,
+    /// `~`, bitwise complement.
+    BitNot,
+    /// `!!`, Conduct's "unwrap or throw" operator.
+    Unwrap,
+}
+
+/// The radix an integer literal was written in. The non-decimal forms
+/// keep the digits as written (case, leading zeros — separators aside),
+/// so formatting reproduces the source spelling while evaluation uses
+/// the numeric value alone. Programmatically built literals default to
+/// decimal.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum IntBase {
+    #[default]
+    Dec,
+    /// `0xFFAAFF`: the digits after the `0x` prefix.
+    Hex(String),
+    /// `0o143047`: the digits after the `0o` prefix.
+    Oct(String),
+    /// `0b010101`: the digits after the `0b` prefix.
+    Bin(String),
+}
+
+impl IntBase {
+    /// Reads the radix (and its digits) off an integer literal's source
+    /// slice; digit separators are dropped.
+    pub fn from_source(slice: &str) -> IntBase {
+        let digits = || slice[2..].replace('_', "");
+        match slice.as_bytes().first_chunk::<2>() {
+            Some(b"0x" | b"0X") => IntBase::Hex(digits()),
+            Some(b"0o" | b"0O") => IntBase::Oct(digits()),
+            Some(b"0b" | b"0B") => IntBase::Bin(digits()),
+            _ => IntBase::Dec,
+        }
+    }
+}
+
+/// A literal value as written in source, before any evaluation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Literal {
+    /// An integer and the radix it was written in; the radix is purely
+    /// presentational (see [`IntBase`]).
+    Int(i64, IntBase),
+    Float(f64),
+    /// The string literal verbatim as written, delimiters and all —
+    /// quoted (`"a\nb"`, `'a'`) or raw (`r"\n"`, `r#".."#`). Use
+    /// [`Literal::str_value`] to decode it into the text it denotes.
+    Str(String),
+    Bool(bool),
+    Nil,
+    /// A regex literal `/[a-z]+/i`: the pattern between the delimiters
+    /// (verbatim, escapes unprocessed) and its trailing flags.
+    Regex { pattern: String, flags: String },
+}
+
+impl Literal {
+    /// Decodes a string literal into the text it denotes: quoted forms
+    /// get their escapes (`\n`, `\t`, `\uXXXX`, ...) processed, raw
+    /// forms are taken verbatim between their delimiters. `None` for
+    /// non-string literals.
+    pub fn str_value(&self) -> Option<String> {
+        let Literal::Str(source) = self else {
+            return None;
+        };
+        if let Some(stripped) = source.strip_prefix('r') {
+            let hashes = stripped.chars().take_while(|&c| c == '#').count();
+            let inner = stripped.get(hashes + 1..stripped.len() - hashes - 1)?;
+            return Some(inner.to_owned());
+        }
+        Some(unescape(source.get(1..source.len() - 1)?))
+    }
+}
+
+/// Processes the escape sequences of a quoted string literal's contents.
+/// Unknown escapes degrade to the escaped character itself rather than
+/// erroring, matching how forgiving the lexer is about them.
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('r') => out.push('\r'),
+            Some('0') => out.push('\0'),
+            Some('u') => {
+                // `\u{1F600}` braces an arbitrary-length scalar; the
+                // fixed `\uXXXX` form takes exactly four hex digits.
+                let digits: String = if chars.clone().next() == Some('{') {
+                    chars.next();
+                    let braced: String = chars.clone().take_while(|&c| c != '}').collect();
+                    for _ in 0..braced.len() {
+                        chars.next();
+                    }
+                    chars.next(); // the closing `}`
+                    braced
+                } else {
+                    let fixed: String = chars
+                        .clone()
+                        .take(4)
+                        .take_while(char::is_ascii_hexdigit)
+                        .collect();
+                    for _ in 0..fixed.len() {
+                        chars.next();
+                    }
+                    fixed
+                };
+                if let Some(decoded) = u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                {
+                    out.push(decoded);
+                }
+            }
+            Some(other) => out.push(other),
+            None => {}
+        }
+    }
+    out
+}
+
+/// Validates the escape sequences of a quoted string literal slice
+/// (delimiters included), returning the byte range *within the slice*
+/// and a message for the first malformed one: bad or missing hex digits
+/// in `\uXXXX`, and an unterminated, empty, or out-of-range `\u{...}`.
+/// Decoding itself ([`unescape`]) stays forgiving; the parser turns
+/// these into real diagnostics with exact spans.
+pub(crate) fn check_escapes(literal: &str) -> Option<((usize, usize), String)> {
+    let bytes = literal.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] != b'\\' {
+            i += 1;
+            continue;
+        }
+        let escape_start = i;
+        i += 1;
+        if bytes.get(i) != Some(&b'u') {
+            // Everything but `\u` decodes forgivingly (unknown escapes
+            // degrade to the escaped character itself).
+            i += 1;
+            continue;
+        }
+        i += 1;
+        if bytes.get(i) == Some(&b'{') {
+            let Some(close) = literal[i..].find('}') else {
+                return Some((
+                    (escape_start, literal.len()),
+                    "unterminated `\\u{...}` escape".to_owned(),
+                ));
+            };
+            let digits = &literal[i + 1..i + close];
+            let end = i + close + 1;
+            if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return Some((
+                    (escape_start, end),
+                    format!("`\\u{{{digits}}}` is not a hexadecimal scalar value"),
+                ));
+            }
+            if u32::from_str_radix(digits, 16)
+                .ok()
+                .and_then(char::from_u32)
+                .is_none()
+            {
+                return Some((
+                    (escape_start, end),
+                    format!("`\\u{{{digits}}}` is outside the Unicode scalar range"),
+                ));
+            }
+            i = end;
+        } else {
+            let digits: String = literal[i..].chars().take(4).collect();
+            let hex_len = digits
+                .bytes()
+                .take_while(|b| b.is_ascii_hexdigit())
+                .count();
+            if hex_len < 4 {
+                return Some((
+                    (escape_start, i + hex_len),
+                    "`\\u` expects four hex digits (or a braced `\\u{...}` scalar)"
+                        .to_owned(),
+                ));
+            }
+            i += 4;
+        }
+    }
+    None
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Expr {
+    pub kind: ExprKind,
+    pub area: CodeArea,
+}
+
+impl Expr {
+    /// The `(start, end)` byte span of this expression in its source;
+    /// shorthand for `self.area.span`.
+    pub fn span(&self) -> (usize, usize) {
+        self.area.span
+    }
+}
+
+/// One function (or arrow-function) parameter.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Param {
+    pub name: String,
+    pub ty: Option<Expr>,
+    /// From `greeting = "Hello"`. Parameters with defaults must follow
+    /// all parameters without; the parser enforces the ordering.
+    pub default: Option<Expr>,
+    /// From `...rest`: collects any trailing call arguments into an
+    /// array. Only valid on the final parameter.
+    pub variadic: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ExprKind {
+    Literal(Literal),
+    Array(Vec<Expr>),
+    /// `(1, "two", 3.0)`: a fixed-size grouping. `(expr)` stays plain
+    /// grouping; the trailing comma makes `(expr,)` a one-element tuple.
+    Tuple(Vec<Expr>),
+    /// `"Hello ${name}"`: literal text and embedded expressions, in
+    /// source order. A string with no (unescaped) `${..}` stays a plain
+    /// [`Literal::Str`].
+    InterpolatedString(Vec<StringPart>),
+    Compound(Vec<(CompoundKey, Expr)>),
+    /// `#{1, 2, 3}`: a set literal. `#{}` is the empty set — a map needs
+    /// at least one `=>` entry to read as one.
+    Set(Vec<Expr>),
+    /// `#{ k => v, .. }`: a map literal whose keys are arbitrary
+    /// expressions, unlike [`ExprKind::Compound`]'s string field names.
+    Map(Vec<(Expr, Expr)>),
+    TypeDef(Vec<(String, String)>),
+    Ident(String),
+    /// A dotted module/member path, e.g. `std.io.IoError`.
+    Path(Vec<String>),
+    /// `0..10`, `0..=10`, `0..10 step 2`: a range with an optional step
+    /// (1 when absent). A zero step is a validation error.
+    Range {
+        start: Box<Expr>,
+        end: Box<Expr>,
+        inclusive: bool,
+        step: Option<Box<Expr>>,
+    },
+    Unary(UnaryOp, Box<Expr>),
+    /// `typeof expr` (or the call-style `typeof(expr)`): the name of the
+    /// operand's runtime type, always a `str`.
+    TypeOf(Box<Expr>),
+    /// `expr?`: rethrows to the caller when `expr` evaluates to an error,
+    /// Rust-style. Distinct from the ternary `?` (whose then-branch must
+    /// follow on the same line) and from `?.` optional chaining.
+    Try(Box<Expr>),
+    /// `i++`: yields the value, then adds one to the place. The target
+    /// must be assignable (the validator rejects literals and consts).
+    PostIncrement(Box<Expr>),
+    /// `i--`, the decrementing twin of [`ExprKind::PostIncrement`].
+    PostDecrement(Box<Expr>),
+    /// `...expr` inside an array literal (`[...a, 4]`) or a call's
+    /// argument list (`f(...args)`): splices the operand's elements in
+    /// place. The parser only ever builds it in those two positions.
+    Spread(Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+    Ternary {
+        cond: Box<Expr>,
+        then: Box<Expr>,
+        otherwise: Box<Expr>,
+    },
+    Call {
+        callee: Box<Expr>,
+        args: Vec<Expr>,
+    },
+    Index {
+        target: Box<Expr>,
+        index: Box<Expr>,
+    },
+    Member {
+        target: Box<Expr>,
+        name: String,
+    },
+    /// `a ?? b`: evaluates to `b` when `a` is nil. Chains associate to
+    /// the left, so `a ?? b ?? c` is `(a ?? b) ?? c`.
+    NullCoalesce(Box<Expr>, Box<Expr>),
+    /// `a?.b`: nil when `target` is nil, member access otherwise, so a
+    /// `a?.b?.c` chain short-circuits to nil on the first nil link.
+    OptionalAccess {
+        target: Box<Expr>,
+        name: String,
+    },
+    ArrowFn {
+        params: Vec<Param>,
+        body: Vec<Statement>,
+        /// Whether the body was written as a bare expression
+        /// (`x => x * 2`); the lone `return` inside is the implicit one
+        /// the parser inserted, and the formatter prints the concise
+        /// form back.
+        expr_body: bool,
+    },
+    Match {
+        subject: Box<Expr>,
+        arms: Vec<MatchArm>,
+    },
+    /// A `while`/`for` loop in expression position: `let x = while cond
+    /// { break value }` yields whatever value broke it (nil when the
+    /// loop ends without one). Wraps the ordinary loop statement.
+    Loop(Box<Statement>),
+    /// A syntax error the parser recovered from; carries no meaning beyond
+    /// its span, so later passes should skip it rather than evaluate it.
+    Garbage,
+}
+
+/// One compound-literal key: a static name (`{ name: .. }`, bare or
+/// quoted) or a computed `[expr]` evaluated when the literal is built.
+/// A compound with any computed key has no static shape the checker can
+/// reason about.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum CompoundKey {
+    Static(String),
+    Computed(Expr),
+}
+
+impl CompoundKey {
+    /// The key's name when it's static; computed keys have none until
+    /// runtime.
+    pub fn as_static(&self) -> Option<&str> {
+        match self {
+            CompoundKey::Static(name) => Some(name),
+            CompoundKey::Computed(_) => None,
+        }
+    }
+}
+
+/// One piece of an interpolated string literal; see
+/// [`ExprKind::InterpolatedString`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum StringPart {
+    /// Literal text between interpolations, escapes still unprocessed
+    /// (decode alongside [`Literal::str_value`] semantics).
+    Text(String),
+    Expr(Expr),
+}
+
+/// One `pattern => body` arm of a `match` expression.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub body: Box<Expr>,
+}
+
+/// A pattern tried against a `match` subject, and whatever bindings it
+/// introduces into the arm's body on a successful match.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Pattern {
+    /// `_`, matches anything and binds nothing.
+    Wildcard,
+    Literal(Literal),
+    /// A bare name, matches anything and binds it.
+    Binding(String),
+    Array {
+        elements: Vec<Pattern>,
+        /// `..name` trailing a fixed prefix, binding the remainder.
+        rest: Option<String>,
+    },
+    Compound {
+        fields: Vec<(String, Pattern)>,
+        /// Whether a trailing `..` allows fields beyond `fields` to exist.
+        open: bool,
+    },
+}
+
+/// A source comment captured by a comment-keeping parse (see
+/// [`crate::parser::Parser::new_keep_comments`]); the lexer normally
+/// skips these entirely.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Comment {
+    /// The comment verbatim, delimiters included.
+    pub text: String,
+    /// Byte span in the source.
+    pub span: (usize, usize),
+    /// Whether this is a `///` doc comment.
+    pub doc: bool,
+}
+
+/// One variant of an `enum` declaration.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EnumVariant {
+    pub name: String,
+    /// Field names of a tuple-style variant (`Circle(radius)`); empty
+    /// for a unit variant.
+    pub fields: Vec<String>,
+}
+
+/// `@name(args...)` metadata attached to a declaration, e.g.
+/// `@deprecated("use foo")`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Annotation {
+    pub name: String,
+    pub args: Vec<Expr>,
+    pub area: CodeArea,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Statement {
+    pub kind: StatementKind,
+    pub area: CodeArea,
+    /// Comments attached to this statement by a comment-keeping parse;
+    /// empty otherwise.
+    pub comments: Vec<Comment>,
+    /// `@..` annotations preceding this declaration; only `fn`, `const`,
+    /// and `let` accept them.
+    pub annotations: Vec<Annotation>,
+}
+
+impl Statement {
+    /// The `(start, end)` byte span of this statement in its source;
+    /// shorthand for `self.area.span`.
+    pub fn span(&self) -> (usize, usize) {
+        self.area.span
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum StatementKind {
+    Expr(Expr),
+    Let {
+        name: String,
+        ty: Option<Expr>,
+        value: Option<Expr>,
+    },
+    /// `let (a, b) = pair`: destructures a tuple value into one binding
+    /// per element.
+    LetTuple {
+        names: Vec<String>,
+        value: Expr,
+    },
+    Const {
+        name: String,
+        ty: Option<Expr>,
+        value: Option<Expr>,
+        native: bool,
+    },
+    Fn {
+        name: String,
+        /// `fn map<T, U>(..)`: declared type parameters, stored for the
+        /// gradually-typed future; today they only scope and print.
+        type_params: Vec<String>,
+        params: Vec<Param>,
+        ret: Option<Expr>,
+        body: Vec<Statement>,
+        native: bool,
+    },
+    Assign {
+        target: Expr,
+        op: Option<BinOp>,
+        value: Expr,
+    },
+    /// `a = b = 0`: every target receives the same value, assigned
+    /// right-to-left.
+    AssignChain {
+        targets: Vec<Expr>,
+        value: Expr,
+    },
+    /// `a, b = b, a`: parallel assignment — all values evaluate before
+    /// any target is written, which is what makes the swap work.
+    AssignParallel {
+        targets: Vec<Expr>,
+        values: Vec<Expr>,
+    },
+    If {
+        branches: Vec<(Expr, Vec<Statement>)>,
+        otherwise: Option<Vec<Statement>>,
+    },
+    For {
+        binding: String,
+        iterable: Expr,
+        body: Vec<Statement>,
+        /// From `outer: for ..`, the label a `break`/`continue` can target.
+        label: Option<String>,
+    },
+    While {
+        cond: Expr,
+        body: Vec<Statement>,
+        label: Option<String>,
+    },
+    /// `if let x = maybe() { .. }`: binds `x` and enters the body only
+    /// when the expression is non-nil; the binding is scoped to the body.
+    IfLet {
+        binding: String,
+        value: Expr,
+        body: Vec<Statement>,
+        otherwise: Option<Vec<Statement>>,
+    },
+    /// `while let x = next() { .. }`: re-evaluates the expression each
+    /// iteration, binding it and looping until it comes back nil.
+    WhileLet {
+        binding: String,
+        value: Expr,
+        body: Vec<Statement>,
+        label: Option<String>,
+    },
+    /// `do { .. } while cond`: post-condition loop, body runs at least
+    /// once.
+    DoWhile {
+        body: Vec<Statement>,
+        cond: Expr,
+    },
+    Return(Option<Expr>),
+    /// `break`, optionally targeting an enclosing loop's label and
+    /// optionally carrying a value — `break 5` gives an
+    /// expression-position loop (see [`ExprKind::Loop`]) its result.
+    /// After `break`, a bare identifier on the same line reads as a
+    /// label; parenthesize (`break (x)`) to break with a variable.
+    Break {
+        label: Option<String>,
+        value: Option<Expr>,
+    },
+    Continue(Option<String>),
+    Throw(Expr),
+    /// `assert cond, "msg"` / `assert_eq a, b, "msg"`: a test assertion.
+    /// `assert_eq` keeps both operands separately (rather than desugaring
+    /// to `a == b`) so a runtime can report the two values on failure.
+    Assert {
+        lhs: Expr,
+        /// The second operand of an `assert_eq`; a plain `assert` has none.
+        rhs: Option<Expr>,
+        message: Option<Expr>,
+    },
+    Try {
+        body: Vec<Statement>,
+        catches: Vec<CatchArm>,
+    },
+    Module(String),
+    Import {
+        path: String,
+        /// A local rename from `import std.io as io`, if one was written.
+        alias: Option<String>,
+    },
+    /// `import { read, write as w } from std.io`: pulls individual items
+    /// out of a module instead of binding the whole module.
+    SelectiveImport {
+        /// Each imported name with its optional `as` rename.
+        names: Vec<(String, Option<String>)>,
+        path: String,
+    },
+    /// `include '../include/headers.cdh'`: compile-time textual
+    /// inclusion. Unlike `import`, the included file's declarations
+    /// splice straight into the current scope, unqualified; the
+    /// validator resolves and merges them.
+    Include(String),
+    Export(String),
+    /// `export { read as r, write } from internal`: re-exports a subset
+    /// of another module's items, optionally renamed — mirroring
+    /// [`StatementKind::SelectiveImport`]. `export foo as bar` renames a
+    /// local definition instead, carried as a single-entry list with no
+    /// source module.
+    SelectiveExport {
+        names: Vec<(String, Option<String>)>,
+        /// The module re-exported from; `None` when renaming local names.
+        from: Option<String>,
+    },
+    /// `enum Shape { Circle(radius), Rect(w, h) }`: a sum type with unit
+    /// and tuple-style variants.
+    Enum {
+        name: String,
+        variants: Vec<EnumVariant>,
+    },
+    /// `struct Point { x: num, y: num }`: a named record shape, unlike
+    /// the anonymous `type { .. }` literal.
+    Struct {
+        name: String,
+        /// `struct Box<T> { .. }`: declared type parameters.
+        type_params: Vec<String>,
+        fields: Vec<(String, String)>,
+    },
+    /// A syntax error the parser recovered from.
+    Error,
+}
+
+/// A read-only pass over the tree. Every hook defaults to recursing via
+/// its `walk_*` driver, so an implementor overrides only the nodes it
+/// cares about (and calls the driver itself if it still wants the
+/// children visited). Returning [`ControlFlow::Break`] from any hook
+/// stops the entire walk, which is how a search-style pass bails out
+/// early instead of touching the rest of the tree.
+pub trait Visitor {
+    fn visit_statement(&mut self, stmt: &Statement) -> ControlFlow<()> {
+        walk_statement(self, stmt)
+    }
+
+    fn visit_expression(&mut self, expr: &Expr) -> ControlFlow<()> {
+        walk_expression(self, expr)
+    }
+
+    /// Called for every [`Literal`] value. Literals have no children, so
+    /// the default just continues.
+    fn visit_value(&mut self, value: &Literal) -> ControlFlow<()> {
+        let _ = value;
+        ControlFlow::Continue(())
+    }
+}
+
+/// Drives [`Visitor::visit_statement`] over each statement in a block.
+pub fn walk_block<V: Visitor + ?Sized>(
+    visitor: &mut V,
+    stmts: &[Statement],
+) -> ControlFlow<()> {
+    for stmt in stmts {
+        visitor.visit_statement(stmt)?;
+    }
+    ControlFlow::Continue(())
+}
+
+/// Recurses into every child node of `stmt`, in source order.
+pub fn walk_statement<V: Visitor + ?Sized>(
+    visitor: &mut V,
+    stmt: &Statement,
+) -> ControlFlow<()> {
+    match &stmt.kind {
+        StatementKind::Expr(expr)
+        | StatementKind::Return(Some(expr))
+        | StatementKind::Throw(expr) => visitor.visit_expression(expr),
+        StatementKind::Assert { lhs, rhs, message } => {
+            visitor.visit_expression(lhs)?;
+            if let Some(rhs) = rhs {
+                visitor.visit_expression(rhs)?;
+            }
+            if let Some(message) = message {
+                visitor.visit_expression(message)?;
+            }
+            ControlFlow::Continue(())
+        }
+        StatementKind::Let { ty, value, .. } | StatementKind::Const { ty, value, .. } => {
+            if let Some(ty) = ty {
+                visitor.visit_expression(ty)?;
+            }
+            if let Some(value) = value {
+                visitor.visit_expression(value)?;
+            }
+            ControlFlow::Continue(())
+        }
+        StatementKind::Fn {
+            params, ret, body, ..
+        } => {
+            for param in params {
+                if let Some(ty) = &param.ty {
+                    visitor.visit_expression(ty)?;
+                }
+                if let Some(default) = &param.default {
+                    visitor.visit_expression(default)?;
+                }
+            }
+            if let Some(ret) = ret {
+                visitor.visit_expression(ret)?;
+            }
+            walk_block(visitor, body)
+        }
+        StatementKind::LetTuple { value, .. } => visitor.visit_expression(value),
+        StatementKind::Break { value, .. } => {
+            if let Some(value) = value {
+                visitor.visit_expression(value)?;
+            }
+            ControlFlow::Continue(())
+        }
+        StatementKind::AssignChain { targets, value } => {
+            for target in targets {
+                visitor.visit_expression(target)?;
+            }
+            visitor.visit_expression(value)
+        }
+        StatementKind::AssignParallel { targets, values } => {
+            for target in targets {
+                visitor.visit_expression(target)?;
+            }
+            for value in values {
+                visitor.visit_expression(value)?;
+            }
+            ControlFlow::Continue(())
+        }
+        StatementKind::Assign { target, value, .. } => {
+            visitor.visit_expression(target)?;
+            visitor.visit_expression(value)
+        }
+        StatementKind::If { branches, otherwise } => {
+            for (cond, body) in branches {
+                visitor.visit_expression(cond)?;
+                walk_block(visitor, body)?;
+            }
+            if let Some(otherwise) = otherwise {
+                walk_block(visitor, otherwise)?;
+            }
+            ControlFlow::Continue(())
+        }
+        StatementKind::For { iterable, body, .. } => {
+            visitor.visit_expression(iterable)?;
+            walk_block(visitor, body)
+        }
+        StatementKind::While { cond, body, .. } => {
+            visitor.visit_expression(cond)?;
+            walk_block(visitor, body)
+        }
+        StatementKind::IfLet {
+            value,
+            body,
+            otherwise,
+            ..
+        } => {
+            visitor.visit_expression(value)?;
+            walk_block(visitor, body)?;
+            if let Some(otherwise) = otherwise {
+                walk_block(visitor, otherwise)?;
+            }
+            ControlFlow::Continue(())
+        }
+        StatementKind::WhileLet { value, body, .. } => {
+            visitor.visit_expression(value)?;
+            walk_block(visitor, body)
+        }
+        StatementKind::DoWhile { body, cond } => {
+            walk_block(visitor, body)?;
+            visitor.visit_expression(cond)
+        }
+        StatementKind::Try { body, catches } => {
+            walk_block(visitor, body)?;
+            for arm in catches {
+                walk_block(visitor, &arm.body)?;
+            }
+            ControlFlow::Continue(())
+        }
+        StatementKind::Return(None)
+
+        | StatementKind::Continue(_)
+        | StatementKind::Module(_)
+        | StatementKind::Import { .. }
+        | StatementKind::SelectiveImport { .. }
+        | StatementKind::Include(_)
+        | StatementKind::Export(_)
+        | StatementKind::SelectiveExport { .. }
+        | StatementKind::Enum { .. }
+        | StatementKind::Struct { .. }
+        | StatementKind::Error => ControlFlow::Continue(()),
+    }
+}
+
+/// Recurses into every child node of `expr`, in source order.
+pub fn walk_expression<V: Visitor + ?Sized>(
+    visitor: &mut V,
+    expr: &Expr,
+) -> ControlFlow<()> {
+    match &expr.kind {
+        ExprKind::Literal(value) => visitor.visit_value(value),
+        ExprKind::Unary(_, inner)
+        | ExprKind::TypeOf(inner)
+        | ExprKind::Try(inner)
+        | ExprKind::PostIncrement(inner)
+        | ExprKind::PostDecrement(inner)
+        | ExprKind::Spread(inner) => visitor.visit_expression(inner),
+        ExprKind::Binary(_, lhs, rhs) => {
+            visitor.visit_expression(lhs)?;
+            visitor.visit_expression(rhs)
+        }
+        ExprKind::Ternary {
+            cond,
+            then,
+            otherwise,
+        } => {
+            visitor.visit_expression(cond)?;
+            visitor.visit_expression(then)?;
+            visitor.visit_expression(otherwise)
+        }
+        ExprKind::Call { callee, args } => {
+            visitor.visit_expression(callee)?;
+            for arg in args {
+                visitor.visit_expression(arg)?;
+            }
+            ControlFlow::Continue(())
+        }
+        ExprKind::Index { target, index } => {
+            visitor.visit_expression(target)?;
+            visitor.visit_expression(index)
+        }
+        ExprKind::Member { target, .. } | ExprKind::OptionalAccess { target, .. } => {
+            visitor.visit_expression(target)
+        }
+        ExprKind::NullCoalesce(lhs, rhs) => {
+            visitor.visit_expression(lhs)?;
+            visitor.visit_expression(rhs)
+        }
+        ExprKind::Array(elements) | ExprKind::Tuple(elements) | ExprKind::Set(elements) => {
+            for element in elements {
+                visitor.visit_expression(element)?;
+            }
+            ControlFlow::Continue(())
+        }
+        ExprKind::Map(entries) => {
+            for (key, value) in entries {
+                visitor.visit_expression(key)?;
+                visitor.visit_expression(value)?;
+            }
+            ControlFlow::Continue(())
+        }
+        ExprKind::InterpolatedString(parts) => {
+            for part in parts {
+                if let StringPart::Expr(expr) = part {
+                    visitor.visit_expression(expr)?;
+                }
+            }
+            ControlFlow::Continue(())
+        }
+        ExprKind::Compound(fields) => {
+            for (key, value) in fields {
+                if let CompoundKey::Computed(key) = key {
+                    visitor.visit_expression(key)?;
+                }
+                visitor.visit_expression(value)?;
+            }
+            ControlFlow::Continue(())
+        }
+        ExprKind::ArrowFn { params, body, .. } => {
+            for param in params {
+                if let Some(ty) = &param.ty {
+                    visitor.visit_expression(ty)?;
+                }
+                if let Some(default) = &param.default {
+                    visitor.visit_expression(default)?;
+                }
+            }
+            walk_block(visitor, body)
+        }
+        ExprKind::Match { subject, arms } => {
+            visitor.visit_expression(subject)?;
+            for arm in arms {
+                visitor.visit_expression(&arm.body)?;
+            }
+            ControlFlow::Continue(())
+        }
+        ExprKind::Range { start, end, step, .. } => {
+            visitor.visit_expression(start)?;
+            visitor.visit_expression(end)?;
+            if let Some(step) = step {
+                visitor.visit_expression(step)?;
+            }
+            ControlFlow::Continue(())
+        }
+        ExprKind::Loop(stmt) => visitor.visit_statement(stmt),
+        ExprKind::Ident(_) | ExprKind::Path(_) | ExprKind::TypeDef(_) | ExprKind::Garbage => {
+            ControlFlow::Continue(())
+        }
+    }
+}
+
+/// The mutable twin of [`Visitor`]: every hook receives `&mut` nodes, so
+/// a pass can rewrite children in place — renames, literal rewrites,
+/// desugarings — without reconstructing the tree. The drivers only ever
+/// hand out mutable references; spans stay exactly as parsed unless a
+/// hook changes them deliberately.
+pub trait VisitorMut {
+    fn visit_statement(&mut self, stmt: &mut Statement) -> ControlFlow<()> {
+        walk_statement_mut(self, stmt)
+    }
+
+    fn visit_expression(&mut self, expr: &mut Expr) -> ControlFlow<()> {
+        walk_expression_mut(self, expr)
+    }
+
+    /// Called for every [`Literal`] value. Literals have no children, so
+    /// the default just continues.
+    fn visit_value(&mut self, value: &mut Literal) -> ControlFlow<()> {
+        let _ = value;
+        ControlFlow::Continue(())
+    }
+}
+
+/// Drives [`VisitorMut::visit_statement`] over each statement in a block.
+pub fn walk_block_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    stmts: &mut [Statement],
+) -> ControlFlow<()> {
+    for stmt in stmts {
+        visitor.visit_statement(stmt)?;
+    }
+    ControlFlow::Continue(())
+}
+
+/// Mutable twin of [`walk_statement`]: recurses into every child of
+/// `stmt` in source order, handing each out `&mut`.
+pub fn walk_statement_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    stmt: &mut Statement,
+) -> ControlFlow<()> {
+    match &mut stmt.kind {
+        StatementKind::Expr(expr)
+        | StatementKind::Return(Some(expr))
+        | StatementKind::Throw(expr) => visitor.visit_expression(expr),
+        StatementKind::Assert { lhs, rhs, message } => {
+            visitor.visit_expression(lhs)?;
+            if let Some(rhs) = rhs {
+                visitor.visit_expression(rhs)?;
+            }
+            if let Some(message) = message {
+                visitor.visit_expression(message)?;
+            }
+            ControlFlow::Continue(())
+        }
+        StatementKind::Let { ty, value, .. } | StatementKind::Const { ty, value, .. } => {
+            if let Some(ty) = ty {
+                visitor.visit_expression(ty)?;
+            }
+            if let Some(value) = value {
+                visitor.visit_expression(value)?;
+            }
+            ControlFlow::Continue(())
+        }
+        StatementKind::Fn {
+            params, ret, body, ..
+        } => {
+            for param in params {
+                if let Some(ty) = &mut param.ty {
+                    visitor.visit_expression(ty)?;
+                }
+                if let Some(default) = &mut param.default {
+                    visitor.visit_expression(default)?;
+                }
+            }
+            if let Some(ret) = ret.as_mut() {
+                visitor.visit_expression(ret)?;
+            }
+            walk_block_mut(visitor, body)
+        }
+        StatementKind::LetTuple { value, .. } => visitor.visit_expression(value),
+        StatementKind::Break { value, .. } => {
+            if let Some(value) = value {
+                visitor.visit_expression(value)?;
+            }
+            ControlFlow::Continue(())
+        }
+        StatementKind::AssignChain { targets, value } => {
+            for target in targets {
+                visitor.visit_expression(target)?;
+            }
+            visitor.visit_expression(value)
+        }
+        StatementKind::AssignParallel { targets, values } => {
+            for target in targets {
+                visitor.visit_expression(target)?;
+            }
+            for value in values {
+                visitor.visit_expression(value)?;
+            }
+            ControlFlow::Continue(())
+        }
+        StatementKind::Assign { target, value, .. } => {
+            visitor.visit_expression(target)?;
+            visitor.visit_expression(value)
+        }
+        StatementKind::If { branches, otherwise } => {
+            for (cond, body) in branches {
+                visitor.visit_expression(cond)?;
+                walk_block_mut(visitor, body)?;
+            }
+            if let Some(otherwise) = otherwise {
+                walk_block_mut(visitor, otherwise)?;
+            }
+            ControlFlow::Continue(())
+        }
+        StatementKind::For { iterable, body, .. } => {
+            visitor.visit_expression(iterable)?;
+            walk_block_mut(visitor, body)
+        }
+        StatementKind::While { cond, body, .. } => {
+            visitor.visit_expression(cond)?;
+            walk_block_mut(visitor, body)
+        }
+        StatementKind::IfLet {
+            value,
+            body,
+            otherwise,
+            ..
+        } => {
+            visitor.visit_expression(value)?;
+            walk_block_mut(visitor, body)?;
+            if let Some(otherwise) = otherwise {
+                walk_block_mut(visitor, otherwise)?;
+            }
+            ControlFlow::Continue(())
+        }
+        StatementKind::WhileLet { value, body, .. } => {
+            visitor.visit_expression(value)?;
+            walk_block_mut(visitor, body)
+        }
+        StatementKind::DoWhile { body, cond } => {
+            walk_block_mut(visitor, body)?;
+            visitor.visit_expression(cond)
+        }
+        StatementKind::Try { body, catches } => {
+            walk_block_mut(visitor, body)?;
+            for arm in catches {
+                walk_block_mut(visitor, &mut arm.body)?;
+            }
+            ControlFlow::Continue(())
+        }
+        StatementKind::Return(None)
+
+        | StatementKind::Continue(_)
+        | StatementKind::Module(_)
+        | StatementKind::Import { .. }
+        | StatementKind::SelectiveImport { .. }
+        | StatementKind::Include(_)
+        | StatementKind::Export(_)
+        | StatementKind::SelectiveExport { .. }
+        | StatementKind::Enum { .. }
+        | StatementKind::Struct { .. }
+        | StatementKind::Error => ControlFlow::Continue(()),
+    }
+}
+
+/// Recurses into every child node of `expr`, in source order.
+pub fn walk_expression_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    expr: &mut Expr,
+) -> ControlFlow<()> {
+    match &mut expr.kind {
+        ExprKind::Literal(value) => visitor.visit_value(value),
+        ExprKind::Unary(_, inner)
+        | ExprKind::TypeOf(inner)
+        | ExprKind::Try(inner)
+        | ExprKind::PostIncrement(inner)
+        | ExprKind::PostDecrement(inner)
+        | ExprKind::Spread(inner) => visitor.visit_expression(inner),
+        ExprKind::Binary(_, lhs, rhs) => {
+            visitor.visit_expression(lhs)?;
+            visitor.visit_expression(rhs)
+        }
+        ExprKind::Ternary {
+            cond,
+            then,
+            otherwise,
+        } => {
+            visitor.visit_expression(cond)?;
+            visitor.visit_expression(then)?;
+            visitor.visit_expression(otherwise)
+        }
+        ExprKind::Call { callee, args } => {
+            visitor.visit_expression(callee)?;
+            for arg in args {
+                visitor.visit_expression(arg)?;
+            }
+            ControlFlow::Continue(())
+        }
+        ExprKind::Index { target, index } => {
+            visitor.visit_expression(target)?;
+            visitor.visit_expression(index)
+        }
+        ExprKind::Member { target, .. } | ExprKind::OptionalAccess { target, .. } => {
+            visitor.visit_expression(target)
+        }
+        ExprKind::NullCoalesce(lhs, rhs) => {
+            visitor.visit_expression(lhs)?;
+            visitor.visit_expression(rhs)
+        }
+        ExprKind::Array(elements) | ExprKind::Tuple(elements) | ExprKind::Set(elements) => {
+            for element in elements {
+                visitor.visit_expression(element)?;
+            }
+            ControlFlow::Continue(())
+        }
+        ExprKind::Map(entries) => {
+            for (key, value) in entries {
+                visitor.visit_expression(key)?;
+                visitor.visit_expression(value)?;
+            }
+            ControlFlow::Continue(())
+        }
+        ExprKind::InterpolatedString(parts) => {
+            for part in parts {
+                if let StringPart::Expr(expr) = part {
+                    visitor.visit_expression(expr)?;
+                }
+            }
+            ControlFlow::Continue(())
+        }
+        ExprKind::Compound(fields) => {
+            for (key, value) in fields {
+                if let CompoundKey::Computed(key) = key {
+                    visitor.visit_expression(key)?;
+                }
+                visitor.visit_expression(value)?;
+            }
+            ControlFlow::Continue(())
+        }
+        ExprKind::ArrowFn { params, body, .. } => {
+            for param in params {
+                if let Some(ty) = &mut param.ty {
+                    visitor.visit_expression(ty)?;
+                }
+                if let Some(default) = &mut param.default {
+                    visitor.visit_expression(default)?;
+                }
+            }
+            walk_block_mut(visitor, body)
+        }
+        ExprKind::Match { subject, arms } => {
+            visitor.visit_expression(subject)?;
+            for arm in arms {
+                visitor.visit_expression(&mut arm.body)?;
+            }
+            ControlFlow::Continue(())
+        }
+        ExprKind::Range { start, end, step, .. } => {
+            visitor.visit_expression(start)?;
+            visitor.visit_expression(end)?;
+            if let Some(step) = step {
+                visitor.visit_expression(step)?;
+            }
+            ControlFlow::Continue(())
+        }
+        ExprKind::Loop(stmt) => visitor.visit_statement(stmt),
+        ExprKind::Ident(_) | ExprKind::Path(_) | ExprKind::TypeDef(_) | ExprKind::Garbage => {
+            ControlFlow::Continue(())
+        }
+    }
+}
+
+/// Renders `stmts` as a compact, stable S-expression — one line per
+/// top-level statement — for snapshot tests and grammar-diff debugging,
+/// where `{:#?}` output is far too noisy. The shape is
+/// `(let a (+ 1 (call d)))`-style: a head symbol per node kind, children
+/// in source order.
+pub fn to_sexpr(stmts: &[Statement]) -> String {
+    stmts
+        .iter()
+        .map(statement_sexpr)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn block_sexpr(stmts: &[Statement]) -> String {
+    stmts
+        .iter()
+        .map(statement_sexpr)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn statement_sexpr(stmt: &Statement) -> String {
+    match &stmt.kind {
+        StatementKind::Expr(expr) => expr_sexpr(expr),
+        StatementKind::Let { name, value, .. } => match value {
+            Some(value) => format!("(let {name} {})", expr_sexpr(value)),
+            None => format!("(let {name})"),
+        },
+        StatementKind::LetTuple { names, value } => {
+            format!("(let-tuple ({}) {})", names.join(" "), expr_sexpr(value))
+        }
+        StatementKind::Const { name, value, native, .. } => {
+            let head = if *native { "native-const" } else { "const" };
+            match value {
+                Some(value) => format!("({head} {name} {})", expr_sexpr(value)),
+                None => format!("({head} {name})"),
+            }
+        }
+        StatementKind::Fn {
+            name,
+            params,
+            body,
+            native,
+            ..
+        } => {
+            let head = if *native { "native-fn" } else { "fn" };
+            let params = params
+                .iter()
+                .map(param_sexpr)
+                .collect::<Vec<_>>()
+                .join(" ");
+            format!("({head} {name} ({params}) {})", block_sexpr(body))
+        }
+        StatementKind::Assign { target, op, value } => {
+            let op = match op {
+                Some(op) => format!("{}=", crate::fmt::binop_text(*op)),
+                None => "=".to_owned(),
+            };
+            format!("({op} {} {})", expr_sexpr(target), expr_sexpr(value))
+        }
+        StatementKind::AssignChain { targets, value } => {
+            let targets = targets.iter().map(expr_sexpr).collect::<Vec<_>>().join(" ");
+            format!("(=chain ({targets}) {})", expr_sexpr(value))
+        }
+        StatementKind::AssignParallel { targets, values } => {
+            let targets = targets.iter().map(expr_sexpr).collect::<Vec<_>>().join(" ");
+            let values = values.iter().map(expr_sexpr).collect::<Vec<_>>().join(" ");
+            format!("(=parallel ({targets}) ({values}))")
+        }
+        StatementKind::If { branches, otherwise } => {
+            let mut out = "(if".to_owned();
+            for (cond, body) in branches {
+                out.push_str(&format!(" ({} {})", expr_sexpr(cond), block_sexpr(body)));
+            }
+            if let Some(otherwise) = otherwise {
+                out.push_str(&format!(" (else {})", block_sexpr(otherwise)));
+            }
+            out.push(')');
+            out
+        }
+        StatementKind::For {
+            binding,
+            iterable,
+            body,
+            label,
+        } => {
+            let label = label.as_ref().map(|l| format!("{l}: ")).unwrap_or_default();
+            format!(
+                "(for {label}{binding} {} {})",
+                expr_sexpr(iterable),
+                block_sexpr(body)
+            )
+        }
+        StatementKind::While { cond, body, label } => {
+            let label = label.as_ref().map(|l| format!("{l}: ")).unwrap_or_default();
+            format!("(while {label}{} {})", expr_sexpr(cond), block_sexpr(body))
+        }
+        StatementKind::DoWhile { body, cond } => {
+            format!("(do-while {} {})", block_sexpr(body), expr_sexpr(cond))
+        }
+        StatementKind::IfLet {
+            binding,
+            value,
+            body,
+            otherwise,
+        } => {
+            let mut out = format!(
+                "(if-let {binding} {} {}",
+                expr_sexpr(value),
+                block_sexpr(body)
+            );
+            if let Some(otherwise) = otherwise {
+                out.push(' ');
+                out.push_str(&block_sexpr(otherwise));
+            }
+            out.push(')');
+            out
+        }
+        StatementKind::WhileLet {
+            binding,
+            value,
+            body,
+            label,
+        } => {
+            let label = label.as_ref().map(|l| format!("{l}: ")).unwrap_or_default();
+            format!(
+                "(while-let {label}{binding} {} {})",
+                expr_sexpr(value),
+                block_sexpr(body)
+            )
+        }
+        StatementKind::Return(None) => "(return)".to_owned(),
+        StatementKind::Return(Some(value)) => format!("(return {})", expr_sexpr(value)),
+        StatementKind::Break { label, value } => {
+            let mut out = "(break".to_owned();
+            if let Some(label) = label {
+                out.push(' ');
+                out.push_str(label);
+            }
+            if let Some(value) = value {
+                out.push(' ');
+                out.push_str(&expr_sexpr(value));
+            }
+            out.push(')');
+            out
+        }
+        StatementKind::Continue(None) => "(continue)".to_owned(),
+        StatementKind::Continue(Some(label)) => format!("(continue {label})"),
+        StatementKind::Throw(value) => format!("(throw {})", expr_sexpr(value)),
+        StatementKind::Assert { lhs, rhs, message } => {
+            let mut out = match rhs {
+                Some(rhs) => format!("(assert-eq {} {}", expr_sexpr(lhs), expr_sexpr(rhs)),
+                None => format!("(assert {}", expr_sexpr(lhs)),
+            };
+            if let Some(message) = message {
+                out.push(' ');
+                out.push_str(&expr_sexpr(message));
+            }
+            out.push(')');
+            out
+        }
+        StatementKind::Try { body, catches } => {
+            let mut out = format!("(try {}", block_sexpr(body));
+            for arm in catches {
+                let pattern = match &arm.pattern {
+                    CatchPattern::Type(types) => types
+                        .iter()
+                        .map(|parts| parts.join("."))
+                        .collect::<Vec<_>>()
+                        .join(" | "),
+                    CatchPattern::Any => "*".to_owned(),
+                    CatchPattern::Nil => "?".to_owned(),
+                };
+                let binding = arm.binding.as_deref().unwrap_or("_");
+                out.push_str(&format!(
+                    " (catch {pattern} {binding} {})",
+                    block_sexpr(&arm.body)
+                ));
+            }
+            out.push(')');
+            out
+        }
+        StatementKind::Module(name) => format!("(module {name})"),
+        StatementKind::Import { path, alias } => match alias {
+            Some(alias) => format!("(import {path} as {alias})"),
+            None => format!("(import {path})"),
+        },
+        StatementKind::SelectiveImport { names, path } => {
+            let names = names
+                .iter()
+                .map(|(name, alias)| match alias {
+                    Some(alias) => format!("({name} as {alias})"),
+                    None => name.clone(),
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            format!("(import ({names}) from {path})")
+        }
+        StatementKind::Include(path) => format!("(include {path})"),
+        StatementKind::Export(path) => format!("(export {path})"),
+        StatementKind::SelectiveExport { names, from } => {
+            let names = names
+                .iter()
+                .map(|(name, alias)| match alias {
+                    Some(alias) => format!("({name} {alias})"),
+                    None => name.clone(),
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            match from {
+                Some(from) => format!("(export-from {from} {names})"),
+                None => format!("(export {names})"),
+            }
+        }
+        StatementKind::Enum { name, variants } => {
+            let variants = variants
+                .iter()
+                .map(|variant| {
+                    if variant.fields.is_empty() {
+                        variant.name.clone()
+                    } else {
+                        format!("({} {})", variant.name, variant.fields.join(" "))
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            format!("(enum {name} {variants})")
+        }
+        StatementKind::Struct { name, fields, .. } => {
+            let fields = fields
+                .iter()
+                .map(|(field, ty)| format!("({field} {ty})"))
+                .collect::<Vec<_>>()
+                .join(" ");
+            format!("(struct {name} {fields})")
+        }
+        StatementKind::Error => "(error)".to_owned(),
+    }
+}
+
+fn param_sexpr(param: &Param) -> String {
+    let name = if param.variadic {
+        format!("...{}", param.name)
+    } else {
+        param.name.clone()
+    };
+    match &param.default {
+        Some(default) => format!("(= {name} {})", expr_sexpr(default)),
+        None => name,
+    }
+}
+
+fn expr_sexpr(expr: &Expr) -> String {
+    match &expr.kind {
+        ExprKind::Literal(lit) => literal_sexpr(lit),
+        ExprKind::Ident(name) => name.clone(),
+        ExprKind::Path(parts) => format!("(path {})", parts.join(".")),
+        ExprKind::Unary(op, inner) => {
+            let op = match op {
+                UnaryOp::Not => "!",
+                UnaryOp::Neg => "neg",
+                UnaryOp::BitNot => "~",
+                UnaryOp::Unwrap => "!!",
+            };
+            format!("({op} {})", expr_sexpr(inner))
+        }
+        ExprKind::Binary(op, lhs, rhs) => format!(
+            "({} {} {})",
+            crate::fmt::binop_text(*op),
+            expr_sexpr(lhs),
+            expr_sexpr(rhs)
+        ),
+        ExprKind::Ternary {
+            cond,
+            then,
+            otherwise,
+        } => format!(
+            "(if? {} {} {})",
+            expr_sexpr(cond),
+            expr_sexpr(then),
+            expr_sexpr(otherwise)
+        ),
+        ExprKind::NullCoalesce(lhs, rhs) => {
+            format!("(?? {} {})", expr_sexpr(lhs), expr_sexpr(rhs))
+        }
+        ExprKind::Call { callee, args } => {
+            let mut out = format!("(call {}", expr_sexpr(callee));
+            for arg in args {
+                out.push(' ');
+                out.push_str(&expr_sexpr(arg));
+            }
+            out.push(')');
+            out
+        }
+        ExprKind::Index { target, index } => {
+            format!("(index {} {})", expr_sexpr(target), expr_sexpr(index))
+        }
+        ExprKind::Member { target, name } => {
+            format!("(member {} {name})", expr_sexpr(target))
+        }
+        ExprKind::OptionalAccess { target, name } => {
+            format!("(?. {} {name})", expr_sexpr(target))
+        }
+        ExprKind::Array(elements) => seq_sexpr("array", elements),
+        ExprKind::InterpolatedString(parts) => {
+            let parts = parts
+                .iter()
+                .map(|part| match part {
+                    StringPart::Text(text) => format!("{text:?}"),
+                    StringPart::Expr(expr) => expr_sexpr(expr),
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            format!("(interp {parts})")
+        }
+        ExprKind::Tuple(elements) => seq_sexpr("tuple", elements),
+        ExprKind::Set(elements) => seq_sexpr("set", elements),
+        ExprKind::Map(entries) => {
+            let entries = entries
+                .iter()
+                .map(|(key, value)| format!("({} {})", expr_sexpr(key), expr_sexpr(value)))
+                .collect::<Vec<_>>()
+                .join(" ");
+            format!("(map {entries})")
+        }
+        ExprKind::Compound(fields) => {
+            let fields = fields
+                .iter()
+                .map(|(key, value)| match key {
+                    CompoundKey::Static(name) => format!("({name} {})", expr_sexpr(value)),
+                    CompoundKey::Computed(key) => {
+                        format!("([{}] {})", expr_sexpr(key), expr_sexpr(value))
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            format!("(compound {fields})")
+        }
+        ExprKind::TypeDef(fields) => {
+            let fields = fields
+                .iter()
+                .map(|(name, ty)| format!("({name} {ty})"))
+                .collect::<Vec<_>>()
+                .join(" ");
+            format!("(typedef {fields})")
+        }
+        ExprKind::ArrowFn { params, body, .. } => {
+            let params = params
+                .iter()
+                .map(param_sexpr)
+                .collect::<Vec<_>>()
+                .join(" ");
+            format!("(arrow ({params}) {})", block_sexpr(body))
+        }
+        ExprKind::Match { subject, arms } => {
+            let mut out = format!("(match {}", expr_sexpr(subject));
+            for arm in arms {
+                out.push_str(&format!(
+                    " ({} {})",
+                    pattern_sexpr(&arm.pattern),
+                    expr_sexpr(&arm.body)
+                ));
+            }
+            out.push(')');
+            out
+        }
+        ExprKind::Range {
+            start,
+            end,
+            inclusive,
+            step,
+        } => {
+            let op = if *inclusive { "..=" } else { ".." };
+            let step = step
+                .as_ref()
+                .map(|step| format!(" step {}", expr_sexpr(step)))
+                .unwrap_or_default();
+            format!("({op} {} {}{step})", expr_sexpr(start), expr_sexpr(end))
+        }
+        ExprKind::TypeOf(inner) => format!("(typeof {})", expr_sexpr(inner)),
+        ExprKind::Try(inner) => format!("(try {})", expr_sexpr(inner)),
+        ExprKind::PostIncrement(inner) => format!("(post-inc {})", expr_sexpr(inner)),
+        ExprKind::PostDecrement(inner) => format!("(post-dec {})", expr_sexpr(inner)),
+        ExprKind::Spread(inner) => format!("(spread {})", expr_sexpr(inner)),
+        ExprKind::Loop(stmt) => format!("(loop {})", statement_sexpr(stmt)),
+        ExprKind::Garbage => "(garbage)".to_owned(),
+    }
+}
+
+fn seq_sexpr(head: &str, elements: &[Expr]) -> String {
+    let mut out = format!("({head}");
+    for element in elements {
+        out.push(' ');
+        out.push_str(&expr_sexpr(element));
+    }
+    out.push(')');
+    out
+}
+
+fn literal_sexpr(lit: &Literal) -> String {
+    match lit {
+        Literal::Int(n, _) => n.to_string(),
+        Literal::Float(n) => format!("{n:?}"),
+        Literal::Str(s) => s.clone(),
+        Literal::Bool(b) => b.to_string(),
+        Literal::Nil => "nil".to_owned(),
+        Literal::Regex { pattern, flags } => format!("/{pattern}/{flags}"),
+    }
+}
+
+fn pattern_sexpr(pattern: &Pattern) -> String {
+    match pattern {
+        Pattern::Wildcard => "_".to_owned(),
+        Pattern::Literal(lit) => literal_sexpr(lit),
+        Pattern::Binding(name) => name.clone(),
+        Pattern::Array { elements, rest } => {
+            let mut parts: Vec<String> = elements.iter().map(pattern_sexpr).collect();
+            if let Some(rest) = rest {
+                parts.push(format!("...{rest}"));
+            }
+            format!("(pat-array {})", parts.join(" "))
+        }
+        Pattern::Compound { fields, open } => {
+            let mut parts: Vec<String> = fields
+                .iter()
+                .map(|(name, binding)| format!("({name} {})", pattern_sexpr(binding)))
+                .collect();
+            if *open {
+                parts.push("...".to_owned());
+            }
+            format!("(pat-compound {})", parts.join(" "))
+        }
+    }
+}
+
+/// One `catch <pattern> as <name> { .. }` arm of a `try` statement.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CatchArm {
+    pub pattern: CatchPattern,
+    pub binding: Option<String>,
+    pub body: Vec<Statement>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum CatchPattern {
+    /// `catch std.io.IoError as io`, or several alternatives in one
+    /// clause — `catch IoError | ParseError as e`. Each entry is one
+    /// dotted error-type path.
+    Type(Vec<Vec<String>>),
+    /// `catch * as error`
+    Any,
+    /// `catch?`, catches only nil-throws.
+    Nil,
+}
+
+/// Serializes a statement tree as JSON for external consumers — web
+/// editors, visualizers — that can't read the compact binary format.
+/// Every enum node carries an explicit `"kind"` tag with its payload
+/// under `"value"`, and every statement/expression keeps its `area`
+/// (source identity plus byte span), so the JSON alone can drive
+/// highlighting in a browser. [`from_json`] reverses the encoding to an
+/// identical in-memory tree.
+#[cfg(feature = "json")]
+pub fn to_json(stmts: &[Statement]) -> serde_json::Value {
+    let value = serde_json::to_value(stmts).expect("the AST always serializes");
+    tag_kinds(value)
+}
+
+/// Rebuilds a statement tree from [`to_json`] output. Errors surface as
+/// `serde_json`'s own, pointing at whatever part of the value didn't
+/// match the schema.
+#[cfg(feature = "json")]
+pub fn from_json(value: &serde_json::Value) -> Result<Vec<Statement>, serde_json::Error> {
+    serde_json::from_value(untag_kinds(value.clone()))
+}
+
+/// Rewrites serde's externally-tagged enum encoding (`{"Let": {..}}`)
+/// into the `{"kind": "Let", "value": {..}}` form [`to_json`] promises.
+/// Single-key objects only ever arise from enum variants here: none of
+/// the AST's structs has exactly one field, and the tree contains no
+/// maps.
+#[cfg(feature = "json")]
+fn tag_kinds(value: serde_json::Value) -> serde_json::Value {
+    use serde_json::Value;
+    match value {
+        Value::Object(map) if map.len() == 1 => {
+            let (kind, inner) = map.into_iter().next().expect("len checked above");
+            serde_json::json!({ "kind": kind, "value": tag_kinds(inner) })
+        }
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, value)| (key, tag_kinds(value)))
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.into_iter().map(tag_kinds).collect()),
+        other => other,
+    }
+}
+
+/// Exact inverse of [`tag_kinds`].
+#[cfg(feature = "json")]
+fn untag_kinds(value: serde_json::Value) -> serde_json::Value {
+    use serde_json::Value;
+    match value {
+        Value::Object(map)
+            if map.len() == 2 && map.contains_key("kind") && map.contains_key("value") =>
+        {
+            let kind = map["kind"]
+                .as_str()
+                .unwrap_or_default()
+                .to_owned();
+            let inner = untag_kinds(map["value"].clone());
+            let mut tagged = serde_json::Map::new();
+            tagged.insert(kind, inner);
+            Value::Object(tagged)
+        }
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, value)| (key, untag_kinds(value)))
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.into_iter().map(untag_kinds).collect()),
+        other => other,
+    }
+}
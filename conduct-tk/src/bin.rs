@@ -0,0 +1,434 @@
+//! Serializes a parsed statement tree to/from Conduct's `.cdt` binary
+//! format, so a `compile` step can cache an AST instead of reparsing.
+//!
+//! Layout: 4 magic bytes, a `u16` version, a one-byte compression flag,
+//! the (possibly gzipped) bincode payload, and a trailing CRC32 of the
+//! payload as stored.
+
+use crate::ast::Statement;
+
+/// The first four bytes of every `.cdt` file; anything else is not a
+/// compiled Conduct binary (or got corrupted in transit).
+const MAGIC: [u8; 4] = *b"CDT\0";
+
+/// Bumped whenever the on-disk layout changes; `from_binary` refuses to
+/// load a file stamped with any other version rather than guessing.
+const FORMAT_VERSION: u16 = 3;
+
+/// Bits of the header's flag byte. `FLAG_GZIP` marks a compressed
+/// payload; `FLAG_DEBUG` marks a source-map side-table appended after
+/// the statements (see [`to_binary_with_map`]).
+const FLAG_RAW: u8 = 0;
+const FLAG_GZIP: u8 = 1;
+const FLAG_DEBUG: u8 = 2;
+
+/// Bytes of header (`MAGIC` + the little-endian version + the
+/// compression flag) before the payload starts.
+const HEADER_LEN: usize = MAGIC.len() + 3;
+
+/// Bytes of footer: the little-endian CRC32 of the payload.
+const FOOTER_LEN: usize = 4;
+
+#[derive(Debug)]
+pub enum BinError {
+    Encode(bincode::Error),
+    Decode(bincode::Error),
+    /// The input is shorter than the `.cdt` header itself.
+    Truncated,
+    /// The magic bytes are wrong: not a `.cdt` file, or a corrupted one.
+    BadMagic,
+    VersionMismatch { found: u16 },
+    /// The header's compression flag isn't one this build understands —
+    /// either a corrupted file or a payload encoding we can't decode
+    /// (gzip, in a build without the `flate2` feature).
+    UnsupportedFlags { found: u8 },
+    /// The payload doesn't hash to the stored CRC32: truncation or
+    /// bit-rot somewhere between the header and the footer.
+    ChecksumMismatch,
+    /// A collection inside the payload declares more bytes than the
+    /// payload holds — an adversarial or corrupted length prefix that
+    /// would otherwise trigger a huge allocation.
+    LengthOverflow { available: usize },
+}
+
+impl std::fmt::Display for BinError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BinError::Encode(err) => write!(f, "failed to encode binary: {err}"),
+            BinError::Decode(err) => write!(f, "failed to decode binary: {err}"),
+            BinError::Truncated => {
+                write!(f, "truncated .cdt file: shorter than the {HEADER_LEN}-byte header")
+            }
+            BinError::BadMagic => {
+                write!(f, "not a .cdt file: bad magic bytes (corrupted or wrong format?)")
+            }
+            BinError::VersionMismatch { found } => {
+                write!(f, "unsupported .cdt format version {found}, this build reads {FORMAT_VERSION}")
+            }
+            BinError::UnsupportedFlags { found } => {
+                write!(f, "unsupported .cdt compression flag {found:#04x}")
+            }
+            BinError::ChecksumMismatch => {
+                write!(f, "checksum mismatch: .cdt payload is corrupted")
+            }
+            BinError::LengthOverflow { available } => {
+                write!(
+                    f,
+                    "declared length exceeds the {available} payload bytes available"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for BinError {}
+
+pub fn to_binary(statements: Vec<Statement>) -> Result<Vec<u8>, BinError> {
+    let payload = bincode::serialize(&statements).map_err(BinError::Encode)?;
+    Ok(assemble(FLAG_RAW, &payload))
+}
+
+/// Like [`to_binary`], but gzip-compresses the payload; worthwhile for
+/// large modules, where the serialized tree outgrows its source.
+/// [`from_binary`] auto-detects which encoding it's handed via the header
+/// flag, so callers don't need to remember how a file was written.
+#[cfg(feature = "flate2")]
+pub fn to_binary_compressed(statements: Vec<Statement>) -> Result<Vec<u8>, BinError> {
+    use std::io::Write;
+
+    let payload = bincode::serialize(&statements).map_err(BinError::Encode)?;
+    let mut encoder =
+        flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder
+        .write_all(&payload)
+        .and_then(|_| encoder.finish())
+        .map(|compressed| assemble(FLAG_GZIP, &compressed))
+        .map_err(|err| BinError::Encode(Box::new(bincode::ErrorKind::Io(err))))
+}
+
+/// Explicit-name twin of [`from_binary`] for symmetry with
+/// [`to_binary_compressed`]; the flag byte makes decoding self-describing
+/// either way.
+#[cfg(feature = "flate2")]
+pub fn from_binary_compressed(bytes: &[u8]) -> Result<Vec<Statement>, BinError> {
+    from_binary(bytes)
+}
+
+fn assemble(flag: u8, payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(HEADER_LEN + payload.len() + FOOTER_LEN);
+    out.extend_from_slice(&MAGIC);
+    out.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    out.push(flag);
+    out.extend_from_slice(payload);
+    out.extend_from_slice(&crc32(payload).to_le_bytes());
+    out
+}
+
+pub fn from_binary(bytes: &[u8]) -> Result<Vec<Statement>, BinError> {
+    // `deserialize_bounded` allows trailing bytes, so a debug-flagged
+    // file decodes fine here too — the source map just goes unread.
+    let (_, payload) = validated_payload(bytes)?;
+    deserialize_bounded(&payload)
+}
+
+/// The debug side-table of [`to_binary_with_map`]: each top-level
+/// statement's original [`crate::err::CodeArea`], in statement order, so
+/// a runtime can point stack traces back at source.
+pub type SourceMap = Vec<crate::err::CodeArea>;
+
+/// Like [`to_binary`], but appends a [`SourceMap`] side-table after the
+/// statements and sets the debug flag bit. `strip_debug` drops the table
+/// — for release builds — yielding exactly [`to_binary`]'s output.
+pub fn to_binary_with_map(
+    statements: Vec<Statement>,
+    strip_debug: bool,
+) -> Result<Vec<u8>, BinError> {
+    if strip_debug {
+        return to_binary(statements);
+    }
+    let map: SourceMap = statements.iter().map(|stmt| stmt.area.clone()).collect();
+    let mut payload = bincode::serialize(&statements).map_err(BinError::Encode)?;
+    payload.extend(bincode::serialize(&map).map_err(BinError::Encode)?);
+    Ok(assemble(FLAG_DEBUG, &payload))
+}
+
+/// Decodes a `.cdt` along with its [`SourceMap`], when the file carries
+/// one (`None` for maps stripped at write time or files from plain
+/// [`to_binary`]).
+pub fn from_binary_with_map(
+    bytes: &[u8],
+) -> Result<(Vec<Statement>, Option<SourceMap>), BinError> {
+    use bincode::Options;
+
+    let (flag, payload) = validated_payload(bytes)?;
+    if flag & FLAG_DEBUG == 0 {
+        return Ok((deserialize_bounded(&payload)?, None));
+    }
+    guard_declared_length(&payload)?;
+    // The statements and the map sit back to back; deserializing through
+    // a cursor slice leaves `rest` at the map's first byte.
+    let options = bincode::options()
+        .with_fixint_encoding()
+        .allow_trailing_bytes()
+        .with_limit(payload.len() as u64);
+    let mut rest: &[u8] = &payload;
+    let statements: Vec<Statement> = options
+        .deserialize_from(&mut rest)
+        .map_err(|err| decode_error(err, payload.len()))?;
+    let map: SourceMap = options
+        .deserialize_from(&mut rest)
+        .map_err(|err| decode_error(err, payload.len()))?;
+    Ok((statements, Some(map)))
+}
+
+/// Validates magic, version, flag bits, and the CRC32 footer, returning
+/// the flag byte and the (decompressed, version-migrated) payload.
+fn validated_payload(bytes: &[u8]) -> Result<(u8, std::borrow::Cow<'_, [u8]>), BinError> {
+    if bytes.len() < HEADER_LEN + FOOTER_LEN {
+        return Err(BinError::Truncated);
+    }
+    if bytes[..MAGIC.len()] != MAGIC {
+        return Err(BinError::BadMagic);
+    }
+    let found = u16::from_le_bytes([bytes[4], bytes[5]]);
+    if found > FORMAT_VERSION {
+        // Files from the future never load; older ones get a chance on
+        // the migration ladder below.
+        return Err(BinError::VersionMismatch { found });
+    }
+    let flag = bytes[6];
+    if flag & !(FLAG_GZIP | FLAG_DEBUG) != 0 {
+        return Err(BinError::UnsupportedFlags { found: flag });
+    }
+    let (payload, footer) = bytes[HEADER_LEN..].split_at(bytes.len() - HEADER_LEN - FOOTER_LEN);
+    let stored = u32::from_le_bytes(footer.try_into().expect("footer is four bytes"));
+    // Catch corruption up front with a clear error, before bincode trips
+    // over it somewhere deep in a statement.
+    if crc32(payload) != stored {
+        return Err(BinError::ChecksumMismatch);
+    }
+    let mut payload = if flag & FLAG_GZIP != 0 {
+        #[cfg(feature = "flate2")]
+        {
+            use std::io::Read;
+
+            let mut decompressed = Vec::new();
+            flate2::read::GzDecoder::new(payload)
+                .read_to_end(&mut decompressed)
+                .map_err(|err| BinError::Decode(Box::new(bincode::ErrorKind::Io(err))))?;
+            std::borrow::Cow::Owned(decompressed)
+        }
+        #[cfg(not(feature = "flate2"))]
+        return Err(BinError::UnsupportedFlags { found: flag });
+    } else {
+        std::borrow::Cow::Borrowed(payload)
+    };
+    if found < FORMAT_VERSION {
+        payload = std::borrow::Cow::Owned(migrate(payload.into_owned(), found)?);
+    }
+    Ok((flag, payload))
+}
+
+/// One rung of the version-migration ladder: upgrades a payload from its
+/// keyed version to the next one.
+type Migration = fn(Vec<u8>) -> Result<Vec<u8>, BinError>;
+
+/// Supported upgrades, keyed by source version; a file older than the
+/// earliest entry (or missing a rung) can't load. Today's rungs are
+/// pass-throughs — versions 1 and 2 already used the current payload
+/// layout, only the header evolved — but a real layout change gets its
+/// adapter here.
+const MIGRATIONS: &[(u16, Migration)] = &[(1, migrate_passthrough), (2, migrate_passthrough)];
+
+fn migrate_passthrough(payload: Vec<u8>) -> Result<Vec<u8>, BinError> {
+    Ok(payload)
+}
+
+/// Climbs the [`MIGRATIONS`] ladder from `version` up to
+/// [`FORMAT_VERSION`], transforming the payload one rung at a time.
+fn migrate(mut payload: Vec<u8>, mut version: u16) -> Result<Vec<u8>, BinError> {
+    while version < FORMAT_VERSION {
+        let Some((_, upgrade)) = MIGRATIONS.iter().find(|(from, _)| *from == version) else {
+            return Err(BinError::VersionMismatch { found: version });
+        };
+        payload = upgrade(payload)?;
+        version += 1;
+    }
+    Ok(payload)
+}
+
+/// `bincode::deserialize` with the classic wire format (fixint, trailing
+/// bytes allowed) plus a byte limit matching the input, mapping the
+/// limit trip to [`BinError::LengthOverflow`].
+fn deserialize_bounded(payload: &[u8]) -> Result<Vec<Statement>, BinError> {
+    use bincode::Options;
+
+    guard_declared_length(payload)?;
+    let options = bincode::options()
+        .with_fixint_encoding()
+        .allow_trailing_bytes()
+        .with_limit(payload.len() as u64);
+    options
+        .deserialize(payload)
+        .map_err(|err| decode_error(err, payload.len()))
+}
+
+/// The payload opens with the statement count; every statement costs at
+/// least a byte, so a count beyond the payload length is bogus on its
+/// face — reject it before the decoder starts chewing.
+fn guard_declared_length(payload: &[u8]) -> Result<(), BinError> {
+    if payload.len() >= 8 {
+        let declared = u64::from_le_bytes(payload[..8].try_into().expect("eight bytes"));
+        if declared > payload.len() as u64 {
+            return Err(BinError::LengthOverflow {
+                available: payload.len(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Maps a bincode failure to [`BinError`], folding the size-limit trip
+/// into [`BinError::LengthOverflow`].
+fn decode_error(err: bincode::Error, available: usize) -> BinError {
+    match *err {
+        bincode::ErrorKind::SizeLimit => BinError::LengthOverflow { available },
+        _ => BinError::Decode(err),
+    }
+}
+
+/// Decodes a `.cdt` stream one top-level statement at a time, without
+/// buffering the whole payload: the header is validated up front, each
+/// `next()` deserializes a single [`Statement`], and once the count is
+/// exhausted the CRC32 footer is checked against everything read. Only
+/// uncompressed streams are supported — a gzipped payload isn't
+/// statement-addressable without inflating it first.
+pub struct BinaryReader<R: std::io::Read> {
+    reader: Crc32Reader<R>,
+    /// Statements left to decode (bincode's `Vec` length prefix).
+    remaining: u64,
+    /// Whether the footer check has run (or an error already surfaced).
+    finished: bool,
+    current_module: String,
+}
+
+impl<R: std::io::Read> BinaryReader<R> {
+    /// Validates the stream's header and reads the statement count;
+    /// decoding then happens lazily through the [`Iterator`] impl.
+    pub fn new(mut reader: R) -> Result<Self, BinError> {
+        let mut header = [0u8; HEADER_LEN];
+        reader
+            .read_exact(&mut header)
+            .map_err(|_| BinError::Truncated)?;
+        if header[..MAGIC.len()] != MAGIC {
+            return Err(BinError::BadMagic);
+        }
+        let found = u16::from_le_bytes([header[4], header[5]]);
+        if found != FORMAT_VERSION {
+            return Err(BinError::VersionMismatch { found });
+        }
+        if header[6] != FLAG_RAW {
+            return Err(BinError::UnsupportedFlags { found: header[6] });
+        }
+        let mut reader = Crc32Reader { inner: reader, crc: 0xFFFF_FFFF };
+        let mut count = [0u8; 8];
+        std::io::Read::read_exact(&mut reader, &mut count).map_err(|_| BinError::Truncated)?;
+        Ok(Self {
+            reader,
+            remaining: u64::from_le_bytes(count),
+            finished: false,
+            current_module: "main".to_owned(),
+        })
+    }
+
+    fn stream_error(&self, message: String) -> Box<crate::err::ErrorReport> {
+        Box::new(crate::err::ErrorReport {
+            code: crate::err::codes::Code::CorruptBinary.as_str(),
+            severity: crate::err::Severity::Error,
+            call_stack: vec![],
+            notes: Vec::new(),
+            help: None,
+            current_module: self.current_module.clone(),
+            position: crate::err::CodeArea {
+                src: crate::err::CodeSource::Inline(String::new()),
+                span: (0, 0),
+            },
+            message,
+            labels: vec![],
+        })
+    }
+}
+
+impl<R: std::io::Read> Iterator for BinaryReader<R> {
+    type Item = crate::err::Res<Statement>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        if self.remaining > 0 {
+            self.remaining -= 1;
+            return match bincode::deserialize_from(&mut self.reader) {
+                Ok(stmt) => Some(Ok(stmt)),
+                Err(err) => {
+                    self.finished = true;
+                    Some(Err(self.stream_error(format!(
+                        "stream ended mid-statement: {err}"
+                    ))))
+                }
+            };
+        }
+        // Every statement decoded: the next four bytes must be the CRC32
+        // of everything read since the header.
+        self.finished = true;
+        let computed = !self.reader.crc;
+        let mut footer = [0u8; 4];
+        if std::io::Read::read_exact(&mut self.reader.inner, &mut footer).is_err() {
+            return Some(Err(self.stream_error(
+                "stream ended before its checksum footer".to_owned(),
+            )));
+        }
+        if u32::from_le_bytes(footer) != computed {
+            return Some(Err(
+                self.stream_error("checksum mismatch: .cdt payload is corrupted".to_owned())
+            ));
+        }
+        None
+    }
+}
+
+/// A [`std::io::Read`] adapter that CRC32-hashes everything it hands out,
+/// so [`BinaryReader`] can verify the footer after streaming the payload.
+struct Crc32Reader<R> {
+    inner: R,
+    /// Running (pre-inverted) CRC state; finalize with `!crc`.
+    crc: u32,
+}
+
+impl<R: std::io::Read> std::io::Read for Crc32Reader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        for &byte in &buf[..n] {
+            self.crc ^= byte as u32;
+            for _ in 0..8 {
+                let mask = (self.crc & 1).wrapping_neg();
+                self.crc = (self.crc >> 1) ^ (0xEDB8_8320 & mask);
+            }
+        }
+        Ok(n)
+    }
+}
+
+/// CRC-32 (IEEE, reflected) of `bytes`; implemented inline rather than
+/// pulling in a dependency for ten lines of bit-twiddling.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
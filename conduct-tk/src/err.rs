@@ -0,0 +1,667 @@
+//! Diagnostics: where a piece of source came from, spans into it, and the
+//! fancy `ariadne`-rendered reports the CLI prints.
+
+use std::path::PathBuf;
+
+use ariadne::{Cache, Color, Label, Report, ReportKind, Source};
+use serde::{Deserialize, Serialize};
+
+/// Where a [`CodeArea`]'s span should be resolved against.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CodeSource {
+    File(PathBuf),
+    Inline(String),
+    /// One numbered line of an interactive session; reports render it as
+    /// `in:<line>` the way a REPL prompt would.
+    Repl { line: usize },
+}
+
+impl CodeSource {
+    pub fn name(&self) -> String {
+        match self {
+            CodeSource::File(path) => path.display().to_string(),
+            CodeSource::Inline(_) => "<inline>".to_owned(),
+            CodeSource::Repl { line } => format!("in:{line}"),
+        }
+    }
+}
+
+/// A byte span of source, tagged with the [`CodeSource`] it was taken from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CodeArea {
+    pub src: CodeSource,
+    pub span: (usize, usize),
+}
+
+/// Picks a distinct color for each successive label in a report, cycling
+/// through a small fixed palette so adjacent labels never collide.
+#[derive(Default)]
+pub struct FancyColorGenerator {
+    idx: usize,
+}
+
+impl FancyColorGenerator {
+    const PALETTE: [Color; 6] = [
+        Color::Red,
+        Color::Yellow,
+        Color::Green,
+        Color::Cyan,
+        Color::Magenta,
+        Color::Blue,
+    ];
+
+    pub fn next_color(&mut self) -> Color {
+        let color = Self::PALETTE[self.idx % Self::PALETTE.len()];
+        self.idx += 1;
+        color
+    }
+}
+
+/// How serious a diagnostic is; decides the `ariadne` report kind and
+/// whether suppression comments apply (only warnings and infos can be
+/// silenced, hard errors always surface).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+/// One frame of a cross-module trace: where the jump happened, which
+/// module it was in, and a short description of why ("while importing",
+/// "in macro expansion").
+#[derive(Debug, Clone)]
+pub struct StackFrame {
+    pub area: CodeArea,
+    pub module: String,
+    pub context: String,
+}
+
+impl StackFrame {
+    pub fn new(area: CodeArea, module: impl Into<String>, context: impl Into<String>) -> Self {
+        Self {
+            area,
+            module: module.into(),
+            context: context.into(),
+        }
+    }
+}
+
+/// Migration shim for callers still holding bare areas: the module name
+/// comes off the area's source and the context stays generic.
+impl From<CodeArea> for StackFrame {
+    fn from(area: CodeArea) -> Self {
+        let module = area.src.name();
+        Self {
+            area,
+            module,
+            context: "while evaluating".to_owned(),
+        }
+    }
+}
+
+/// One fully-formed diagnostic: a message, the call stack of `import`s that
+/// led here, and the labeled spans that explain it.
+#[derive(Debug, Clone)]
+pub struct ErrorReport {
+    pub code: &'static str,
+    pub severity: Severity,
+    pub call_stack: Vec<StackFrame>,
+    pub current_module: String,
+    pub position: CodeArea,
+    pub message: String,
+    pub labels: Vec<(CodeArea, String)>,
+    /// Footer note lines, rendered after the labels.
+    pub notes: Vec<String>,
+    /// A `help:` footer suggesting the fix, when there's one to suggest.
+    pub help: Option<String>,
+}
+
+impl ErrorReport {
+    /// Appends a footer note line; chainable, like the ariadne builder
+    /// it feeds.
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    /// Sets the `help:` footer suggesting how to fix the problem.
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+
+    /// Builds the `ariadne::Report` for this diagnostic, ready to `.print()`.
+    pub fn report(&self) -> Report<'static, (String, std::ops::Range<usize>)> {
+        let kind = match self.severity {
+            Severity::Error => ReportKind::Error,
+            Severity::Warning => ReportKind::Warning,
+            Severity::Info => ReportKind::Advice,
+        };
+        let name = self.position.src.name();
+        let mut builder = Report::build(kind, name.clone(), self.position.span.0)
+            .with_code(self.code)
+            .with_message(&self.message);
+
+        for (area, label) in &self.labels {
+            builder = builder.with_label(
+                Label::new((area.src.name(), area.span.0..area.span.1)).with_message(label),
+            );
+        }
+        // Each trace frame renders as its own labeled line, innermost
+        // first, so a cross-module failure reads like a stack trace.
+        for frame in &self.call_stack {
+            builder = builder.with_label(
+                Label::new((frame.area.src.name(), frame.area.span.0..frame.area.span.1))
+                    .with_message(format!("{}, in module `{}`", frame.context, frame.module)),
+            );
+        }
+        if !self.notes.is_empty() {
+            // ariadne renders a single note footer; stacked notes share it.
+            builder = builder.with_note(self.notes.join("\n"));
+        }
+        if let Some(help) = &self.help {
+            builder = builder.with_help(help);
+        }
+
+        builder.finish()
+    }
+}
+
+#[cfg(feature = "json")]
+impl ErrorReport {
+    /// Serializes this diagnostic as a machine-readable JSON object, for
+    /// editor integrations that can't consume the `ariadne` rendering.
+    /// Spans are `[start, end)` byte offsets; sources tag themselves as
+    /// `file` (with a path) or `inline`.
+    pub fn to_json(&self) -> serde_json::Value {
+        fn source(src: &CodeSource) -> serde_json::Value {
+            match src {
+                CodeSource::File(path) => serde_json::json!({
+                    "type": "file",
+                    "path": path.display().to_string(),
+                }),
+                CodeSource::Inline(_) => serde_json::json!({ "type": "inline" }),
+                CodeSource::Repl { line } => serde_json::json!({
+                    "type": "repl",
+                    "line": line,
+                }),
+            }
+        }
+        fn area(area: &CodeArea) -> serde_json::Value {
+            serde_json::json!({
+                "source": source(&area.src),
+                "span": [area.span.0, area.span.1],
+            })
+        }
+
+        serde_json::json!({
+            "code": self.code,
+            "severity": match self.severity {
+                Severity::Error => "error",
+                Severity::Warning => "warning",
+                Severity::Info => "info",
+            },
+            "message": self.message,
+            "module": self.current_module,
+            "position": area(&self.position),
+            "call_stack": self
+                .call_stack
+                .iter()
+                .map(|frame| {
+                    serde_json::json!({
+                        "area": area(&frame.area),
+                        "module": frame.module,
+                        "context": frame.context,
+                    })
+                })
+                .collect::<Vec<_>>(),
+            "labels": self
+                .labels
+                .iter()
+                .map(|(at, text)| {
+                    serde_json::json!({
+                        "source": source(&at.src),
+                        "span": [at.span.0, at.span.1],
+                        "text": text,
+                    })
+                })
+                .collect::<Vec<_>>(),
+        })
+    }
+}
+
+/// A zero-based position the way LSP counts: lines split on `\n` (which
+/// covers CRLF too) and characters in UTF-16 code units.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LspPosition {
+    pub line: u32,
+    pub character: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LspRange {
+    pub start: LspPosition,
+    pub end: LspPosition,
+}
+
+/// An `ErrorReport` reshaped the way an LSP `Diagnostic` wants it:
+/// line/character range instead of byte span, plus the fields an editor
+/// plugin forwards verbatim.
+#[derive(Debug, Clone)]
+pub struct LspDiagnostic {
+    pub range: LspRange,
+    pub severity: Severity,
+    pub code: String,
+    pub message: String,
+    /// The originating source's display name (path, `<inline>`, `in:N`).
+    pub source_name: String,
+}
+
+impl ErrorReport {
+    /// Converts this report into an LSP-shaped diagnostic against
+    /// `source` (the text the report's byte spans index into).
+    pub fn to_lsp(&self, source: &str) -> LspDiagnostic {
+        LspDiagnostic {
+            range: LspRange {
+                start: lsp_position(source, self.position.span.0),
+                end: lsp_position(source, self.position.span.1),
+            },
+            severity: self.severity,
+            code: self.code.to_owned(),
+            message: self.message.clone(),
+            source_name: self.position.src.name(),
+        }
+    }
+}
+
+impl ErrorReport {
+    /// Formats this report as a grep-style one-liner,
+    /// `file:line:col: severity[code]: message`, for batch tooling that
+    /// wants one row per diagnostic instead of the full `ariadne`
+    /// rendering. `source` is the text the report's byte spans index
+    /// into; line and column are one-based, and the column counts
+    /// characters rather than bytes or UTF-16 units.
+    pub fn summary(&self, source: &str) -> String {
+        let offset = self.position.span.0.min(source.len());
+        let before = &source[..offset];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map(|at| at + 1).unwrap_or(0);
+        let column = source[line_start..offset].chars().count() + 1;
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Info => "info",
+        };
+        format!(
+            "{}:{line}:{column}: {severity}[{}]: {}",
+            self.position.src.name(),
+            self.code,
+            self.message
+        )
+    }
+}
+
+/// Maps a byte offset into `source` to an LSP position: zero-based line
+/// plus the UTF-16 code-unit column (an emoji before the span widens the
+/// column by two, the way editors expect).
+fn lsp_position(source: &str, offset: usize) -> LspPosition {
+    let offset = offset.min(source.len());
+    let before = &source[..offset];
+    let line = before.matches('\n').count();
+    let line_start = before.rfind('\n').map(|at| at + 1).unwrap_or(0);
+    let character: usize = source[line_start..offset]
+        .chars()
+        .map(char::len_utf16)
+        .sum();
+    LspPosition {
+        line: line as u32,
+        character: character as u32,
+    }
+}
+
+/// The central registry of diagnostic codes. Every [`ErrorReport`] the
+/// toolkit emits names one of these; the rendered form (`E01`, `W04`) is
+/// stable — codes are never renumbered or reused, only added — so
+/// external tooling may match on it.
+pub mod codes {
+    /// One diagnostic code, by name. `E` codes are hard errors; `W` codes
+    /// are warnings, some of which a `// conduct:ignore <rule>` comment
+    /// can silence (see [`Code::rule_name`]).
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Code {
+        /// `E01`: the parser hit input its grammar can't accept.
+        SyntaxError,
+        /// `E02`: a source file couldn't be read.
+        UnreadableFile,
+        /// `E03`: a binary `.cdt` stream ended or corrupted mid-statement.
+        CorruptBinary,
+        /// `E10`: a semantic rule was broken — undefined symbols, const
+        /// reassignment, type mismatches, and the validator's other
+        /// hard errors.
+        SemanticError,
+        /// `E12`: constant folding met a literal division (or modulo)
+        /// by zero.
+        DivisionByZero,
+        /// `E14`: the import graph contains a cycle.
+        ImportCycle,
+        /// `E15`: `const` initializers reference each other out of order.
+        ConstDependency,
+        /// `W01`: an import is never referenced.
+        UnusedImport,
+        /// `W02`: code after an unconditional block exit can never run.
+        UnreachableCode,
+        /// `W03`: a binding shadows one from an enclosing scope.
+        Shadowing,
+        /// `W04`: a binding is never read.
+        UnusedVariable,
+        /// `W05`: an annotation name the validator doesn't know.
+        UnknownAnnotation,
+        /// `W06`: a function parameter is reassigned inside its body.
+        ParameterReassigned,
+        /// `W07`: `?` error propagation at the top level, with no caller
+        /// to rethrow to.
+        TopLevelPropagation,
+        /// `W08`: an `if`/`while`/ternary condition folds to a constant.
+        ConstantCondition,
+        /// `E99`: reserved for tests and internal tooling fixtures.
+        Internal,
+    }
+
+    /// Every registered code, for exhaustive sweeps (collision tests,
+    /// documentation generators).
+    pub const ALL: [Code; 16] = [
+        Code::SyntaxError,
+        Code::UnreadableFile,
+        Code::CorruptBinary,
+        Code::SemanticError,
+        Code::DivisionByZero,
+        Code::ImportCycle,
+        Code::ConstDependency,
+        Code::UnusedImport,
+        Code::UnreachableCode,
+        Code::Shadowing,
+        Code::UnusedVariable,
+        Code::UnknownAnnotation,
+        Code::ParameterReassigned,
+        Code::TopLevelPropagation,
+        Code::ConstantCondition,
+        Code::Internal,
+    ];
+
+    impl Code {
+        /// The stable rendered form, e.g. `"E01"` — what
+        /// [`super::ErrorReport::code`] carries.
+        pub const fn as_str(self) -> &'static str {
+            match self {
+                Code::SyntaxError => "E01",
+                Code::UnreadableFile => "E02",
+                Code::CorruptBinary => "E03",
+                Code::SemanticError => "E10",
+                Code::DivisionByZero => "E12",
+                Code::ImportCycle => "E14",
+                Code::ConstDependency => "E15",
+                Code::UnusedImport => "W01",
+                Code::UnreachableCode => "W02",
+                Code::Shadowing => "W03",
+                Code::UnusedVariable => "W04",
+                Code::UnknownAnnotation => "W05",
+                Code::ParameterReassigned => "W06",
+                Code::TopLevelPropagation => "W07",
+                Code::ConstantCondition => "W08",
+                Code::Internal => "E99",
+            }
+        }
+
+        /// The long-form explanation behind an eventual
+        /// `conduct explain <code>`.
+        pub const fn explanation(self) -> &'static str {
+            match self {
+                Code::SyntaxError => {
+                    "The parser could not make sense of the input at the reported \
+                     position. The report's labels point at the token that broke the \
+                     grammar; parsing recovers at the next statement boundary, so one \
+                     run surfaces every syntax error in a file."
+                }
+                Code::UnreadableFile => {
+                    "A source file could not be opened or read. The message carries \
+                     the operating system's reason (missing file, permissions, ...)."
+                }
+                Code::CorruptBinary => {
+                    "A compiled .cdt stream ended mid-statement or failed to decode. \
+                     The file was likely truncated, or produced by an incompatible \
+                     version of the compiler."
+                }
+                Code::SemanticError => {
+                    "The code parses but breaks a semantic rule: an undefined symbol, \
+                     reassigning a const, a type mismatch between two known types, a \
+                     break outside a loop, and similar. The labels point at both the \
+                     offending use and the definition it conflicts with."
+                }
+                Code::DivisionByZero => {
+                    "Constant folding found a division or modulo whose operands are \
+                     literals and whose divisor is zero. The expression is left \
+                     unfolded and would fail at runtime."
+                }
+                Code::ImportCycle => {
+                    "Following imports from the entry file leads back to a module \
+                     already being imported. The message spells out the cycle; break \
+                     it by extracting the shared pieces into a third module."
+                }
+                Code::ConstDependency => {
+                    "Const initializers must be evaluable top to bottom: a const may \
+                     only read consts declared above it, never itself or one declared \
+                     further down, and mutual references form an unevaluable cycle."
+                }
+                Code::UnusedImport => {
+                    "An import is never referenced. Remove it, or silence the warning \
+                     with `// conduct:ignore unused-import` if it's load-bearing for \
+                     side effects."
+                }
+                Code::UnreachableCode => {
+                    "A statement follows a return/throw/break/continue that always \
+                     exits the block first, so it can never run. Only the first dead \
+                     statement in a block is reported."
+                }
+                Code::Shadowing => {
+                    "A binding reuses a name from an enclosing scope; the inner one \
+                     wins until its scope ends. Rename one of them, or silence with \
+                     `// conduct:ignore shadow` where the shadowing is deliberate."
+                }
+                Code::UnusedVariable => {
+                    "A `let` binding is never read. Delete it, or prefix the name \
+                     with `_` to mark it intentionally unused."
+                }
+                Code::UnknownAnnotation => {
+                    "An `@annotation` name the validator wasn't configured to accept; \
+                     likely a typo. Known names are configurable via \
+                     `Validator::known_annotations`."
+                }
+                Code::ParameterReassigned => {
+                    "A function parameter is reassigned inside its body, which often \
+                     hides the caller's value. Opt-in via \
+                     `Validator::check_param_reassign`."
+                }
+                Code::TopLevelPropagation => {
+                    "The `?` propagation operator rethrows to the caller, but at the \
+                     top level there is no caller: a failure becomes an unhandled \
+                     throw. Wrap the call in try/catch instead."
+                }
+                Code::ConstantCondition => {
+                    "The condition folds to a constant true or false, so the branch \
+                     decision is made before the program ever runs — usually a bug, \
+                     occasionally a deliberate guard. Opt-in via \
+                     `Validator::check_const_conditions`."
+                }
+                Code::Internal => {
+                    "Reserved for tests and internal tooling; user code should never \
+                     see this."
+                }
+            }
+        }
+
+        /// The `// conduct:ignore <rule>` name that silences this
+        /// warning, for the codes that have one.
+        pub const fn rule_name(self) -> Option<&'static str> {
+            match self {
+                Code::UnusedImport => Some("unused-import"),
+                Code::UnreachableCode => Some("unreachable"),
+                Code::Shadowing => Some("shadow"),
+                Code::UnusedVariable => Some("unused-var"),
+                _ => None,
+            }
+        }
+    }
+
+    /// Looks a code up by its rendered form (`"E01"`, `"W04"`), for
+    /// `conduct explain E01` style tooling.
+    pub fn lookup(code: &str) -> Option<Code> {
+        ALL.into_iter().find(|c| c.as_str() == code)
+    }
+}
+
+/// An [`ariadne::Cache`] that resolves [`CodeArea`] sources on demand.
+/// Stateless, so copies are free — [`Errors::report_all`] clones one per
+/// report.
+#[derive(Clone, Copy, Default)]
+pub struct ConductCache;
+
+impl Cache<String> for ConductCache {
+    type Storage = String;
+
+    fn fetch(&mut self, id: &String) -> Result<&Source<String>, Box<dyn std::fmt::Debug + '_>> {
+        // Sources are re-read lazily; callers that need repeated lookups
+        // should prefer caching at a higher level (e.g. the LSP session).
+        let contents = if id == "<inline>" || id.starts_with("in:") {
+            // Inline and REPL sources carry no file to re-read.
+            String::new()
+        } else {
+            std::fs::read_to_string(id).unwrap_or_default()
+        };
+        let leaked: &'static str = Box::leak(contents.into_boxed_str());
+        Ok(Box::leak(Box::new(Source::from(leaked.to_owned()))))
+    }
+
+    fn display<'a>(&self, id: &'a String) -> Option<Box<dyn std::fmt::Display + 'a>> {
+        Some(Box::new(id.clone()))
+    }
+}
+
+/// An accumulator for passes that keep going after the first problem:
+/// diagnostics `push`/`extend` in as the walk finds them, and the pass
+/// settles up once at the end via [`Errors::into_result`]. The `?`-able
+/// counterpart to [`Res`]'s single-error shape.
+#[derive(Debug, Default)]
+pub struct Errors {
+    reports: Vec<ErrorReport>,
+}
+
+impl Errors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, report: ErrorReport) {
+        self.reports.push(report);
+    }
+
+    pub fn extend(&mut self, reports: impl IntoIterator<Item = ErrorReport>) {
+        self.reports.extend(reports);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.reports.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.reports.len()
+    }
+
+    /// Keeps only the reports `keep` approves — how suppression comments
+    /// drop their warnings after the fact.
+    pub fn retain(&mut self, keep: impl FnMut(&ErrorReport) -> bool) {
+        self.reports.retain(keep);
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut ErrorReport> {
+        self.reports.iter_mut()
+    }
+
+    /// `Ok(())` when nothing was collected, otherwise `Err` carrying the
+    /// whole batch — the `?`-friendly settle-up at the end of a pass.
+    pub fn into_result(self) -> Result<(), Vec<ErrorReport>> {
+        if self.reports.is_empty() {
+            Ok(())
+        } else {
+            Err(self.reports)
+        }
+    }
+
+    /// The collected reports, empty or not.
+    pub fn into_reports(self) -> Vec<ErrorReport> {
+        self.reports
+    }
+
+    /// Prints every collected report through `cache`.
+    pub fn report_all<C: Cache<String> + Clone>(&self, cache: C) {
+        for report in &self.reports {
+            let _ = report.report().print(cache.clone());
+        }
+    }
+}
+
+/// The result type threaded through the whole pipeline: parsing, validation,
+/// and the optimizer all bottom out in an [`ErrorReport`] on failure.
+///
+/// Boxed because `ErrorReport` is large (it carries a `call_stack` and a
+/// `labels` vec of its own) and most `Res<T>` values on the happy path never
+/// touch the error branch at all.
+pub type Res<T> = Result<T, Box<ErrorReport>>;
+
+/// Something [`check!`] can print and panic with: either a single
+/// [`ErrorReport`] or the batch a [`crate::parser::ParserPipeline`] returns.
+pub trait Reportable {
+    fn print_all(&self);
+    fn panic_message(&self) -> String;
+}
+
+impl Reportable for ErrorReport {
+    fn print_all(&self) {
+        let _ = self.report().print(ConductCache);
+    }
+
+    fn panic_message(&self) -> String {
+        self.message.clone()
+    }
+}
+
+impl Reportable for Vec<ErrorReport> {
+    fn print_all(&self) {
+        for report in self {
+            report.print_all();
+        }
+    }
+
+    fn panic_message(&self) -> String {
+        self.first()
+            .map(ErrorReport::panic_message)
+            .unwrap_or_else(|| "unknown error".to_owned())
+    }
+}
+
+/// Unwraps a `Result<T, impl Reportable>`, printing the error(s) with
+/// `ariadne` before panicking. Only meant for tests and quick diagnostics;
+/// the CLI collects reports instead of unwrapping.
+#[macro_export]
+macro_rules! check {
+    ($expr:expr) => {{
+        use $crate::err::Reportable as _;
+        match $expr {
+            Ok(value) => value,
+            Err(report) => {
+                report.print_all();
+                panic!("{}", report.panic_message())
+            }
+        }
+    }};
+}
@@ -0,0 +1,872 @@
+//! Re-emits a parsed tree as canonical Conduct source: 4-space indents,
+//! one space around binary operators, and multi-line compound/type
+//! literals with trailing commas. Formatting is idempotent — parsing the
+//! output and formatting again yields the same text — so it's safe to run
+//! on every save.
+
+use crate::ast::{
+    BinOp, CatchPattern, CompoundKey, Expr, ExprKind, IntBase, Literal, Param, Pattern, Statement,
+    StatementKind, StringPart, UnaryOp,
+};
+
+/// Style knobs for [`format_with`]. The defaults — 4-space indents,
+/// 100 columns, no tabs, no forced trailing commas — are the canonical
+/// style [`format`] emits.
+#[derive(Debug, Clone)]
+pub struct FormatOptions {
+    /// Spaces per indent level; ignored under [`FormatOptions::use_tabs`].
+    pub indent_width: usize,
+    /// Indent with one tab per level instead of spaces.
+    pub use_tabs: bool,
+    /// Lines longer than this wrap where the formatter knows how: call
+    /// argument lists, array literals, and member chains.
+    pub max_width: usize,
+    /// Force a trailing comma on wrapped argument lists and arrays
+    /// (compound and type literals always carry one).
+    pub trailing_commas: bool,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            indent_width: 4,
+            use_tabs: false,
+            max_width: 100,
+            trailing_commas: false,
+        }
+    }
+}
+
+/// Formats `stmts` as canonical Conduct source, ending with a newline.
+pub fn format(stmts: &[Statement]) -> String {
+    format_with(stmts, &FormatOptions::default())
+}
+
+/// [`format`] with explicit style [`FormatOptions`]. Whatever the
+/// options, the output re-parses to the same tree.
+pub fn format_with(stmts: &[Statement], options: &FormatOptions) -> String {
+    let mut fmt = Formatter {
+        options: options.clone(),
+        ..Formatter::default()
+    };
+    fmt.block_body(stmts);
+    fmt.out
+}
+
+#[derive(Default)]
+struct Formatter {
+    out: String,
+    indent: usize,
+    options: FormatOptions,
+}
+
+impl Formatter {
+    /// The leading whitespace for `levels` of indentation under the
+    /// current options.
+    fn indentation(&self, levels: usize) -> String {
+        if self.options.use_tabs {
+            "\t".repeat(levels)
+        } else {
+            " ".repeat(levels * self.options.indent_width)
+        }
+    }
+
+    /// Whether a rendering fits the configured width at the current
+    /// indent level.
+    fn fits(&self, text: &str) -> bool {
+        let indent = if self.options.use_tabs {
+            self.indent * self.options.indent_width
+        } else {
+            self.indentation(self.indent).len()
+        };
+        indent + text.chars().count() <= self.options.max_width
+    }
+
+    fn line(&mut self, text: &str) {
+        let indent = self.indentation(self.indent);
+        self.out.push_str(&indent);
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    /// Emits the statements of a block at the current indent level.
+    fn block_body(&mut self, stmts: &[Statement]) {
+        for stmt in stmts {
+            self.statement(stmt);
+        }
+    }
+
+    /// Emits `header {` ... `}` with `body` indented one level.
+    fn braced(&mut self, header: &str, body: &[Statement]) {
+        self.line(&format!("{header} {{"));
+        self.indent += 1;
+        self.block_body(body);
+        self.indent -= 1;
+        self.line("}");
+    }
+
+    fn statement(&mut self, stmt: &Statement) {
+        for annotation in &stmt.annotations {
+            if annotation.args.is_empty() {
+                self.line(&format!("@{}", annotation.name));
+            } else {
+                let args = annotation
+                    .args
+                    .iter()
+                    .map(|arg| self.expr(arg))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                self.line(&format!("@{}({args})", annotation.name));
+            }
+        }
+        match &stmt.kind {
+            StatementKind::Expr(expr) => {
+                let text = self.expr(expr);
+                self.line(&text);
+            }
+            StatementKind::Let { name, ty, value } => {
+                let text = self.binding("let", name, ty, value);
+                self.line(&text);
+            }
+            StatementKind::LetTuple { names, value } => {
+                let value = self.expr(value);
+                self.line(&format!("let ({}) = {value}", names.join(", ")));
+            }
+            StatementKind::Const {
+                name,
+                ty,
+                value,
+                native,
+            } => {
+                let keyword = if *native { "native const" } else { "const" };
+                let text = self.binding(keyword, name, ty, value);
+                self.line(&text);
+            }
+            StatementKind::Fn {
+                name,
+                type_params,
+                params,
+                ret,
+                body,
+                native,
+            } => {
+                let generics = if type_params.is_empty() {
+                    String::new()
+                } else {
+                    format!("<{}>", type_params.join(", "))
+                };
+                let params = self.params(params);
+                let ret = match ret {
+                    Some(ret) => format!(": {}", self.type_annotation(ret)),
+                    None => String::new(),
+                };
+                if *native {
+                    self.line(&format!("native fn {name}{generics}({params}){ret}"));
+                } else {
+                    self.braced(&format!("fn {name}{generics}({params}){ret}"), body);
+                }
+            }
+            StatementKind::Assign { target, op, value } => {
+                let target = self.expr(target);
+                let value = self.expr(value);
+                let op = match op {
+                    Some(op) => format!("{}=", binop_text(*op)),
+                    None => "=".to_owned(),
+                };
+                self.line(&format!("{target} {op} {value}"));
+            }
+            StatementKind::AssignChain { targets, value } => {
+                let targets = targets
+                    .iter()
+                    .map(|target| self.expr(target))
+                    .collect::<Vec<_>>()
+                    .join(" = ");
+                let value = self.expr(value);
+                self.line(&format!("{targets} = {value}"));
+            }
+            StatementKind::AssignParallel { targets, values } => {
+                let targets = targets
+                    .iter()
+                    .map(|target| self.expr(target))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let values = values
+                    .iter()
+                    .map(|value| self.expr(value))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                self.line(&format!("{targets} = {values}"));
+            }
+            StatementKind::If { branches, otherwise } => {
+                self.if_stmt(branches, otherwise.as_deref());
+            }
+            StatementKind::For {
+                binding,
+                iterable,
+                body,
+                label,
+            } => {
+                let iterable = self.expr(iterable);
+                let prefix = label.as_ref().map(|l| format!("{l}: ")).unwrap_or_default();
+                self.braced(&format!("{prefix}for {binding} in {iterable}"), body);
+            }
+            StatementKind::While { cond, body, label } => {
+                let cond = self.expr(cond);
+                let prefix = label.as_ref().map(|l| format!("{l}: ")).unwrap_or_default();
+                self.braced(&format!("{prefix}while {cond}"), body);
+            }
+            StatementKind::IfLet {
+                binding,
+                value,
+                body,
+                otherwise,
+            } => {
+                let value = self.expr(value);
+                if let Some(otherwise) = otherwise {
+                    self.line(&format!("if let {binding} = {value} {{"));
+                    self.indent += 1;
+                    self.block_body(body);
+                    self.indent -= 1;
+                    self.line("} else {");
+                    self.indent += 1;
+                    self.block_body(otherwise);
+                    self.indent -= 1;
+                    self.line("}");
+                } else {
+                    self.braced(&format!("if let {binding} = {value}"), body);
+                }
+            }
+            StatementKind::WhileLet {
+                binding,
+                value,
+                body,
+                label,
+            } => {
+                let value = self.expr(value);
+                let prefix = label.as_ref().map(|l| format!("{l}: ")).unwrap_or_default();
+                self.braced(&format!("{prefix}while let {binding} = {value}"), body);
+            }
+            StatementKind::DoWhile { body, cond } => {
+                self.line("do {");
+                self.indent += 1;
+                self.block_body(body);
+                self.indent -= 1;
+                let cond = self.expr(cond);
+                self.line(&format!("}} while {cond}"));
+            }
+            StatementKind::Return(None) => self.line("return"),
+            StatementKind::Return(Some(value)) => {
+                let value = self.expr(value);
+                self.line(&format!("return {value}"));
+            }
+            StatementKind::Break { label, value } => {
+                let mut text = "break".to_owned();
+                if let Some(label) = label {
+                    text.push(' ');
+                    text.push_str(label);
+                }
+                if let Some(value) = value {
+                    let value = self.expr(value);
+                    text.push(' ');
+                    text.push_str(&value);
+                }
+                self.line(&text);
+            }
+            StatementKind::Continue(label) => match label {
+                Some(label) => self.line(&format!("continue {label}")),
+                None => self.line("continue"),
+            },
+            StatementKind::Throw(value) => {
+                let value = self.expr(value);
+                self.line(&format!("throw {value}"));
+            }
+            StatementKind::Assert { lhs, rhs, message } => {
+                let mut text = match rhs {
+                    Some(rhs) => format!("assert_eq {}, {}", self.expr(lhs), self.expr(rhs)),
+                    None => format!("assert {}", self.expr(lhs)),
+                };
+                if let Some(message) = message {
+                    let message = self.expr(message);
+                    text.push_str(&format!(", {message}"));
+                }
+                self.line(&text);
+            }
+            StatementKind::Try { body, catches } => {
+                // `}` and the next `catch` share a line, so the arms are
+                // emitted by hand rather than through `braced`.
+                self.line("try {");
+                self.indent += 1;
+                self.block_body(body);
+                self.indent -= 1;
+                let mut header = "}".to_owned();
+                for arm in catches {
+                    let pattern = match &arm.pattern {
+                        CatchPattern::Type(types) => types
+                            .iter()
+                            .map(|parts| parts.join("."))
+                            .collect::<Vec<_>>()
+                            .join(" | "),
+                        CatchPattern::Any => "*".to_owned(),
+                        CatchPattern::Nil => {
+                            header.push_str(" catch? {");
+                            self.line(&header);
+                            self.indent += 1;
+                            self.block_body(&arm.body);
+                            self.indent -= 1;
+                            header = "}".to_owned();
+                            continue;
+                        }
+                    };
+                    match &arm.binding {
+                        Some(binding) => header.push_str(&format!(" catch {pattern} as {binding} {{")),
+                        None => header.push_str(&format!(" catch {pattern} in {{")),
+                    }
+                    self.line(&header);
+                    self.indent += 1;
+                    self.block_body(&arm.body);
+                    self.indent -= 1;
+                    header = "}".to_owned();
+                }
+                self.line(&header);
+            }
+            StatementKind::Module(name) => self.line(&format!("module {name}")),
+            StatementKind::Import { path, alias } => match alias {
+                Some(alias) => self.line(&format!("import {path} as {alias}")),
+                None => self.line(&format!("import {path}")),
+            },
+            StatementKind::SelectiveImport { names, path } => {
+                let names = names
+                    .iter()
+                    .map(|(name, alias)| match alias {
+                        Some(alias) => format!("{name} as {alias}"),
+                        None => name.clone(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                self.line(&format!("import {{ {names} }} from {path}"));
+            }
+            StatementKind::Include(path) => self.line(&format!("include {path}")),
+            StatementKind::Export(path) => self.line(&format!("export {path}")),
+            StatementKind::SelectiveExport { names, from } => {
+                let names = names
+                    .iter()
+                    .map(|(name, alias)| match alias {
+                        Some(alias) => format!("{name} as {alias}"),
+                        None => name.clone(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                match from {
+                    Some(from) => self.line(&format!("export {{ {names} }} from {from}")),
+                    None => self.line(&format!("export {names}")),
+                }
+            }
+            StatementKind::Enum { name, variants } => {
+                self.line(&format!("enum {name} {{"));
+                self.indent += 1;
+                for variant in variants {
+                    if variant.fields.is_empty() {
+                        self.line(&format!("{},", variant.name));
+                    } else {
+                        self.line(&format!("{}({}),", variant.name, variant.fields.join(", ")));
+                    }
+                }
+                self.indent -= 1;
+                self.line("}");
+            }
+            StatementKind::Struct {
+                name,
+                type_params,
+                fields,
+            } => {
+                let generics = if type_params.is_empty() {
+                    String::new()
+                } else {
+                    format!("<{}>", type_params.join(", "))
+                };
+                if fields.is_empty() {
+                    self.line(&format!("struct {name}{generics} {{}}"));
+                } else {
+                    self.line(&format!("struct {name}{generics} {{"));
+                    self.indent += 1;
+                    for (field, ty) in fields {
+                        self.line(&format!("{field}: {ty},"));
+                    }
+                    self.indent -= 1;
+                    self.line("}");
+                }
+            }
+            // There's no source to faithfully re-emit for a recovered
+            // error; leave a marker rather than silently dropping it.
+            StatementKind::Error => self.line("/* unparsed statement */"),
+        }
+    }
+
+    fn params(&mut self, params: &[Param]) -> String {
+        params
+            .iter()
+            .map(|param| {
+                let mut text = if param.variadic {
+                    format!("...{}", param.name)
+                } else {
+                    param.name.clone()
+                };
+                if let Some(ty) = &param.ty {
+                    text.push_str(&format!(": {}", self.type_annotation(ty)));
+                }
+                if let Some(default) = &param.default {
+                    let default = self.expr(default);
+                    text.push_str(&format!(" = {default}"));
+                }
+                text
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn binding(
+        &mut self,
+        keyword: &str,
+        name: &str,
+        ty: &Option<Expr>,
+        value: &Option<Expr>,
+    ) -> String {
+        let mut text = format!("{keyword} {name}");
+        if let Some(ty) = ty {
+            text.push_str(&format!(": {}", self.type_annotation(ty)));
+        }
+        if let Some(value) = value {
+            let value = self.expr(value);
+            text.push_str(&format!(" = {value}"));
+        }
+        text
+    }
+
+    fn if_stmt(&mut self, branches: &[(Expr, Vec<Statement>)], otherwise: Option<&[Statement]>) {
+        let mut header = String::new();
+        for (cond, body) in branches {
+            let cond = self.expr(cond);
+            header.push_str(&format!("if {cond} {{"));
+            self.line(&header);
+            self.indent += 1;
+            self.block_body(body);
+            self.indent -= 1;
+            header = "} else ".to_owned();
+        }
+        if let Some(otherwise) = otherwise {
+            // An optimizer-produced bare `else` block has no branches to
+            // hang off; emit it as `if true` so it still parses.
+            if branches.is_empty() {
+                self.line("if true {");
+            } else {
+                self.line("} else {");
+            }
+            self.indent += 1;
+            self.block_body(otherwise);
+            self.indent -= 1;
+        }
+        self.line("}");
+    }
+
+    /// A type annotation re-reads some expression shapes specially:
+    /// a single-element array is the `T[]` suffix form.
+    fn type_annotation(&mut self, ty: &Expr) -> String {
+        match &ty.kind {
+            ExprKind::Array(elements) if elements.len() == 1 => {
+                format!("{}[]", self.type_annotation(&elements[0]))
+            }
+            _ => self.expr(ty),
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) -> String {
+        match &expr.kind {
+            ExprKind::Literal(lit) => literal_text(lit),
+            ExprKind::Ident(name) => name.clone(),
+            ExprKind::Path(parts) => parts.join("."),
+            ExprKind::Unary(op, inner) => {
+                let inner = self.grouped(inner);
+                match op {
+                    UnaryOp::Not => format!("!{inner}"),
+                    UnaryOp::Neg => format!("-{inner}"),
+                    UnaryOp::BitNot => format!("~{inner}"),
+                    UnaryOp::Unwrap => format!("{inner}!!"),
+                }
+            }
+            ExprKind::Try(inner) => {
+                let inner = self.grouped(inner);
+                format!("{inner}?")
+            }
+            ExprKind::PostIncrement(inner) => {
+                let inner = self.grouped(inner);
+                format!("{inner}++")
+            }
+            ExprKind::PostDecrement(inner) => {
+                let inner = self.grouped(inner);
+                format!("{inner}--")
+            }
+            ExprKind::Spread(inner) => {
+                let inner = self.grouped(inner);
+                format!("...{inner}")
+            }
+            ExprKind::TypeOf(inner) => {
+                let inner = self.expr(inner);
+                format!("typeof({inner})")
+            }
+            ExprKind::Binary(op, lhs, rhs) => {
+                let lhs = self.grouped(lhs);
+                let rhs = self.grouped(rhs);
+                format!("{lhs} {} {rhs}", binop_text(*op))
+            }
+            // Ranges read as `0..10`, not `0 .. 10`.
+            ExprKind::Range {
+                start,
+                end,
+                inclusive,
+                step,
+            } => {
+                let start = self.grouped(start);
+                let end = self.grouped(end);
+                let op = if *inclusive { "..=" } else { ".." };
+                match step {
+                    Some(step) => {
+                        let step = self.grouped(step);
+                        format!("{start}{op}{end} step {step}")
+                    }
+                    None => format!("{start}{op}{end}"),
+                }
+            }
+            ExprKind::Ternary {
+                cond,
+                then,
+                otherwise,
+            } => {
+                let cond = self.grouped(cond);
+                let then = self.grouped(then);
+                let otherwise = self.grouped(otherwise);
+                format!("{cond} ? {then} : {otherwise}")
+            }
+            ExprKind::Call { callee, args } => {
+                let callee = self.grouped(callee);
+                let rendered: Vec<String> = args.iter().map(|arg| self.expr(arg)).collect();
+                let single = format!("{callee}({})", rendered.join(", "));
+                if self.fits(&single) || rendered.is_empty() {
+                    return single;
+                }
+                // Too wide: one argument per continuation line.
+                let mut text = format!("{callee}(\n");
+                let inner = self.indentation(self.indent + 1);
+                for (i, arg) in rendered.iter().enumerate() {
+                    text.push_str(&inner);
+                    text.push_str(arg);
+                    if i + 1 < rendered.len() || self.options.trailing_commas {
+                        text.push(',');
+                    }
+                    text.push('\n');
+                }
+                text.push_str(&self.indentation(self.indent));
+                text.push(')');
+                text
+            }
+            ExprKind::Index { target, index } => {
+                let target = self.grouped(target);
+                let index = self.expr(index);
+                format!("{target}[{index}]")
+            }
+            ExprKind::Member { target, name } => {
+                let target = self.grouped(target);
+                let single = format!("{target}.{name}");
+                if self.fits(&single) {
+                    return single;
+                }
+                // A chain past the width limit continues on an aligned
+                // line, so `a.b.c.d()` breaks at its links.
+                format!("{target}\n{}.{name}", self.indentation(self.indent + 1))
+            }
+            ExprKind::NullCoalesce(lhs, rhs) => {
+                let lhs = self.grouped(lhs);
+                let rhs = self.grouped(rhs);
+                format!("{lhs} ?? {rhs}")
+            }
+            ExprKind::OptionalAccess { target, name } => {
+                let target = self.grouped(target);
+                format!("{target}?.{name}")
+            }
+            ExprKind::Tuple(elements) => match elements.as_slice() {
+                [] => "()".to_owned(),
+                // The trailing comma is what keeps a reparse from reading
+                // a one-element tuple back as plain grouping.
+                [only] => {
+                    let only = self.expr(only);
+                    format!("({only},)")
+                }
+                _ => {
+                    let elements = elements
+                        .iter()
+                        .map(|element| self.expr(element))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    format!("({elements})")
+                }
+            },
+            ExprKind::InterpolatedString(parts) => {
+                let mut out = "\"".to_owned();
+                for part in parts {
+                    match part {
+                        StringPart::Text(text) => out.push_str(text),
+                        StringPart::Expr(expr) => {
+                            let expr = self.expr(expr);
+                            out.push_str(&format!("${{{expr}}}"));
+                        }
+                    }
+                }
+                out.push('"');
+                out
+            }
+            ExprKind::Array(elements) => {
+                let rendered: Vec<String> = elements
+                    .iter()
+                    .map(|element| self.expr(element))
+                    .collect();
+                let single = format!("[{}]", rendered.join(", "));
+                if self.fits(&single) || rendered.is_empty() {
+                    return single;
+                }
+                let mut text = "[\n".to_owned();
+                let inner = self.indentation(self.indent + 1);
+                for (i, element) in rendered.iter().enumerate() {
+                    text.push_str(&inner);
+                    text.push_str(element);
+                    if i + 1 < rendered.len() || self.options.trailing_commas {
+                        text.push(',');
+                    }
+                    text.push('\n');
+                }
+                text.push_str(&self.indentation(self.indent));
+                text.push(']');
+                text
+            }
+            ExprKind::Set(elements) => {
+                let elements = elements
+                    .iter()
+                    .map(|element| self.expr(element))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("#{{{elements}}}")
+            }
+            ExprKind::Map(entries) => {
+                let entries = entries
+                    .iter()
+                    .map(|(key, value)| format!("{} => {}", self.expr(key), self.expr(value)))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("#{{ {entries} }}")
+            }
+            ExprKind::Compound(fields) => {
+                if fields.is_empty() {
+                    return "{}".to_owned();
+                }
+                let mut text = "{\n".to_owned();
+                self.indent += 1;
+                for (key, value) in fields {
+                    let value = self.expr(value);
+                    let key = match key {
+                        CompoundKey::Static(name) => name.clone(),
+                        CompoundKey::Computed(key) => format!("[{}]", self.expr(key)),
+                    };
+                    text.push_str(&self.indentation(self.indent));
+                    text.push_str(&format!("{key}: {value},\n"));
+                }
+                self.indent -= 1;
+                text.push_str(&self.indentation(self.indent));
+                text.push('}');
+                text
+            }
+            ExprKind::TypeDef(fields) => {
+                if fields.is_empty() {
+                    return "type {}".to_owned();
+                }
+                let mut text = "type {\n".to_owned();
+                self.indent += 1;
+                for (name, ty) in fields {
+                    text.push_str(&self.indentation(self.indent));
+                    text.push_str(&format!("{name}: {ty},\n"));
+                }
+                self.indent -= 1;
+                text.push_str(&self.indentation(self.indent));
+                text.push('}');
+                text
+            }
+            ExprKind::ArrowFn {
+                params,
+                body,
+                expr_body,
+            } => {
+                // A bare-expression body prints back concise, with a lone
+                // plain parameter unparenthesized.
+                if *expr_body {
+                    if let [Statement {
+                        kind: StatementKind::Return(Some(value)),
+                        ..
+                    }] = body.as_slice()
+                    {
+                        let value = self.expr(value);
+                        let head = match params.as_slice() {
+                            [param]
+                                if param.ty.is_none()
+                                    && param.default.is_none()
+                                    && !param.variadic =>
+                            {
+                                param.name.clone()
+                            }
+                            _ => format!("({})", self.params(params)),
+                        };
+                        return format!("{head} => {value}");
+                    }
+                }
+                let params = self.params(params);
+                let mut text = format!("({params}) => {{\n");
+                self.indent += 1;
+                let mut inner = Formatter {
+                    out: String::new(),
+                    indent: self.indent,
+                    options: self.options.clone(),
+                };
+                inner.block_body(body);
+                text.push_str(&inner.out);
+                self.indent -= 1;
+                text.push_str(&self.indentation(self.indent));
+                text.push('}');
+                text
+            }
+            ExprKind::Match { subject, arms } => {
+                let subject = self.grouped(subject);
+                let mut text = format!("match {subject} {{\n");
+                self.indent += 1;
+                for arm in arms {
+                    let body = self.expr(&arm.body);
+                    text.push_str(&self.indentation(self.indent));
+                    text.push_str(&format!("{} => {body},\n", pattern_text(&arm.pattern)));
+                }
+                self.indent -= 1;
+                text.push_str(&self.indentation(self.indent));
+                text.push('}');
+                text
+            }
+            ExprKind::Loop(stmt) => {
+                // Render the wrapped loop statement and splice it in,
+                // sans its trailing newline.
+                let mut inner = Formatter {
+                    out: String::new(),
+                    indent: self.indent,
+                    options: self.options.clone(),
+                };
+                inner.statement(stmt);
+                inner.out.trim_end().trim_start().to_owned()
+            }
+            ExprKind::Garbage => "/* unparsed expression */".to_owned(),
+        }
+    }
+
+    /// Like [`Formatter::expr`], but parenthesizes anything that isn't an
+    /// atom so nesting never changes meaning. Always parenthesizing keeps
+    /// the formatter honest (and idempotent) without tracking the
+    /// precedence table here a second time.
+    fn grouped(&mut self, expr: &Expr) -> String {
+        let needs_parens = matches!(
+            expr.kind,
+            ExprKind::Binary(..)
+                | ExprKind::Range { .. }
+                | ExprKind::NullCoalesce(..)
+                | ExprKind::Ternary { .. }
+                | ExprKind::ArrowFn { .. }
+        );
+        let text = self.expr(expr);
+        if needs_parens {
+            format!("({text})")
+        } else {
+            text
+        }
+    }
+}
+
+pub(crate) fn binop_text(op: BinOp) -> &'static str {
+    match op {
+        BinOp::Add => "+",
+        BinOp::Sub => "-",
+        BinOp::Mul => "*",
+        BinOp::Div => "/",
+        BinOp::Mod => "%",
+        BinOp::Pow => "**",
+        BinOp::Eq => "==",
+        BinOp::NotEq => "!=",
+        BinOp::Lt => "<",
+        BinOp::LtEq => "<=",
+        BinOp::Gt => ">",
+        BinOp::GtEq => ">=",
+        BinOp::And => "&&",
+        BinOp::Or => "||",
+        BinOp::BitAnd => "&",
+        BinOp::BitOr => "|",
+        BinOp::BitXor => "^",
+        BinOp::Shl => "<<",
+        BinOp::Shr => ">>",
+    }
+}
+
+fn literal_text(lit: &Literal) -> String {
+    match lit {
+        Literal::Int(n, base) => match base {
+            IntBase::Dec => n.to_string(),
+            IntBase::Hex(digits) => format!("0x{digits}"),
+            IntBase::Oct(digits) => format!("0o{digits}"),
+            IntBase::Bin(digits) => format!("0b{digits}"),
+        },
+        // `{:?}` keeps a decimal point (`1.0`, not `1`), so the output
+        // re-lexes as a float rather than collapsing into an integer —
+        // and it prints the shortest form that parses back to the same
+        // bits, so `1e-5` stays `1e-5` instead of exploding into
+        // `0.00001` and `f64::MAX` survives to the last digit.
+        Literal::Float(n) if n.is_finite() => format!("{n:?}"),
+        // Non-finite floats only arise from folding an overflow; no
+        // literal spells them, but an exponent far past f64's range
+        // re-parses to the same infinity (and a 0/0 to the same NaN).
+        Literal::Float(n) if n.is_nan() => "(0.0 / 0.0)".to_owned(),
+        Literal::Float(n) if n.is_sign_negative() => "-1e999".to_owned(),
+        Literal::Float(_) => "1e999".to_owned(),
+        // String literals keep their source quotes all the way through the
+        // lexer, so they re-emit verbatim.
+        Literal::Str(s) => s.clone(),
+        Literal::Bool(b) => b.to_string(),
+        Literal::Nil => "nil".to_owned(),
+        Literal::Regex { pattern, flags } => format!("/{pattern}/{flags}"),
+    }
+}
+
+fn pattern_text(pattern: &Pattern) -> String {
+    match pattern {
+        Pattern::Wildcard => "_".to_owned(),
+        Pattern::Literal(lit) => literal_text(lit),
+        Pattern::Binding(name) => name.clone(),
+        Pattern::Array { elements, rest } => {
+            let mut parts: Vec<String> = elements.iter().map(pattern_text).collect();
+            if let Some(rest) = rest {
+                parts.push(format!("{rest}.."));
+            }
+            format!("[{}]", parts.join(", "))
+        }
+        Pattern::Compound { fields, open } => {
+            let mut parts: Vec<String> = fields
+                .iter()
+                .map(|(name, binding)| match binding {
+                    Pattern::Binding(bound) if bound == name => name.clone(),
+                    other => format!("{name}: {}", pattern_text(other)),
+                })
+                .collect();
+            if *open {
+                parts.push("..".to_owned());
+            }
+            format!("{{ {} }}", parts.join(", "))
+        }
+    }
+}
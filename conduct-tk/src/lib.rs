@@ -1,7 +1,10 @@
 pub mod ast;
 pub mod bin;
 pub mod err;
+pub mod fmt;
+pub mod optimize;
 pub mod parser;
+pub mod reparse;
 pub mod tk;
 pub mod validate;
 
@@ -20,9 +23,11 @@ mod tests {
     use logos::Logos;
 
     use crate::{
+        ast::{BinOp, Comment, Expr, ExprKind, Literal, StatementKind},
         bin::{from_binary, to_binary},
         check,
-        err::{CodeArea, CodeSource, ConductCache, ErrorReport, FancyColorGenerator, Res},
+        err::{CodeArea, CodeSource, ConductCache, ErrorReport, FancyColorGenerator, Res, Severity},
+        optimize::{optimize, OptimizationLevel},
         parser::Parser,
         tk::Token,
         validate::Validator,
@@ -60,6 +65,421 @@ mod tests {
         }
     }
 
+    #[test]
+    fn lexing_with_spans() {
+        use crate::tk::lex_with_spans;
+
+        // The same input `basic_tokenization` walks, spans included.
+        let text = r#"
+        /*
+        Line 1 comment
+        Line 2 comment
+        */
+        import std.io
+
+        let var = 0xFFAAFF
+        var = 0b010101
+        var = 0o143047
+        var = 1234567890123456
+        var = "Hello, World!"
+
+        native fun callable(name) {
+            println("Hello ${name}")
+        }
+        "#
+        .trim();
+        let tokens = lex_with_spans(text);
+        assert!(!tokens.is_empty());
+        // Spans are ordered, non-overlapping, and each maps back to its
+        // own slice of the input; the gaps are skipped trivia.
+        for window in tokens.windows(2) {
+            assert!(window[0].1.end <= window[1].1.start);
+        }
+        let (token, span) = &tokens[0];
+        assert_eq!(token, &Token::Import);
+        assert_eq!(&text[span.clone()], "import");
+
+        // Unrecognized input becomes a spanned error token, not a gap.
+        let tokens = lex_with_spans("let a = §");
+        let (token, span) = tokens.last().unwrap();
+        assert_eq!(token, &Token::Error);
+        assert_eq!(&"let a = §"[span.clone()], "§");
+    }
+
+    #[test]
+    fn numeric_separators() {
+        let mut lexer = Token::lexer("1_000 1_000_000 0xFF_AA_FF 0b01_01_01 0o14_30_47");
+        assert_eq!(lexer.next(), Some(Ok(Token::Int(1000))));
+        assert_eq!(lexer.next(), Some(Ok(Token::Int(1_000_000))));
+        assert_eq!(lexer.next(), Some(Ok(Token::Int(0xFFAAFF))));
+        assert_eq!(lexer.next(), Some(Ok(Token::Int(0b010101))));
+        assert_eq!(lexer.next(), Some(Ok(Token::Int(0o143047))));
+
+        // A separator must sit between two digits: trailing and doubled
+        // underscores reject the whole literal...
+        assert_eq!(Token::lexer("1_").next(), Some(Err(())));
+        assert_eq!(Token::lexer("1__0").next(), Some(Err(())));
+        assert_eq!(Token::lexer("0x_FF").next(), Some(Err(())));
+        // ...while a *leading* underscore was never a number to begin with
+        // (`_1` is a perfectly ordinary identifier).
+        assert!(matches!(
+            Token::lexer("_1").next(),
+            Some(Ok(Token::Ident(name))) if name == "_1"
+        ));
+    }
+
+    #[test]
+    fn lexer_error_reports() {
+        // `@` is a real token (annotations), so the parse error points at
+        // it; the unlexable `#` gets its own "unexpected character"
+        // report with the exact span.
+        let src = "let a = @#$";
+        let mut parser = Parser::new_inline(src);
+        let (_, errors) = parser.parse_all_recovering();
+        let at = src.find('@').unwrap();
+        assert!(errors
+            .iter()
+            .any(|e| e.position.span.0 == at && e.message.contains("expected an expression")));
+        let hash = src.find('#').unwrap();
+        let unexpected = errors
+            .iter()
+            .find(|e| e.message.contains("unexpected character `#`"))
+            .expect("the bad byte should report");
+        assert_eq!(unexpected.position.span, (hash, hash + 1));
+
+        // Lexing continues after a bad byte: two separated bad bytes
+        // each get a report, and the code between still parses.
+        let mut parser = Parser::new_inline("let a = 1 §\nlet b = 2 §\nprintln(a + b)");
+        let (stmts, errors) = parser.parse_all_recovering();
+        assert_eq!(
+            errors
+                .iter()
+                .filter(|e| e.message.contains("unexpected character"))
+                .count(),
+            2
+        );
+        assert_eq!(stmts.len(), 3);
+    }
+
+    #[test]
+    fn integer_overflow_literals() {
+        // Just over i64::MAX: a dedicated report, never wraparound.
+        let mut parser = Parser::new_inline("let a = 9223372036854775808");
+        let (_, errors) = parser.parse_all_recovering();
+        assert!(errors.iter().any(|e| e.message.contains("too large")));
+
+        let mut parser = Parser::new_inline("let a = 99999999999999999999999999");
+        let (_, errors) = parser.parse_all_recovering();
+        assert!(errors.iter().any(|e| e.message.contains("too large")));
+
+        // i64::MAX itself is fine...
+        let mut parser = Parser::new_inline("let a = 9223372036854775807");
+        let stmt = check!(parser.parse_statement());
+        let StatementKind::Let { value: Some(value), .. } = stmt.kind else {
+            panic!("expected a let statement");
+        };
+        assert!(matches!(
+            value.kind,
+            ExprKind::Literal(Literal::Int(i64::MAX, _))
+        ));
+
+        // ...and so is i64::MIN, whose magnitude alone would overflow:
+        // the prefix minus folds into the literal.
+        let mut parser = Parser::new_inline("let a = -9223372036854775808");
+        let stmt = check!(parser.parse_statement());
+        let StatementKind::Let { value: Some(value), .. } = stmt.kind else {
+            panic!("expected a let statement");
+        };
+        assert!(matches!(
+            value.kind,
+            ExprKind::Literal(Literal::Int(i64::MIN, _))
+        ));
+
+        // A minus after a value is subtraction, so the magnitude still
+        // overflows there.
+        let mut parser = Parser::new_inline("let b = x - 9223372036854775808");
+        let (_, errors) = parser.parse_all_recovering();
+        assert!(errors.iter().any(|e| e.message.contains("too large")));
+
+        // Malformed separators stay plain lex rejections.
+        assert_eq!(Token::lexer("1__0").next(), Some(Err(())));
+    }
+
+    #[test]
+    fn int_lexing_matches_reference() {
+        // The allocation-free fast path and the separator slow path must
+        // agree with a naive strip-then-parse reference in every radix.
+        let cases = [
+            "0",
+            "42",
+            "1_000",
+            "1_000_000",
+            "9223372036854775807",
+            "0xFF",
+            "0xff_aa_ff",
+            "0XFF_AA",
+            "0b1010",
+            "0b01_01_01",
+            "0o17",
+            "0o14_30_47",
+        ];
+        for case in cases {
+            let Some(Ok(Token::Int(found))) = Token::lexer(case).next() else {
+                panic!("expected `{case}` to lex as an integer");
+            };
+            let (digits, radix) = match case.get(..2) {
+                Some("0x" | "0X") => (&case[2..], 16),
+                Some("0b" | "0B") => (&case[2..], 2),
+                Some("0o" | "0O") => (&case[2..], 8),
+                _ => (case, 10),
+            };
+            let expected = i64::from_str_radix(&digits.replace('_', ""), radix).unwrap();
+            assert_eq!(found, expected, "lexing `{case}`");
+        }
+
+        // Separator-rule rejections are unchanged.
+        assert_eq!(Token::lexer("1__0").next(), Some(Err(())));
+        assert_eq!(Token::lexer("1_").next(), Some(Err(())));
+    }
+
+    #[test]
+    fn float_literals() {
+        let mut lexer = Token::lexer("2.75 .5 1e10 2.5E+3 1.0e-5");
+        assert_eq!(lexer.next(), Some(Ok(Token::Float(2.75))));
+        assert_eq!(lexer.next(), Some(Ok(Token::Float(0.5))));
+        assert_eq!(lexer.next(), Some(Ok(Token::Float(1e10))));
+        assert_eq!(lexer.next(), Some(Ok(Token::Float(2.5e3))));
+        assert_eq!(lexer.next(), Some(Ok(Token::Float(1.0e-5))));
+
+        // `1.method()` is a call on an integer, not a malformed float.
+        let mut lexer = Token::lexer("1.method()");
+        assert_eq!(lexer.next(), Some(Ok(Token::Int(1))));
+        assert_eq!(lexer.next(), Some(Ok(Token::Dot)));
+        assert!(matches!(lexer.next(), Some(Ok(Token::Ident(_)))));
+
+        let mut parser = Parser::new_inline("let pi = 3.14159");
+        let stmt = check!(parser.parse_statement());
+        let StatementKind::Let { value: Some(value), .. } = stmt.kind else {
+            panic!("expected a let statement, got {:?}", stmt.kind);
+        };
+        assert!(matches!(value.kind, ExprKind::Literal(Literal::Float(_))));
+    }
+
+    #[test]
+    fn integer_base_round_trips() -> Res<()> {
+        use crate::fmt::format;
+
+        // The written radix survives parse → format, digits intact.
+        let src = "let a = 0b010101\nlet b = 0o143047\nlet c = 0xFFAAFF\nlet d = 1234\nprintln(a + b + c + d)";
+        let mut parser = Parser::new_inline(src);
+        let stmts = check!(parser.parse());
+        let formatted = format(&stmts);
+        assert!(formatted.contains("0b010101"));
+        assert!(formatted.contains("0o143047"));
+        assert!(formatted.contains("0xFFAAFF"));
+        assert!(formatted.contains("1234"));
+
+        // ...and formatting is idempotent over the re-parse.
+        let mut parser = Parser::new_inline(formatted.trim());
+        let stmts = check!(parser.parse());
+        assert_eq!(format(&stmts), formatted);
+
+        // The value is radix-independent: folding hex math yields plain
+        // decimal, the written base being purely presentational.
+        let mut parser = Parser::new_inline("const x = 0x10 * 2");
+        let stmts = check!(parser.parse());
+        let (stmts, _) = crate::optimize::fold_constants(stmts);
+        let StatementKind::Const { value: Some(value), .. } = &stmts[0].kind else {
+            panic!("expected a const statement");
+        };
+        assert!(matches!(
+            &value.kind,
+            ExprKind::Literal(Literal::Int(32, crate::ast::IntBase::Dec))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn format_options() -> Res<()> {
+        use crate::ast::to_sexpr;
+        use crate::fmt::{format_with, FormatOptions};
+
+        let src = "fn process(alpha, beta, gamma, delta) {\n    return alpha + beta + gamma + delta\n}\nlet result = process(1000000, 2000000, 3000000, 4000000)\nprintln(result.descriptionText.innerContent.finalValue)";
+        let mut parser = Parser::new_inline(src);
+        let stmts = check!(parser.parse());
+
+        // At the default 100 columns everything stays on one line...
+        let wide = format_with(&stmts, &FormatOptions::default());
+        assert!(wide.contains("process(1000000, 2000000, 3000000, 4000000)"));
+        assert!(!wide.contains('\t'));
+
+        // ...while a narrow tab-indented style wraps the call one
+        // argument per line, trailing comma included, and breaks the
+        // member chain at its links.
+        let narrow = format_with(
+            &stmts,
+            &FormatOptions {
+                indent_width: 2,
+                use_tabs: true,
+                max_width: 24,
+                trailing_commas: true,
+            },
+        );
+        assert_ne!(wide, narrow);
+        assert!(narrow.contains("process(\n"));
+        assert!(narrow.contains("\t1000000,\n"));
+        assert!(narrow.contains("4000000,\n"));
+        assert!(narrow.contains("\n\t.finalValue"));
+
+        // Both styles re-parse to the same tree.
+        let mut parser = Parser::new_inline(wide.trim());
+        let from_wide = check!(parser.parse());
+        assert_eq!(to_sexpr(&from_wide), to_sexpr(&stmts));
+        let mut parser = Parser::new_inline(narrow.trim());
+        let from_narrow = check!(parser.parse());
+        assert_eq!(to_sexpr(&from_narrow), to_sexpr(&stmts));
+        Ok(())
+    }
+
+    #[test]
+    fn float_formatting_round_trips() -> Res<()> {
+        use crate::fmt::format;
+
+        // Parse, format, re-parse: every edge case must come back with
+        // the exact same bits, in the shortest spelling (`1e-5` must not
+        // explode into `0.00001`).
+        for value in [
+            1e-5,
+            f64::MAX,
+            5e-324,                  // smallest subnormal
+            2.2250738585072014e-308, // smallest normal
+            0.1,
+            1e20,
+        ] {
+            let src = format!("let x = {value:?}\nprintln(x)");
+            let mut parser = Parser::new_inline(&src);
+            let stmts = check!(parser.parse());
+            let formatted = format(&stmts);
+            let mut parser = Parser::new_inline(formatted.trim());
+            let stmts = check!(parser.parse());
+            let StatementKind::Let { value: Some(expr), .. } = &stmts[0].kind else {
+                panic!("expected a let statement after re-parsing {formatted:?}");
+            };
+            let ExprKind::Literal(Literal::Float(found)) = expr.kind else {
+                panic!("expected a float literal after re-parsing {formatted:?}");
+            };
+            assert_eq!(found.to_bits(), value.to_bits(), "round-tripping {value:?}");
+        }
+
+        // `-0.0` parses as a negation, formats back as one, and the
+        // negated value keeps its sign bit.
+        let mut parser = Parser::new_inline("let x = -0.0");
+        let stmts = check!(parser.parse());
+        let formatted = format(&stmts);
+        let mut parser = Parser::new_inline(formatted.trim());
+        let stmts = check!(parser.parse());
+        let StatementKind::Let { value: Some(expr), .. } = &stmts[0].kind else {
+            panic!("expected a let statement after re-parsing {formatted:?}");
+        };
+        let ExprKind::Unary(crate::ast::UnaryOp::Neg, inner) = &expr.kind else {
+            panic!("expected a negation after re-parsing {formatted:?}");
+        };
+        let ExprKind::Literal(Literal::Float(n)) = inner.kind else {
+            panic!("expected a float literal under the negation");
+        };
+        assert_eq!((-n).to_bits(), (-0.0f64).to_bits());
+
+        // Integers never pick up a decimal point or an exponent.
+        let mut parser = Parser::new_inline("let x = 1234567890123456");
+        let stmts = check!(parser.parse());
+        assert!(format(&stmts).contains("1234567890123456"));
+        Ok(())
+    }
+
+    #[test]
+    fn hex_float_literals() {
+        // 1.5 * 2^3 and a plain power of two.
+        let mut lexer = Token::lexer("0x1.8p3 0x1p10 0x1.921fb6p+1");
+        assert_eq!(lexer.next(), Some(Ok(Token::Float(12.0))));
+        assert_eq!(lexer.next(), Some(Ok(Token::Float(1024.0))));
+        // Exact bit pattern: (1 + 0x921fb6/16^6) * 2^1.
+        let expected = (1.0 + 0x921fb6 as f64 / 16f64.powi(6)) * 2.0;
+        let Some(Ok(Token::Float(found))) = lexer.next() else {
+            panic!("expected a hex float token");
+        };
+        assert_eq!(found.to_bits(), expected.to_bits());
+
+        // A missing or empty exponent rejects the literal outright.
+        assert_eq!(Token::lexer("0x1.8p").next(), Some(Err(())));
+        assert_eq!(Token::lexer("0x1.8p+").next(), Some(Err(())));
+
+        // Plain hex ints are untouched.
+        assert_eq!(Token::lexer("0xFF").next(), Some(Ok(Token::Int(0xFF))));
+    }
+
+    #[test]
+    fn node_spans() -> Res<()> {
+        let src = "let a = 1 + 2";
+        let mut parser = Parser::new_inline(src);
+        let stmt = check!(parser.parse_statement());
+        assert_eq!(stmt.span(), (0, src.len()));
+        let StatementKind::Let { value: Some(value), .. } = &stmt.kind else {
+            panic!("expected a let statement, got {:?}", stmt.kind);
+        };
+        // The initializer's span maps straight back to its substring.
+        assert_eq!(&src[value.span().0..value.span().1], "1 + 2");
+        Ok(())
+    }
+
+    #[test]
+    fn peek_lookahead() -> Res<()> {
+        let src = "let a = 1 + 2";
+        let mut parser = Parser::new_inline(src);
+
+        // Peeking, however far, consumes nothing.
+        assert_eq!(parser.peek_kind(), Some(&Token::Let));
+        assert!(matches!(parser.peek_nth(1), Some(Token::Ident(name)) if name == "a"));
+        assert_eq!(parser.peek_nth(2), Some(&Token::Eq));
+        assert_eq!(parser.peek_nth(3), Some(&Token::Int(1)));
+        assert_eq!(parser.peek_nth(99), None);
+        // Still at the start: peek_kind is unchanged...
+        assert_eq!(parser.peek_kind(), Some(&Token::Let));
+
+        // ...and parsing afterward consumes exactly the peeked tokens,
+        // spans intact.
+        let stmt = check!(parser.parse_statement());
+        assert_eq!(stmt.span(), (0, src.len()));
+        let StatementKind::Let { name, value: Some(value), .. } = &stmt.kind else {
+            panic!("expected a let statement, got {:?}", stmt.kind);
+        };
+        assert_eq!(name, "a");
+        assert_eq!(&src[value.span().0..value.span().1], "1 + 2");
+        assert_eq!(parser.peek_kind(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn spanned_combinator() -> Res<()> {
+        // The span covers exactly what the closure consumed.
+        let src = "1 + 2 * 3";
+        let mut parser = Parser::new_inline(src);
+        let (expr, span) = check!(parser.spanned(|p| p.parse_expression()));
+        assert_eq!(&src[span], "1 + 2 * 3");
+        assert!(matches!(expr.kind, ExprKind::Binary(BinOp::Add, ..)));
+
+        // Mid-stream the span starts at the current token, not at zero.
+        let src = "first\n10 + 20";
+        let mut parser = Parser::new_inline(src);
+        check!(parser.parse_expression());
+        let (_, span) = check!(parser.spanned(|p| p.parse_expression()));
+        assert_eq!(&src[span], "10 + 20");
+
+        // A failing closure propagates its report unchanged.
+        let mut parser = Parser::new_inline(")");
+        assert!(parser.spanned(|p| p.parse_expression()).is_err());
+        Ok(())
+    }
+
     #[test]
     fn binary_ops() {
         let mut parser = Parser::new_inline("1234 + 16 ** 12 / 13 == 24");
@@ -67,6 +487,72 @@ mod tests {
         assert!(expr.is_ok())
     }
 
+    #[test]
+    fn bitwise_operators() -> Res<()> {
+        // Shifts bind tighter than `|`: `(1 << 4) | 0xFF`.
+        let mut parser = Parser::new_inline("1 << 4 | 0xFF");
+        let expr = check!(parser.parse_expression());
+        let ExprKind::Binary(BinOp::BitOr, lhs, _) = expr.kind else {
+            panic!("expected `|` at the top, got {:?}", expr.kind);
+        };
+        assert!(matches!(lhs.kind, ExprKind::Binary(BinOp::Shl, ..)));
+
+        // `&` binds tighter than `^`, which binds tighter than `|`.
+        let mut parser = Parser::new_inline("a | b ^ c & d");
+        let expr = check!(parser.parse_expression());
+        let ExprKind::Binary(BinOp::BitOr, _, rhs) = expr.kind else {
+            panic!("expected `|` at the top, got {:?}", expr.kind);
+        };
+        assert!(matches!(rhs.kind, ExprKind::Binary(BinOp::BitXor, ..)));
+
+        let mut parser = Parser::new_inline("~mask >> 2");
+        let expr = check!(parser.parse_expression());
+        assert!(matches!(expr.kind, ExprKind::Binary(BinOp::Shr, ..)));
+        Ok(())
+    }
+
+    #[test]
+    fn chained_comparisons_reject() -> Res<()> {
+        // `1 < 2 < 3` would compare a bool to 3; the error spells out
+        // the conjunction to write instead.
+        let mut parser = Parser::new_inline("1 < 2 < 3");
+        let err = parser.parse_expression().unwrap_err();
+        assert!(err.message.contains("can't be chained"));
+        assert!(err.message.contains("a < b && b < c"));
+
+        // Mixed comparison operators chain-reject the same way...
+        let mut parser = Parser::new_inline("a <= b == c");
+        assert!(parser.parse_expression().is_err());
+
+        // ...while the spelled-out conjunction and a parenthesized
+        // comparison both stay legal.
+        let mut parser = Parser::new_inline("a < b && b < c");
+        check!(parser.parse_expression());
+        let mut parser = Parser::new_inline("(a < b) == c");
+        check!(parser.parse_expression());
+        Ok(())
+    }
+
+    #[test]
+    fn logical_operators_group_left() -> Res<()> {
+        // `a && b || c` is `(a && b) || c`, and the logical forms stay
+        // distinct from the single-character bitwise ones.
+        let mut parser = Parser::new_inline("a && b || c");
+        let expr = check!(parser.parse_expression());
+        let ExprKind::Binary(BinOp::Or, lhs, _) = expr.kind else {
+            panic!("expected `||` at the top, got {:?}", expr.kind);
+        };
+        assert!(matches!(lhs.kind, ExprKind::Binary(BinOp::And, ..)));
+
+        let mut parser = Parser::new_inline("a && b & c");
+        let expr = check!(parser.parse_expression());
+        let ExprKind::Binary(BinOp::And, _, rhs) = expr.kind else {
+            panic!("expected `&&` at the top, got {:?}", expr.kind);
+        };
+        assert!(matches!(rhs.kind, ExprKind::Binary(BinOp::BitAnd, ..)));
+        Ok(())
+    }
+
     #[test]
     fn paths() {
         let mut parser = Parser::new_inline("variable.property[index](arg1, arg2, 0xBAD,)");
@@ -101,7 +587,10 @@ mod tests {
 
         let report = ErrorReport {
             code: "E99",
-            call_stack: vec![area1.clone(), area2.clone()],
+            severity: Severity::Error,
+            call_stack: vec![area1.clone().into(), area2.clone().into()],
+            notes: Vec::new(),
+            help: None,
             current_module: "tests".to_owned(),
             position: area_current.clone(),
             message: "Syntax error".to_string(),
@@ -133,6 +622,333 @@ mod tests {
         assert!(report.report().print(ConductCache).is_ok());
     }
 
+    #[test]
+    fn stack_frame_traces() {
+        use crate::err::StackFrame;
+
+        // Two frames across modules, rendered with their contexts.
+        let entry: PathBuf = "./target/trace_entry.cd".into();
+        let lib: PathBuf = "./target/trace_lib.cd".into();
+        std::fs::write(&entry, "import 'trace_lib.cd'\nprintln(1)").unwrap();
+        std::fs::write(&lib, "include 'nothing.cdh'\nprintln(2)").unwrap();
+
+        let report = ErrorReport {
+            code: "E14",
+            severity: Severity::Error,
+            call_stack: vec![
+                StackFrame::new(
+                    CodeArea {
+                        src: CodeSource::File(lib.clone()),
+                        span: (0, 21),
+                    },
+                    "trace_lib",
+                    "while including",
+                ),
+                StackFrame::new(
+                    CodeArea {
+                        src: CodeSource::File(entry.clone()),
+                        span: (0, 21),
+                    },
+                    "main",
+                    "while importing",
+                ),
+            ],
+            notes: Vec::new(),
+            help: None,
+            current_module: "trace_lib".to_owned(),
+            position: CodeArea {
+                src: CodeSource::File(lib),
+                span: (8, 21),
+            },
+            message: "cannot resolve include `'nothing.cdh'`".to_owned(),
+            labels: vec![],
+        };
+        let mut rendered = Vec::new();
+        report.report().write(ConductCache, &mut rendered).unwrap();
+        let rendered = String::from_utf8_lossy(&rendered);
+        assert!(rendered.contains("while including, in module `trace_lib`"));
+        assert!(rendered.contains("while importing, in module `main`"));
+
+        // The migration shim still takes bare areas.
+        let frame: StackFrame = CodeArea {
+            src: CodeSource::File(entry),
+            span: (0, 1),
+        }
+        .into();
+        assert_eq!(frame.module, "./target/trace_entry.cd");
+        assert_eq!(frame.context, "while evaluating");
+    }
+
+    #[test]
+    fn report_notes_and_help() {
+        // The const-reassignment check carries a help footer, and both
+        // notes and help render into the ariadne output.
+        let parser = Parser::new_inline("const c = 1\nc = 2\nprintln(c)");
+        let validator = Validator::from(&parser);
+        let reports = parser.then_pipe(validator).finish_pipeline().unwrap_err();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(
+            reports[0].help.as_deref(),
+            Some("declare `c` with `let` to make it mutable")
+        );
+
+        // Rendering needs a re-fetchable source (`ConductCache` can't
+        // recover inline text), so run the same case from a file.
+        let path: PathBuf = "./target/help_note.cd".into();
+        std::fs::write(&path, "const c = 1\nc = 2\nprintln(c)").unwrap();
+        let parser = check!(Parser::from_file(&path)) as Parser;
+        let validator = Validator::from(&parser);
+        let reports = parser.then_pipe(validator).finish_pipeline().unwrap_err();
+        let report = reports[0]
+            .clone()
+            .with_note("constants are frozen at definition time");
+        let mut rendered = Vec::new();
+        report
+            .report()
+            .write(ConductCache, &mut rendered)
+            .unwrap();
+        let rendered = String::from_utf8_lossy(&rendered);
+        assert!(rendered.contains("declare `c` with `let` to make it mutable"));
+        assert!(rendered.contains("constants are frozen at definition time"));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn error_report_json_round_trip() {
+        let mut parser = Parser::new_inline("let a = )");
+        let (_, errors) = parser.parse_all_recovering();
+        let report = &errors[0];
+
+        // Render to a string and parse it back: every field must survive.
+        let json: serde_json::Value =
+            serde_json::from_str(&report.to_json().to_string()).unwrap();
+        assert_eq!(json["code"], report.code);
+        assert_eq!(json["message"], report.message);
+        assert_eq!(json["module"], report.current_module);
+        assert_eq!(json["position"]["source"]["type"], "inline");
+        assert_eq!(
+            json["position"]["span"][0].as_u64().unwrap() as usize,
+            report.position.span.0
+        );
+        assert_eq!(json["labels"][0]["text"], report.labels[0].1);
+
+        // A `File` source tags itself distinctly from an `Inline` one.
+        let mut file_report = report.clone();
+        file_report.position.src = CodeSource::File("tests/test.cd".into());
+        let json = file_report.to_json();
+        assert_eq!(json["position"]["source"]["type"], "file");
+        assert_eq!(json["position"]["source"]["path"], "tests/test.cd");
+    }
+
+    #[test]
+    fn error_code_registry() {
+        use crate::err::codes::{lookup, Code, ALL};
+
+        // No two registered codes render the same.
+        for (i, a) in ALL.iter().enumerate() {
+            for b in &ALL[i + 1..] {
+                assert_ne!(a.as_str(), b.as_str(), "{a:?} and {b:?} collide");
+            }
+        }
+        // Lookup round-trips every code, and each has an explanation.
+        for code in ALL {
+            assert_eq!(lookup(code.as_str()), Some(code));
+            assert!(!code.explanation().is_empty());
+        }
+        assert_eq!(lookup("E01"), Some(Code::SyntaxError));
+        assert_eq!(lookup("E00"), None);
+
+        // Producers draw from the registry: a parse error carries the
+        // registered syntax-error code.
+        let mut parser = Parser::new_inline("let a = )");
+        let (_, errors) = parser.parse_all_recovering();
+        assert_eq!(errors[0].code, Code::SyntaxError.as_str());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn ast_json_round_trip() -> Res<()> {
+        use crate::ast::{from_json, to_json};
+
+        let mut parser = check!(Parser::from_file("../tests/test.cd")) as Parser;
+        let stmts = check!(parser.parse());
+        let json = to_json(&stmts);
+
+        // Every node carries a `"kind"` tag, and spans survive so the
+        // JSON can drive a browser editor.
+        let first = &json[0];
+        assert!(first["kind"]["kind"].is_string());
+        assert!(first["area"]["span"][0].is_u64());
+
+        // ...and the tree deserializes back bit-for-bit equal.
+        assert_eq!(from_json(&json).unwrap(), stmts);
+
+        // Malformed input is an error, not a panic.
+        assert!(from_json(&serde_json::json!([{"kind": "Nonsense"}])).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn lsp_diagnostics() {
+        use crate::err::LspPosition;
+
+        // An emoji (2 UTF-16 units, 4 bytes) sits before the error span,
+        // and the file uses CRLF line endings.
+        let src = "let ok = 1\r\nlet x = \"😀\" + )";
+        let mut parser = Parser::new_inline(src);
+        let (_, errors) = parser.parse_all_recovering();
+        assert_eq!(errors.len(), 1);
+        let diagnostic = errors[0].to_lsp(src);
+        assert_eq!(diagnostic.range.start.line, 1);
+        // `let x = "😀" + ` is 15 UTF-16 units: the emoji counts as two.
+        assert_eq!(
+            diagnostic.range.start,
+            LspPosition { line: 1, character: 15 }
+        );
+        assert_eq!(diagnostic.code, "E01");
+        assert_eq!(diagnostic.source_name, "<inline>");
+    }
+
+    #[test]
+    fn error_summaries() {
+        let src = "let a = 1\nlet bb = 2\nlet c = 3";
+        // A span starting at the `bb` of line 2 summarizes as 2:5.
+        let offset = src.find("bb").unwrap();
+        let report = ErrorReport {
+            code: "E99",
+            severity: Severity::Error,
+            call_stack: vec![],
+            notes: Vec::new(),
+            help: None,
+            current_module: "tests".to_owned(),
+            position: CodeArea {
+                src: CodeSource::Inline(src.to_owned()),
+                span: (offset, offset + 2),
+            },
+            message: "Syntax error".to_owned(),
+            labels: vec![],
+        };
+        assert_eq!(report.summary(src), "<inline>:2:5: error[E99]: Syntax error");
+
+        // A file source prints its path, and the severity word follows
+        // the report's severity.
+        let mut file_report = report.clone();
+        file_report.position.src = CodeSource::File("tests/test.cd".into());
+        file_report.severity = Severity::Warning;
+        assert_eq!(
+            file_report.summary(src),
+            "tests/test.cd:2:5: warning[E99]: Syntax error"
+        );
+
+        // The very start of the input is line 1, column 1.
+        let mut first = report.clone();
+        first.position.span = (0, 3);
+        assert!(first.summary(src).starts_with("<inline>:1:1:"));
+
+        // A multi-byte char before the span counts as one column.
+        let emoji_src = "let s = \"😀\" + )";
+        let mut parser = Parser::new_inline(emoji_src);
+        let (_, errors) = parser.parse_all_recovering();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].summary(emoji_src).starts_with("<inline>:1:15:"));
+    }
+
+    #[test]
+    fn named_inline_modules() {
+        // Parse and validation reports both carry the supplied name...
+        let mut parser = Parser::new_inline_named("snippets/demo", "let a = )");
+        let (_, errors) = parser.parse_all_recovering();
+        assert_eq!(errors[0].current_module, "snippets/demo");
+
+        let parser = Parser::new_inline_named("snippets/demo", "undefined()");
+        let validator = Validator::from(&parser);
+        let reports = parser.then_pipe(validator).finish_pipeline().unwrap_err();
+        assert_eq!(reports[0].current_module, "snippets/demo");
+
+        // ...as do reports queued during lexing itself.
+        let mut parser = Parser::new_inline_named("snippets/demo", "let a = 99999999999999999999");
+        let (_, errors) = parser.parse_all_recovering();
+        assert!(errors
+            .iter()
+            .all(|e| e.current_module == "snippets/demo"));
+
+        // The unnamed form keeps its long-standing default.
+        let mut parser = Parser::new_inline("let a = )");
+        let (_, errors) = parser.parse_all_recovering();
+        assert_eq!(errors[0].current_module, "main");
+    }
+
+    #[test]
+    fn repl_line_parsing() -> Res<()> {
+        use crate::parser::ReplItem;
+
+        // A statement stays a statement...
+        let mut parser = Parser::new_repl("let x = 1", 1);
+        let item = check!(parser.parse_repl_line());
+        assert!(matches!(
+            &item,
+            ReplItem::Statement(stmt) if matches!(stmt.kind, StatementKind::Let { .. })
+        ));
+
+        // ...a bare expression comes back as one...
+        let mut parser = Parser::new_repl("1 + 2", 2);
+        let item = check!(parser.parse_repl_line());
+        assert!(matches!(
+            &item,
+            ReplItem::Expression(expr)
+                if matches!(expr.kind, ExprKind::Binary(BinOp::Add, ..))
+        ));
+
+        // ...a genuine statement error is not misreported as a failed
+        // expression...
+        let mut parser = Parser::new_repl("let x = )", 3);
+        let err = parser.parse_repl_line().unwrap_err();
+        assert!(err.message.contains("expected an expression"));
+        assert_eq!(err.position.src, CodeSource::Repl { line: 3 });
+
+        // ...and fully broken input still errors cleanly.
+        let mut parser = Parser::new_repl(") (", 4);
+        assert!(parser.parse_repl_line().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn repl_source_reports() {
+        // A parse error from REPL input line 3 names `in:3`.
+        let mut parser = Parser::new_repl("let a = )", 3);
+        let (_, errors) = parser.parse_all_recovering();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].position.src, CodeSource::Repl { line: 3 });
+        assert_eq!(errors[0].position.src.name(), "in:3");
+        // ...and the ariadne rendering resolves that id without panicking.
+        assert!(errors[0].report().print(ConductCache).is_ok());
+    }
+
+    #[test]
+    fn nesting_depth_limit() {
+        // 10,000 nested parens must come back as a clean report, not a
+        // stack overflow.
+        let src = format!("{}1{}", "(".repeat(10_000), ")".repeat(10_000));
+        let mut parser = Parser::new_inline(&src);
+        let err = parser.parse_expression().unwrap_err();
+        assert!(err.message.contains("maximum nesting depth exceeded"));
+
+        // Nested arrays hit the same guard...
+        let src = format!("{}1{}", "[".repeat(10_000), "]".repeat(10_000));
+        let mut parser = Parser::new_inline(&src);
+        let err = parser.parse_value().unwrap_err();
+        assert!(err.message.contains("maximum nesting depth exceeded"));
+
+        // ...the limit is configurable in both directions...
+        let mut parser = Parser::new_inline("((((1))))");
+        parser.max_depth = 3;
+        assert!(parser.parse_expression().is_err());
+
+        // ...and sane input stays well under the default.
+        let mut parser = Parser::new_inline("((((((((((1))))))))))");
+        check!(parser.parse_expression());
+    }
+
     #[test]
     fn errors() {
         let mut parser = Parser::new_inline("val(a");
@@ -140,6 +956,103 @@ mod tests {
         assert!(expr.is_err())
     }
 
+    #[test]
+    fn token_display_in_messages() {
+        // A mismatched delimiter reads naturally, Debug names nowhere in
+        // sight.
+        let mut parser = Parser::new_inline("let xs = [1, 2)");
+        let err = parser.parse_statement().unwrap_err();
+        assert_eq!(err.message, "expected `]`, found `)`");
+        assert!(!err.message.contains("RBracket"));
+
+        let mut parser = Parser::new_inline("fn f(a {");
+        let err = parser.parse_statement().unwrap_err();
+        assert!(err.message.contains("expected `)`, found `{`"));
+
+        // Value-carrying tokens name their class; EOF says so.
+        assert_eq!(Token::Ident("x".into()).to_string(), "identifier `x`");
+        assert_eq!(Token::Int(1).to_string(), "an integer literal");
+        let mut parser = Parser::new_inline("do { break }");
+        let err = parser.parse_statement().unwrap_err();
+        assert!(err.message.contains("found the end of input"));
+    }
+
+    #[test]
+    fn error_spans_whole_construct() {
+        use crate::parser::merge_spans;
+
+        // An unterminated call underlines from its `(` to where the
+        // parse gave up — here, the end of input.
+        let src = "val(a";
+        let mut parser = Parser::new_inline(src);
+        let err = parser.parse_expression().unwrap_err();
+        assert!(err.message.contains("unterminated call"));
+        assert_eq!(err.position.span, (src.find('(').unwrap(), src.len()));
+        assert_eq!(err.labels[0].0.span, err.position.span);
+
+        // The helper merges in either order.
+        assert_eq!(merge_spans(&(3..4), &(7..9)), 3..9);
+        assert_eq!(merge_spans(&(7..9), &(3..4)), 3..9);
+    }
+
+    #[test]
+    fn recover_to_synchronizes() -> Res<()> {
+        // Garbage between two `let` statements: after the first parses,
+        // `recover_to(&[Let])` skips exactly the junk and lands on the
+        // second `let`.
+        let mut parser = Parser::new_inline("let a = 1\n) ) ,\nlet b = 2");
+        check!(parser.parse_statement());
+        let skipped = parser.recover_to(&[Token::Let]);
+        assert_eq!(skipped, 3);
+        assert_eq!(parser.peek_kind(), Some(&Token::Let));
+        let stmt = check!(parser.parse_statement());
+        assert!(matches!(
+            stmt.kind,
+            StatementKind::Let { ref name, .. } if name == "b"
+        ));
+
+        // Value-carrying kinds match by variant; no target means skip to
+        // the end of input.
+        let mut parser = Parser::new_inline(") ) count = 2");
+        parser.recover_to(&[Token::Ident(String::new())]);
+        assert!(matches!(parser.peek_kind(), Some(Token::Ident(name)) if name == "count"));
+        let mut parser = Parser::new_inline(") ) )");
+        assert_eq!(parser.recover_to(&[Token::Let]), 3);
+        assert_eq!(parser.peek_kind(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn error_recovery() {
+        // Two malformed statements ("a"'s value and all of "b") in a row;
+        // `parse()` should recover from both and still parse "c" that
+        // follows, surfacing both diagnostics through `take_errors()`.
+        let mut parser = Parser::new_inline("let a = \nlet b = )\nlet c = 5");
+        let stmts = parser.parse().unwrap();
+        let errors = parser.take_errors();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(stmts.len(), 3);
+        assert!(matches!(stmts[0].kind, crate::ast::StatementKind::Error));
+        assert!(matches!(stmts[1].kind, crate::ast::StatementKind::Error));
+        assert!(matches!(
+            stmts[2].kind,
+            crate::ast::StatementKind::Let { ref name, .. } if name == "c"
+        ));
+    }
+
+    #[test]
+    fn parse_all_recovering_collects_every_error() {
+        // Three broken statements in a row; each should produce its own
+        // report, and the statements after each one should still be seen.
+        let mut parser =
+            Parser::new_inline("let a = \nconst b = )\nfn c( {\nlet ok = 1");
+        let (stmts, errors) = parser.parse_all_recovering();
+        assert_eq!(errors.len(), 3);
+        assert!(stmts
+            .iter()
+            .any(|s| matches!(s.kind, StatementKind::Let { ref name, .. } if name == "ok")));
+    }
+
     #[test]
     fn stmt_import() -> Res<()> {
         let mut parser = Parser::new_inline(
@@ -157,44 +1070,249 @@ import '../lib/frog.cdl'
     }
 
     #[test]
-    fn stmt_return() -> Res<()> {
+    fn stmt_import_alias() -> Res<()> {
         let mut parser = Parser::new_inline(
             r#"
-return
-return abc
-xreturn 123
+import std.io as io
+import '../lib/frog.cdl' as frog
+import std.ffi
         "#
             .trim(),
         );
-        check!(parser.parse_statement());
-        check!(parser.parse_statement());
-        check!(parser.parse_statement());
+        let stmt = check!(parser.parse_statement());
+        assert!(matches!(
+            stmt.kind,
+            StatementKind::Import { ref alias, .. } if alias.as_deref() == Some("io")
+        ));
+        let stmt = check!(parser.parse_statement());
+        assert!(matches!(
+            stmt.kind,
+            StatementKind::Import { ref path, ref alias }
+                if path == "'../lib/frog.cdl'" && alias.as_deref() == Some("frog")
+        ));
+        let stmt = check!(parser.parse_statement());
+        assert!(matches!(
+            stmt.kind,
+            StatementKind::Import { ref alias, .. } if alias.is_none()
+        ));
         Ok(())
     }
 
     #[test]
-    fn stmt_let() -> Res<()> {
+    fn stmt_selective_import() -> Res<()> {
         let mut parser = Parser::new_inline(
             r#"
-let a
-let b = 1 + d()
-let c = nil
+import { read, write } from std.io
+import { read as r, write } from '../lib/frog.cdl'
         "#
             .trim(),
         );
-        check!(parser.parse_statement());
-        check!(parser.parse_statement());
-        check!(parser.parse_statement());
+        let stmt = check!(parser.parse_statement());
+        assert!(matches!(
+            stmt.kind,
+            StatementKind::SelectiveImport { ref names, ref path }
+                if names.len() == 2 && path == "std.io"
+        ));
+        let stmt = check!(parser.parse_statement());
+        assert!(matches!(
+            stmt.kind,
+            StatementKind::SelectiveImport { ref names, .. }
+                if names[0] == ("read".to_owned(), Some("r".to_owned()))
+        ));
 
+        // An empty list imports nothing, which is almost certainly a
+        // mistake; the diagnostic should say how to fix it.
+        let mut parser = Parser::new_inline("import {} from std.io");
+        let err = parser.parse_statement().unwrap_err();
+        assert!(err.message.contains("at least one item"));
+
+        // ...and so is forgetting the `from` clause entirely.
+        let mut parser = Parser::new_inline("import { read }");
+        let err = parser.parse_statement().unwrap_err();
+        assert!(err.message.contains("from"));
         Ok(())
     }
 
     #[test]
-    fn stmt_const() -> Res<()> {
-        let mut parser = Parser::new_inline(
-            r#"
-const a = 0xFFAAFF;
-const b = 1 + d()
+    fn stmt_include() -> Res<()> {
+        use crate::ast::StatementKind::Include;
+
+        let mut parser = Parser::new_inline("include '../include/headers.cdh'");
+        let stmt = check!(parser.parse_statement());
+        assert!(matches!(
+            stmt.kind,
+            Include(ref path) if path == "'../include/headers.cdh'"
+        ));
+
+        // An included header's declarations splice into scope, so its
+        // `native fn` is callable unqualified.
+        let header = "native fn pow(a, b)\nnative const EULER";
+        let resolver = move |_: &CodeSource, path: &str| {
+            (path == "'math.cdh'").then(|| {
+                (
+                    CodeSource::File("math.cdh".into()),
+                    header.to_owned(),
+                )
+            })
+        };
+        let parser = Parser::new_inline("include 'math.cdh'\nprintln(pow(EULER, 8))");
+        let validator = Validator::from(&parser).include_resolver(resolver);
+        assert!(parser.then_pipe(validator).finish_pipeline().is_ok());
+
+        // Including over an existing definition is a conflict (the fn
+        // hoists, so the order doesn't hide it)...
+        let parser = Parser::new_inline(
+            "include 'math.cdh'\nfn pow(a, b) { return a * b }\nprintln(pow(2, 8))",
+        );
+        let validator = Validator::from(&parser).include_resolver(resolver);
+        let reports = parser.then_pipe(validator).finish_pipeline().unwrap_err();
+        assert!(reports
+            .iter()
+            .any(|r| r.message.contains("include conflicts") && r.message.contains("`pow`")));
+
+        // ...and an unresolvable include reports rather than silently
+        // defining nothing.
+        let parser = Parser::new_inline("include 'missing.cdh'");
+        let validator = Validator::from(&parser).include_resolver(resolver);
+        let reports = parser.then_pipe(validator).finish_pipeline().unwrap_err();
+        assert!(reports
+            .iter()
+            .any(|r| r.message.contains("cannot resolve include")));
+        Ok(())
+    }
+
+    #[test]
+    fn stmt_export_forms() -> Res<()> {
+        use crate::ast::StatementKind::{Export, SelectiveExport};
+
+        // The whole-module form is unchanged.
+        let mut parser = Parser::new_inline("export std.io");
+        let stmt = check!(parser.parse_statement());
+        assert!(matches!(stmt.kind, Export(ref path) if path == "std.io"));
+
+        // A local rename...
+        let mut parser = Parser::new_inline("export foo as publicFoo");
+        let stmt = check!(parser.parse_statement());
+        assert!(matches!(
+            stmt.kind,
+            SelectiveExport { ref names, from: None }
+                if names[0] == ("foo".to_owned(), Some("publicFoo".to_owned()))
+        ));
+
+        // ...and a selective re-export list with aliases.
+        let mut parser = Parser::new_inline("export { foo as bar, baz } from internal");
+        let stmt = check!(parser.parse_statement());
+        let SelectiveExport { names, from } = &stmt.kind else {
+            panic!("expected a selective export, got {:?}", stmt.kind);
+        };
+        assert_eq!(names.len(), 2);
+        assert_eq!(names[1], ("baz".to_owned(), None));
+        assert_eq!(from.as_deref(), Some("internal"));
+
+        // The new forms survive the binary round-trip.
+        let mut parser =
+            Parser::new_inline("export foo as publicFoo\nexport { a, b as c } from internal");
+        let stmts = check!(parser.parse());
+        let bytes = to_binary(stmts.clone()).unwrap();
+        assert_eq!(from_binary(&bytes).unwrap(), stmts);
+
+        // An empty list and a missing `from` both read as mistakes.
+        let mut parser = Parser::new_inline("export {} from internal");
+        assert!(parser.parse_statement().is_err());
+        let mut parser = Parser::new_inline("export { foo }");
+        assert!(parser.parse_statement().is_err());
+
+        // Exporting an undefined local name is the validator's catch;
+        // a defined one counts as used.
+        let parser = Parser::new_inline("export missing as other");
+        let validator = Validator::from(&parser);
+        let reports = parser.then_pipe(validator).finish_pipeline().unwrap_err();
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].message.contains("cannot export undefined symbol `missing`"));
+
+        let parser = Parser::new_inline("fn helper() { return 1 }\nexport helper as api");
+        let validator = Validator::from(&parser);
+        assert!(parser.then_pipe(validator).finish_pipeline().is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn stmt_return() -> Res<()> {
+        let mut parser = Parser::new_inline(
+            r#"
+return
+return abc
+xreturn 123
+        "#
+            .trim(),
+        );
+        check!(parser.parse_statement());
+        check!(parser.parse_statement());
+        check!(parser.parse_statement());
+        Ok(())
+    }
+
+    #[test]
+    fn stmt_let() -> Res<()> {
+        let mut parser = Parser::new_inline(
+            r#"
+let a
+let b = 1 + d()
+let c = nil
+        "#
+            .trim(),
+        );
+        check!(parser.parse_statement());
+        check!(parser.parse_statement());
+        check!(parser.parse_statement());
+
+        Ok(())
+    }
+
+    #[test]
+    fn stmt_let_annotated() -> Res<()> {
+        let mut parser = Parser::new_inline(
+            r#"
+let a: num = 1
+let b: str
+const c: type { hello: num } = { hello: 1 }
+        "#
+            .trim(),
+        );
+        let stmt = check!(parser.parse_statement());
+        let StatementKind::Let { ty: Some(ty), .. } = &stmt.kind else {
+            panic!("expected an annotated let, got {:?}", stmt.kind);
+        };
+        assert!(matches!(ty.kind, ExprKind::Ident(ref name) if name == "num"));
+        check!(parser.parse_statement());
+        let stmt = check!(parser.parse_statement());
+        let StatementKind::Const { ty: Some(ty), .. } = &stmt.kind else {
+            panic!("expected an annotated const, got {:?}", stmt.kind);
+        };
+        assert!(matches!(ty.kind, ExprKind::TypeDef(_)));
+
+        // Parameter annotations can mix with unannotated ones...
+        let mut parser = Parser::new_inline("fn f(x: str, y) { return x + y }");
+        let stmt = check!(parser.parse_statement());
+        let StatementKind::Fn { params, .. } = &stmt.kind else {
+            panic!("expected a fn statement, got {:?}", stmt.kind);
+        };
+        assert!(params[0].ty.is_some());
+        assert!(params[1].ty.is_none());
+
+        // ...and a dangling `:` with no type is a dedicated error.
+        let mut parser = Parser::new_inline("let a: ");
+        let err = parser.parse_statement().unwrap_err();
+        assert!(err.message.contains("expected a type after `:`"));
+        Ok(())
+    }
+
+    #[test]
+    fn stmt_const() -> Res<()> {
+        let mut parser = Parser::new_inline(
+            r#"
+const a = 0xFFAAFF;
+const b = 1 + d()
 const c = nil
         "#
             .trim(),
@@ -206,6 +1324,35 @@ const c = nil
         Ok(())
     }
 
+    #[test]
+    fn const_value_parsing() -> Res<()> {
+        // Operations over literals are compile-time constants...
+        let mut parser = Parser::new_inline("1 + 2");
+        check!(parser.parse_const_value());
+        let mut parser = Parser::new_inline("[1, 2 * 3, \"s\"]");
+        check!(parser.parse_const_value());
+        let mut parser = Parser::new_inline("{ key: 1 + 1 }");
+        check!(parser.parse_const_value());
+
+        // ...a call is not, and the report points at it exactly...
+        let src = "1 + foo()";
+        let mut parser = Parser::new_inline(src);
+        let err = parser.parse_const_value().unwrap_err();
+        assert!(err.message.contains("not a compile-time constant"));
+        assert_eq!(err.position.span.0, src.find("foo").unwrap());
+
+        // ...nor are variable references or arrow functions.
+        let mut parser = Parser::new_inline("limit");
+        assert!(parser.parse_const_value().is_err());
+        let mut parser = Parser::new_inline("x => x");
+        assert!(parser.parse_const_value().is_err());
+
+        // Ordinary `const` statements still take full expressions.
+        let mut parser = Parser::new_inline("const b = 1 + d()");
+        check!(parser.parse_statement());
+        Ok(())
+    }
+
     #[test]
     fn stmt_native_const() -> Res<()> {
         let mut parser = Parser::new_inline(
@@ -283,6 +1430,149 @@ fn semicolon() {
         Ok(())
     }
 
+    #[test]
+    fn stmt_fun_default_params() -> Res<()> {
+        let mut parser = Parser::new_inline(
+            r#"
+fn greet(name, greeting = "Hello") {
+    println(greeting + ", " + name)
+}
+        "#
+            .trim(),
+        );
+        let stmt = check!(parser.parse_statement());
+        let StatementKind::Fn { params, .. } = &stmt.kind else {
+            panic!("expected a fn statement, got {:?}", stmt.kind);
+        };
+        assert!(params[0].default.is_none());
+        assert!(params[1].default.is_some());
+
+        // Defaults survive the binary round-trip.
+        let bytes = to_binary(vec![stmt]).unwrap();
+        let stmts = from_binary(&bytes).unwrap();
+        let StatementKind::Fn { params, .. } = &stmts[0].kind else {
+            panic!("expected a fn statement after round-trip");
+        };
+        assert!(params[1].default.is_some());
+
+        // A required parameter can't follow a defaulted one.
+        let mut parser = Parser::new_inline("fn bad(a = 1, b) { }");
+        let err = parser.parse_statement().unwrap_err();
+        assert!(err.message.contains("without a default follows"));
+        Ok(())
+    }
+
+    #[test]
+    fn annotations() -> Res<()> {
+        let mut parser = Parser::new_inline(
+            r#"
+@inline
+fn fast() { return 1 }
+
+@deprecated("use fast", 2)
+const OLD = 1
+        "#
+            .trim(),
+        );
+        let stmt = check!(parser.parse_statement());
+        assert_eq!(stmt.annotations.len(), 1);
+        assert_eq!(stmt.annotations[0].name, "inline");
+        assert!(stmt.annotations[0].args.is_empty());
+        let stmt = check!(parser.parse_statement());
+        assert_eq!(stmt.annotations[0].args.len(), 2);
+
+        // Annotations only make sense on declarations.
+        let mut parser = Parser::new_inline("@inline\nreturn 1");
+        let err = parser.parse_statement().unwrap_err();
+        assert!(err.message.contains("annotations may only precede"));
+
+        // Unknown names warn (configurably).
+        let parser = Parser::new_inline("@wat\nfn f() { return 1 }\nf()");
+        let validator = Validator::from(&parser);
+        let reports = parser.then_pipe(validator).finish_pipeline().unwrap_err();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].code, "W05");
+        assert!(reports[0].message.contains("unknown annotation `@wat`"));
+
+        let parser = Parser::new_inline("@wat\nfn f() { return 1 }\nf()");
+        let validator = Validator::from(&parser).known_annotations(&["wat"]);
+        assert!(parser.then_pipe(validator).finish_pipeline().is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn stmt_fun_variadic() -> Res<()> {
+        let mut parser = Parser::new_inline(
+            r#"
+fn log(level, ...rest) {
+    println(level)
+}
+
+fn f(...xs) { }
+        "#
+            .trim(),
+        );
+        let stmt = check!(parser.parse_statement());
+        let StatementKind::Fn { params, .. } = &stmt.kind else {
+            panic!("expected a fn statement, got {:?}", stmt.kind);
+        };
+        assert!(!params[0].variadic);
+        assert!(params[1].variadic);
+        check!(parser.parse_statement());
+
+        // Anything after a variadic parameter is an error.
+        let mut parser = Parser::new_inline("fn f(...xs, y) { }");
+        let err = parser.parse_statement().unwrap_err();
+        assert!(err.message.contains("must be the last"));
+
+        // Call sites still take any number of arguments.
+        let mut parser = Parser::new_inline("log(1, 2, 3, 4, 5)");
+        check!(parser.parse_expression());
+        Ok(())
+    }
+
+    #[test]
+    fn keep_comments() -> Res<()> {
+        let src = r#"
+/// Greets someone by name.
+fn greet(name) {
+    // say it
+    println(name)
+}
+
+let x = 1 // trailing note
+        "#
+        .trim();
+        let mut parser =
+            Parser::new_keep_comments(CodeSource::Inline(src.to_owned()), Token::lexer(src));
+        let stmts = check!(parser.parse());
+
+        // The doc comment leads the fn; the plain one inside the body
+        // attaches to the statement it precedes there.
+        let StatementKind::Fn { body, .. } = &stmts[0].kind else {
+            panic!("expected a fn statement, got {:?}", stmts[0].kind);
+        };
+        assert_eq!(stmts[0].comments.len(), 1);
+        assert!(stmts[0].comments[0].doc);
+        assert_eq!(stmts[0].comments[0].text, "/// Greets someone by name.");
+        assert!(matches!(
+            body[0].comments.as_slice(),
+            [Comment { doc: false, text, .. }] if text == "// say it"
+        ));
+
+        // A comment sharing a line with the statement before it trails.
+        assert!(matches!(
+            stmts[1].comments.as_slice(),
+            [Comment { text, .. }] if text == "// trailing note"
+        ));
+
+        // A normal parse keeps discarding them.
+        let mut parser = Parser::new_inline(src);
+        let stmts = check!(parser.parse());
+        assert!(stmts.iter().all(|s| s.comments.is_empty()));
+        Ok(())
+    }
+
     #[test]
     fn stmt_if() -> Res<()> {
         let mut parser = Parser::new_inline(
@@ -317,6 +1607,123 @@ if false {
         Ok(())
     }
 
+    #[test]
+    fn stmt_enum() -> Res<()> {
+        let mut parser = Parser::new_inline(
+            r#"
+enum Color { Red, Green, Blue }
+
+enum Shape {
+    Circle(radius),
+    Rect(w, h),
+}
+        "#
+            .trim(),
+        );
+        let stmt = check!(parser.parse_statement());
+        assert!(matches!(
+            stmt.kind,
+            StatementKind::Enum { ref name, ref variants }
+                if name == "Color" && variants.len() == 3 && variants[0].fields.is_empty()
+        ));
+        // Tuple-style variants carry their field names; note the trailing
+        // comma after the last one.
+        let stmt = check!(parser.parse_statement());
+        assert!(matches!(
+            stmt.kind,
+            StatementKind::Enum { ref variants, .. }
+                if variants[0].fields == ["radius"] && variants[1].fields == ["w", "h"]
+        ));
+
+        // An empty enum declares nothing usable.
+        let mut parser = Parser::new_inline("enum Nothing { }");
+        let err = parser.parse_statement().unwrap_err();
+        assert!(err.message.contains("at least one variant"));
+
+        // Duplicate variant names are the validator's catch.
+        let parser = Parser::new_inline("enum Dup { A, B, A }");
+        let validator = Validator::from(&parser);
+        let reports = parser.then_pipe(validator).finish_pipeline().unwrap_err();
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].message.contains("duplicate variant `A`"));
+        Ok(())
+    }
+
+    #[test]
+    fn stmt_struct() -> Res<()> {
+        let mut parser = Parser::new_inline(
+            r#"
+struct Point {
+    x: num,
+    y: num
+}
+
+struct Named {
+    'hello': str,
+}
+
+struct Unit {}
+        "#
+            .trim(),
+        );
+        let stmt = check!(parser.parse_statement());
+        assert!(matches!(
+            stmt.kind,
+            StatementKind::Struct { ref name, ref fields, .. }
+                if name == "Point" && fields.len() == 2
+        ));
+        check!(parser.parse_statement());
+        let stmt = check!(parser.parse_statement());
+        assert!(matches!(
+            stmt.kind,
+            StatementKind::Struct { ref fields, .. } if fields.is_empty()
+        ));
+
+        // The declared name is referenceable afterwards.
+        let parser = Parser::new_inline("struct Point { x: num }\nlet p = Point\nprintln(p)");
+        let validator = Validator::from(&parser);
+        assert!(parser.then_pipe(validator).finish_pipeline().is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn generic_declarations() -> Res<()> {
+        // `fn map<T, U>(..)` stores its type parameters...
+        let mut parser = Parser::new_inline("fn map<T, U>(list: T[], f): U[] {\n    return list\n}");
+        let stmt = check!(parser.parse_statement());
+        let StatementKind::Fn { type_params, .. } = &stmt.kind else {
+            panic!("expected a fn statement, got {:?}", stmt.kind);
+        };
+        assert_eq!(type_params, &["T", "U"]);
+
+        // ...and so does `struct Box<T>`.
+        let mut parser = Parser::new_inline("struct Box<T> {\n    value: T\n}");
+        let stmt = check!(parser.parse_statement());
+        let StatementKind::Struct { type_params, .. } = &stmt.kind else {
+            panic!("expected a struct statement, got {:?}", stmt.kind);
+        };
+        assert_eq!(type_params, &["T"]);
+
+        // A non-generic fn still parses, and `<` elsewhere is still
+        // less-than.
+        let mut parser = Parser::new_inline("fn plain(a) { return a < 3 }");
+        check!(parser.parse_statement());
+        let mut parser = Parser::new_inline("fn bad<>() { }");
+        assert!(parser.parse_statement().is_err());
+
+        // Unused type parameters warn only when opted in.
+        let src = "fn map<T, U>(list: T[], f) {\n    return f(list)\n}\nprintln(map([1], x => x))";
+        let parser = Parser::new_inline(src);
+        let validator = Validator::from(&parser);
+        assert!(parser.then_pipe(validator).finish_pipeline().is_ok());
+        let parser = Parser::new_inline(src);
+        let validator = Validator::from(&parser).check_type_params(true);
+        let reports = parser.then_pipe(validator).finish_pipeline().unwrap_err();
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].message.contains("unused type parameter `U`"));
+        Ok(())
+    }
+
     #[test]
     fn stmt_assign() -> Res<()> {
         let mut parser = Parser::new_inline(
@@ -337,6 +1744,69 @@ a -= "Hello, World!"
         Ok(())
     }
 
+    #[test]
+    fn stmt_compound_assign() -> Res<()> {
+        let mut parser = Parser::new_inline(
+            r#"
+a *= 2
+a /= 3
+a %= 4
+a **= 2
+        "#
+            .trim(),
+        );
+
+        for op in [BinOp::Mul, BinOp::Div, BinOp::Mod, BinOp::Pow] {
+            let stmt = check!(parser.parse_statement());
+            assert!(matches!(
+                stmt.kind,
+                StatementKind::Assign { op: Some(found), .. } if found == op
+            ));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn stmt_multi_assign() -> Res<()> {
+        // Chained: every target takes the same value.
+        let mut parser = Parser::new_inline("a = b = 0");
+        let stmt = check!(parser.parse_statement());
+        assert!(matches!(
+            stmt.kind,
+            StatementKind::AssignChain { ref targets, .. } if targets.len() == 2
+        ));
+
+        // Parallel: the classic swap.
+        let mut parser = Parser::new_inline("a, b = b, a");
+        let stmt = check!(parser.parse_statement());
+        assert!(matches!(
+            stmt.kind,
+            StatementKind::AssignParallel { ref targets, ref values }
+                if targets.len() == 2 && values.len() == 2
+        ));
+
+        // Mismatched arity is caught at parse time...
+        let mut parser = Parser::new_inline("a, b = 1");
+        let err = parser.parse_statement().unwrap_err();
+        assert!(err.message.contains("2 targets but 1 values"));
+
+        // ...and non-places / consts are the validator's business.
+        let parser = Parser::new_inline("const c = 1\nlet x = 2\nx = c = 5\nprintln(x)");
+        let validator = Validator::from(&parser);
+        let reports = parser.then_pipe(validator).finish_pipeline().unwrap_err();
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].message.contains("cannot reassign const `c`"));
+
+        let parser = Parser::new_inline("1, a = 2, 3");
+        let validator = Validator::from(&parser);
+        let reports = parser.then_pipe(validator).finish_pipeline().unwrap_err();
+        assert!(reports
+            .iter()
+            .any(|r| r.message.contains("not assignable")));
+        Ok(())
+    }
+
     #[test]
     fn stmt_expr() -> Res<()> {
         let mut parser = Parser::new_inline(
@@ -380,6 +1850,105 @@ file.create(args[0])
         Ok(())
     }
 
+    #[test]
+    fn spread_elements() -> Res<()> {
+        // `[...a, 4, ...b]` mixes spreads with plain elements...
+        let mut parser = Parser::new_inline("[...a, 4, ...b]");
+        let expr = check!(parser.parse_expression());
+        let ExprKind::Array(elements) = &expr.kind else {
+            panic!("expected an array literal, got {:?}", expr.kind);
+        };
+        assert_eq!(elements.len(), 3);
+        assert!(matches!(elements[0].kind, ExprKind::Spread(_)));
+        assert!(matches!(elements[1].kind, ExprKind::Literal(Literal::Int(4, _))));
+        assert!(matches!(elements[2].kind, ExprKind::Spread(_)));
+
+        // ...and `f(...args)` spreads call arguments.
+        let mut parser = Parser::new_inline("f(first, ...rest)");
+        let expr = check!(parser.parse_expression());
+        let ExprKind::Call { args, .. } = &expr.kind else {
+            panic!("expected a call, got {:?}", expr.kind);
+        };
+        assert!(matches!(args[1].kind, ExprKind::Spread(_)));
+
+        // A lone `...` has nothing to splice.
+        let mut parser = Parser::new_inline("[...]");
+        assert!(parser.parse_expression().is_err());
+
+        // Spreads survive the binary round-trip.
+        let mut parser = Parser::new_inline("let merged = [...a, ...b]\nf(...merged)");
+        let (stmts, _) = parser.parse_all_recovering();
+        let bytes = to_binary(stmts.clone()).unwrap();
+        assert_eq!(from_binary(&bytes).unwrap(), stmts);
+        Ok(())
+    }
+
+    #[test]
+    fn tuples() -> Res<()> {
+        // Empty, single (needs the trailing comma), and multi-element.
+        let mut parser = Parser::new_inline("()");
+        let expr = check!(parser.parse_expression());
+        assert!(matches!(expr.kind, ExprKind::Tuple(ref e) if e.is_empty()));
+
+        let mut parser = Parser::new_inline("(x,)");
+        let expr = check!(parser.parse_expression());
+        assert!(matches!(expr.kind, ExprKind::Tuple(ref e) if e.len() == 1));
+
+        let mut parser = Parser::new_inline(r#"(1, "two", 3.0)"#);
+        let expr = check!(parser.parse_expression());
+        assert!(matches!(expr.kind, ExprKind::Tuple(ref e) if e.len() == 3));
+
+        // ...while `(expr)` without a comma is still plain grouping.
+        let mut parser = Parser::new_inline("(1 + 2)");
+        let expr = check!(parser.parse_expression());
+        assert!(matches!(expr.kind, ExprKind::Binary(..)));
+
+        // Destructuring binds each name.
+        let parser = Parser::new_inline("let pair = (1, 2)\nlet (a, b) = pair\nprintln(a + b)");
+        let validator = Validator::from(&parser);
+        assert!(parser.then_pipe(validator).finish_pipeline().is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn set_and_map_literals() -> Res<()> {
+        // `#{}` is the empty set; a map needs at least one `=>` entry.
+        let mut parser = Parser::new_inline("#{}");
+        let expr = check!(parser.parse_value());
+        assert!(matches!(expr.kind, ExprKind::Set(ref e) if e.is_empty()));
+
+        let mut parser = Parser::new_inline("#{1, 2, 3}");
+        let expr = check!(parser.parse_value());
+        assert!(matches!(expr.kind, ExprKind::Set(ref e) if e.len() == 3));
+
+        // Non-string keys are the whole point of `=>` maps.
+        let mut parser = Parser::new_inline(r#"#{ 1 => "one", 2 => "two" }"#);
+        let expr = check!(parser.parse_value());
+        let ExprKind::Map(entries) = &expr.kind else {
+            panic!("expected a map literal, got {:?}", expr.kind);
+        };
+        assert_eq!(entries.len(), 2);
+        assert!(matches!(
+            entries[0].0.kind,
+            ExprKind::Literal(Literal::Int(1, _))
+        ));
+
+        // One literal is either a set or a map, never both.
+        let mut parser = Parser::new_inline("#{1, 2 => 3}");
+        let err = parser.parse_value().unwrap_err();
+        assert!(err.message.contains("cannot mix"));
+        let mut parser = Parser::new_inline("#{1 => 2, 3}");
+        let err = parser.parse_value().unwrap_err();
+        assert!(err.message.contains("cannot mix"));
+
+        // Both shapes survive the binary round-trip.
+        let mut parser = Parser::new_inline("let s = #{1, 2}\nlet m = #{ 1 => 2 }");
+        let stmts = check!(parser.parse());
+        let bytes = to_binary(stmts.clone()).unwrap();
+        assert_eq!(from_binary(&bytes).unwrap(), stmts);
+        Ok(())
+    }
+
     #[test]
     fn literal_compound() -> Res<()> {
         let mut parser = Parser::new_inline(
@@ -407,19 +1976,94 @@ file.create(args[0])
     }
 
     #[test]
-    fn arrow_function() -> Res<()> {
-        let mut parser = Parser::new_inline(
-            r#"
-let noargs = () => {
-    let a = 1;
-}
+    fn computed_compound_keys() -> Res<()> {
+        use crate::ast::CompoundKey;
 
-let args = (arg1, arg2) => {
-    import std.io
-    println(arg1 + arg2)
-}
-        "#
-            .trim(),
+        // `{ [a + "x"]: 1 }`: the key is an expression...
+        let mut parser = Parser::new_inline(r#"{ [a + "x"]: 1 }"#);
+        let expr = check!(parser.parse_value());
+        let ExprKind::Compound(fields) = &expr.kind else {
+            panic!("expected a compound literal, got {:?}", expr.kind);
+        };
+        let CompoundKey::Computed(key) = &fields[0].0 else {
+            panic!("expected a computed key, got {:?}", fields[0].0);
+        };
+        assert!(matches!(key.kind, ExprKind::Binary(BinOp::Add, ..)));
+
+        // ...static and computed keys mix freely...
+        let mut parser = Parser::new_inline(r#"{ fixed: 1, [key()]: 2, other: 3 }"#);
+        let expr = check!(parser.parse_value());
+        let ExprKind::Compound(fields) = &expr.kind else {
+            panic!("expected a compound literal, got {:?}", expr.kind);
+        };
+        assert_eq!(fields[0].0.as_static(), Some("fixed"));
+        assert_eq!(fields[1].0.as_static(), None);
+        assert_eq!(fields[2].0.as_static(), Some("other"));
+
+        // ...an unclosed bracket key errors...
+        let mut parser = Parser::new_inline("{ [a: 1 }");
+        assert!(parser.parse_value().is_err());
+
+        // ...and a computed-key literal has no static shape to clash
+        // with an annotation.
+        let parser = Parser::new_inline(
+            "let k = \"dyn\"\nlet shape: type { fixed: num } = { [k]: 1 }\nprintln(shape)",
+        );
+        let validator = Validator::from(&parser);
+        assert!(parser.then_pipe(validator).finish_pipeline().is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn compound_shorthand_fields() -> Res<()> {
+        // `{ name, age }` expands to `{ name: name, age: age }`...
+        let mut parser = Parser::new_inline("{ name, age }");
+        let expr = check!(parser.parse_value());
+        let ExprKind::Compound(fields) = &expr.kind else {
+            panic!("expected a compound literal, got {:?}", expr.kind);
+        };
+        assert_eq!(fields.len(), 2);
+        assert_eq!(fields[0].0.as_static(), Some("name"));
+        assert!(matches!(
+            fields[0].1.kind,
+            ExprKind::Ident(ref value) if value == "name"
+        ));
+
+        // ...mixing shorthand and explicit entries works...
+        let mut parser = Parser::new_inline("{ x, y: 2 }");
+        let expr = check!(parser.parse_value());
+        let ExprKind::Compound(fields) = &expr.kind else {
+            panic!("expected a compound literal, got {:?}", expr.kind);
+        };
+        assert!(matches!(fields[0].1.kind, ExprKind::Ident(_)));
+        assert!(matches!(fields[1].1.kind, ExprKind::Literal(_)));
+
+        // ...and a quoted key can't go short.
+        let mut parser = Parser::new_inline("{ 'hello' }");
+        let err = parser.parse_value().unwrap_err();
+        assert!(err.message.contains("plain identifier"));
+
+        // The validator sees the shorthand value as a read.
+        let parser = Parser::new_inline("let name = \"n\"\nlet p = { name }\nprintln(p)");
+        let validator = Validator::from(&parser);
+        assert!(parser.then_pipe(validator).finish_pipeline().is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn arrow_function() -> Res<()> {
+        let mut parser = Parser::new_inline(
+            r#"
+let noargs = () => {
+    let a = 1;
+}
+
+let args = (arg1, arg2) => {
+    import std.io
+    println(arg1 + arg2)
+}
+        "#
+            .trim(),
         );
 
         check!(parser.parse_statement());
@@ -428,6 +2072,97 @@ let args = (arg1, arg2) => {
         Ok(())
     }
 
+    #[test]
+    fn arrow_expression_bodies() -> Res<()> {
+        // `x => x * 2`: one bare parameter, implicit return.
+        let mut parser = Parser::new_inline("let double = x => x * 2");
+        let stmt = check!(parser.parse_statement());
+        let StatementKind::Let { value: Some(value), .. } = &stmt.kind else {
+            panic!("expected a let statement, got {:?}", stmt.kind);
+        };
+        let ExprKind::ArrowFn { params, body, expr_body } = &value.kind else {
+            panic!("expected an arrow function, got {:?}", value.kind);
+        };
+        assert_eq!(params.len(), 1);
+        assert!(*expr_body);
+        assert!(matches!(body[0].kind, StatementKind::Return(Some(_))));
+
+        // Parenthesized parameters take an expression body too...
+        let mut parser = Parser::new_inline("let add = (a, b) => a + b");
+        let stmt = check!(parser.parse_statement());
+        let StatementKind::Let { value: Some(value), .. } = &stmt.kind else {
+            panic!("expected a let statement, got {:?}", stmt.kind);
+        };
+        assert!(matches!(
+            value.kind,
+            ExprKind::ArrowFn { ref params, expr_body: true, .. } if params.len() == 2
+        ));
+
+        // ...and the braced form still parses as a block.
+        let mut parser = Parser::new_inline("let f = (a) => {\n    return a\n}");
+        let stmt = check!(parser.parse_statement());
+        let StatementKind::Let { value: Some(value), .. } = &stmt.kind else {
+            panic!("expected a let statement, got {:?}", stmt.kind);
+        };
+        assert!(matches!(
+            value.kind,
+            ExprKind::ArrowFn { expr_body: false, .. }
+        ));
+
+        // `{` after `=>` always opens a block; a compound-literal body
+        // needs parentheses.
+        let mut parser = Parser::new_inline("let f = x => ({ a: 1 })");
+        let stmt = check!(parser.parse_statement());
+        let StatementKind::Let { value: Some(value), .. } = &stmt.kind else {
+            panic!("expected a let statement, got {:?}", stmt.kind);
+        };
+        let ExprKind::ArrowFn { body, expr_body: true, .. } = &value.kind else {
+            panic!("expected an expression-bodied arrow, got {:?}", value.kind);
+        };
+        let StatementKind::Return(Some(returned)) = &body[0].kind else {
+            panic!("expected the implicit return");
+        };
+        assert!(matches!(returned.kind, ExprKind::Compound(_)));
+
+        // The formatter prints the concise form back, and validation
+        // sees the parameter as used.
+        let src = "let double = x => x * 2\nprintln(double(4))";
+        let mut parser = Parser::new_inline(src);
+        let stmts = check!(parser.parse());
+        assert!(crate::fmt::format(&stmts).contains("x => x * 2"));
+        let parser = Parser::new_inline(src);
+        let validator = Validator::from(&parser).check_params(true);
+        assert!(parser.then_pipe(validator).finish_pipeline().is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn typeof_operator() -> Res<()> {
+        // Operator form...
+        let mut parser = Parser::new_inline("typeof x");
+        let expr = check!(parser.parse_expression());
+        assert!(matches!(expr.kind, ExprKind::TypeOf(_)));
+
+        // ...and the legacy call-style spelling, which is the same node.
+        let mut parser = Parser::new_inline("typeof(x)");
+        let expr = check!(parser.parse_expression());
+        assert!(matches!(expr.kind, ExprKind::TypeOf(_)));
+
+        // It binds tightly: `typeof a == \"num\"` compares the typeof.
+        let mut parser = Parser::new_inline(r#"typeof a == "num""#);
+        let expr = check!(parser.parse_expression());
+        let ExprKind::Binary(BinOp::Eq, lhs, _) = expr.kind else {
+            panic!("expected `==` at the top, got {:?}", expr.kind);
+        };
+        assert!(matches!(lhs.kind, ExprKind::TypeOf(_)));
+
+        // The validator knows the result is a str.
+        let parser = Parser::new_inline("let x = 1\nlet t: str = typeof x\nprintln(t)");
+        let validator = Validator::from(&parser);
+        assert!(parser.then_pipe(validator).finish_pipeline().is_ok());
+        Ok(())
+    }
+
     #[test]
     fn ternaries() -> Res<()> {
         let mut parser = Parser::new_inline(
@@ -446,6 +2181,135 @@ nil ? nil : nil
         Ok(())
     }
 
+    #[test]
+    fn postfix_increment_decrement() -> Res<()> {
+        // `i++` and `i--` parse as postfix nodes on the place...
+        let mut parser = Parser::new_inline("i++");
+        let expr = check!(parser.parse_expression());
+        let ExprKind::PostIncrement(inner) = &expr.kind else {
+            panic!("expected a post-increment, got {:?}", expr.kind);
+        };
+        assert!(matches!(inner.kind, ExprKind::Ident(ref name) if name == "i"));
+        let mut parser = Parser::new_inline("i--");
+        let expr = check!(parser.parse_expression());
+        assert!(matches!(expr.kind, ExprKind::PostDecrement(_)));
+
+        // ...and `a++ + b` binds the `++` to `a`, leaving a binary add.
+        let mut parser = Parser::new_inline("a++ + b");
+        let expr = check!(parser.parse_expression());
+        let ExprKind::Binary(BinOp::Add, lhs, _) = &expr.kind else {
+            panic!("expected `+` at the top, got {:?}", expr.kind);
+        };
+        assert!(matches!(lhs.kind, ExprKind::PostIncrement(_)));
+
+        // The classic loop idiom validates clean...
+        let parser = Parser::new_inline("let i = 0\nwhile i < 3 {\n    i++\n}\nprintln(i)");
+        let validator = Validator::from(&parser);
+        assert!(parser.then_pipe(validator).finish_pipeline().is_ok());
+
+        // ...a const target is rejected...
+        let parser = Parser::new_inline("const c = 1\nc++\nprintln(c)");
+        let validator = Validator::from(&parser);
+        let reports = parser.then_pipe(validator).finish_pipeline().unwrap_err();
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].message.contains("cannot apply `++` to const `c`"));
+
+        // ...and so is a literal.
+        let parser = Parser::new_inline("1++");
+        let validator = Validator::from(&parser);
+        let reports = parser.then_pipe(validator).finish_pipeline().unwrap_err();
+        assert!(reports
+            .iter()
+            .any(|r| r.message.contains("needs an assignable target")));
+        Ok(())
+    }
+
+    #[test]
+    fn question_mark_forms() -> Res<()> {
+        // `a ? b : c` stays a ternary...
+        let mut parser = Parser::new_inline("a ? b : c");
+        let expr = check!(parser.parse_expression());
+        assert!(matches!(expr.kind, ExprKind::Ternary { .. }));
+
+        // ...`a?` with nothing following propagates...
+        let mut parser = Parser::new_inline("let x = mayFail()?");
+        let stmt = check!(parser.parse_statement());
+        let StatementKind::Let { value: Some(value), .. } = &stmt.kind else {
+            panic!("expected a let statement, got {:?}", stmt.kind);
+        };
+        let ExprKind::Try(inner) = &value.kind else {
+            panic!("expected a propagation, got {:?}", value.kind);
+        };
+        assert!(matches!(inner.kind, ExprKind::Call { .. }));
+
+        // ...and `a?.b` is still optional chaining.
+        let mut parser = Parser::new_inline("a?.b");
+        let expr = check!(parser.parse_expression());
+        assert!(matches!(expr.kind, ExprKind::OptionalAccess { .. }));
+
+        // A `?` at the end of a line propagates even when the next line
+        // starts an expression.
+        let mut parser = Parser::new_inline("let x = f()?\nprintln(x)");
+        let stmts = check!(parser.parse());
+        assert_eq!(stmts.len(), 2);
+        let StatementKind::Let { value: Some(value), .. } = &stmts[0].kind else {
+            panic!("expected a let statement, got {:?}", stmts[0].kind);
+        };
+        assert!(matches!(value.kind, ExprKind::Try(_)));
+
+        // Propagation inside a function has a caller to rethrow to;
+        // at the top level there's nothing above, which warns.
+        let parser =
+            Parser::new_inline("fn g() { return 1 }\nfn f() {\n    return g()?\n}\nf()");
+        let validator = Validator::from(&parser);
+        assert!(parser.then_pipe(validator).finish_pipeline().is_ok());
+
+        let parser = Parser::new_inline("let x = println(1)?\nprintln(x)");
+        let validator = Validator::from(&parser);
+        let reports = parser.then_pipe(validator).finish_pipeline().unwrap_err();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].code, "W07");
+        assert!(reports[0].message.contains("outside of a function"));
+        Ok(())
+    }
+
+    #[test]
+    fn null_coalescing_and_optional_chaining() -> Res<()> {
+        // `??` chains to the left: `(a ?? b) ?? c`.
+        let mut parser = Parser::new_inline("a ?? b ?? c");
+        let expr = check!(parser.parse_expression());
+        let ExprKind::NullCoalesce(lhs, _) = expr.kind else {
+            panic!("expected a null-coalesce, got {:?}", expr.kind);
+        };
+        assert!(matches!(lhs.kind, ExprKind::NullCoalesce(..)));
+
+        // `a?.b?.c` nests optional accesses like plain member access.
+        let mut parser = Parser::new_inline("a?.b?.c");
+        let expr = check!(parser.parse_expression());
+        let ExprKind::OptionalAccess { target, name } = expr.kind else {
+            panic!("expected an optional access, got {:?}", expr.kind);
+        };
+        assert_eq!(name, "c");
+        assert!(matches!(
+            target.kind,
+            ExprKind::OptionalAccess { ref name, .. } if name == "b"
+        ));
+
+        // `??` still coexists with the ternary's lone `?`.
+        let mut parser = Parser::new_inline("a ?? b ? c : d");
+        check!(parser.parse_expression());
+
+        // A literal lhs folds away: `nil ?? x` is just `x`.
+        let mut parser = Parser::new_inline("let v = nil ?? fallback");
+        let stmts = check!(parser.parse());
+        let stmts = optimize(stmts, OptimizationLevel::Simple);
+        let StatementKind::Let { value: Some(value), .. } = &stmts[0].kind else {
+            panic!("expected a let statement");
+        };
+        assert!(matches!(value.kind, ExprKind::Ident(ref name) if name == "fallback"));
+        Ok(())
+    }
+
     #[test]
     fn type_definitions() -> Res<()> {
         let mut parser = Parser::new_inline(
@@ -471,6 +2335,225 @@ type { }
         Ok(())
     }
 
+    #[test]
+    fn constant_folding() -> Res<()> {
+        use crate::optimize::fold_constants;
+
+        let mut parser = Parser::new_inline(
+            r#"
+const x = 2 ** 10
+const s = "a" + "b"
+let y = a + 1
+let z = 1 / 0
+        "#
+            .trim(),
+        );
+        let stmts = check!(parser.parse());
+        let (stmts, reports) = fold_constants(stmts);
+
+        // `2 ** 10` folds to an *integer* 1024...
+        let StatementKind::Const { value: Some(value), .. } = &stmts[0].kind else {
+            panic!("expected a const statement");
+        };
+        assert!(matches!(value.kind, ExprKind::Literal(Literal::Int(1024, _))));
+
+        // ...`"a" + "b"` splices the quoted contents into one literal...
+        let StatementKind::Const { value: Some(value), .. } = &stmts[1].kind else {
+            panic!("expected a const statement");
+        };
+        assert!(matches!(
+            value.kind,
+            ExprKind::Literal(Literal::Str(ref s)) if s == "\"ab\""
+        ));
+
+        // ...a variable operand is left alone...
+        let StatementKind::Let { value: Some(value), .. } = &stmts[2].kind else {
+            panic!("expected a let statement");
+        };
+        assert!(matches!(value.kind, ExprKind::Binary(..)));
+
+        // ...and a literal division by zero is reported, not folded.
+        let StatementKind::Let { value: Some(value), .. } = &stmts[3].kind else {
+            panic!("expected a let statement");
+        };
+        assert!(matches!(value.kind, ExprKind::Binary(..)));
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].message.contains("division by zero"));
+        Ok(())
+    }
+
+    #[test]
+    fn duplicate_definitions() -> Res<()> {
+        // A second `fn` by the same name would silently replace the
+        // first: always an error, labeling both sites.
+        let parser = Parser::new_inline("fn foo() { return 1 }\nfn foo() { return 2 }\nfoo()");
+        let validator = Validator::from(&parser);
+        let reports = parser.then_pipe(validator).finish_pipeline().unwrap_err();
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].message.contains("`foo` is defined twice"));
+        assert_eq!(reports[0].labels.len(), 2);
+
+        // Redeclaring a `let` is shadowing by default...
+        let src = "let x = 1\nprintln(x)\nlet x = 2\nprintln(x)";
+        let parser = Parser::new_inline(src);
+        let validator = Validator::from(&parser);
+        assert!(parser.then_pipe(validator).finish_pipeline().is_ok());
+
+        // ...and an error once opted in.
+        let parser = Parser::new_inline(src);
+        let validator = Validator::from(&parser).check_let_redeclare(true);
+        let reports = parser.then_pipe(validator).finish_pipeline().unwrap_err();
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].message.contains("`x` is defined twice"));
+
+        // Distinct scopes never collide: the inner `foo` draws the usual
+        // shadow warning, but no duplicate-definition error.
+        let parser = Parser::new_inline("fn foo() { return 1 }\nfn bar() {\n    fn foo() { return 2 }\n    return foo()\n}\nprintln(foo() + bar())");
+        let validator = Validator::from(&parser).check_let_redeclare(true);
+        let reports = parser.then_pipe(validator).finish_pipeline().unwrap_err();
+        assert!(reports.iter().all(|r| r.code == "W03"));
+        Ok(())
+    }
+
+    #[test]
+    fn const_dependency_order() -> Res<()> {
+        use crate::validate::check_const_order;
+
+        // A chain in declaration order is fine and evaluates front to back.
+        let mut parser = Parser::new_inline(
+            r#"
+const a = 10
+const b = a * 2
+const c = a + b
+println(c)
+        "#
+            .trim(),
+        );
+        let stmts = check!(parser.parse());
+        let (order, reports) = check_const_order("tests", &stmts);
+        assert!(reports.is_empty());
+        assert_eq!(order, vec![0, 1, 2]);
+
+        // Depending on a const defined further down is a forward reference;
+        // the order still puts the dependency first.
+        let mut parser = Parser::new_inline("const b = a\nconst a = 1");
+        let stmts = check!(parser.parse());
+        let (order, reports) = check_const_order("tests", &stmts);
+        assert_eq!(order, vec![1, 0]);
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0]
+            .message
+            .contains("before `a` is defined"));
+
+        // `const a = a` can never evaluate.
+        let mut parser = Parser::new_inline("const a = a");
+        let stmts = check!(parser.parse());
+        let (_, reports) = check_const_order("tests", &stmts);
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].message.contains("refers to itself"));
+
+        // Mutual references report as one cycle, not two forward refs.
+        let mut parser = Parser::new_inline("const a = b\nconst b = a");
+        let stmts = check!(parser.parse());
+        let (order, reports) = check_const_order("tests", &stmts);
+        assert!(order.is_empty());
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].message.contains("cycle: a → b → a"));
+
+        // A closure body reads lazily: no ordering constraint.
+        let mut parser = Parser::new_inline("const f = () => {\n    return g\n}\nconst g = 1");
+        let stmts = check!(parser.parse());
+        let (_, reports) = check_const_order("tests", &stmts);
+        assert!(reports.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn pipeline_stages_chain() -> Res<()> {
+        use crate::optimize::ConstFolder;
+
+        // Validation then folding: diagnostics from both stages merge,
+        // and the folder's transform reaches the final tree.
+        let parser = Parser::new_inline("const x = 2 ** 10\nprintln(x)");
+        let validator = Validator::from(&parser);
+        let stmts = parser
+            .then_pipe(validator)
+            .then_pipe(ConstFolder)
+            .finish_pipeline()
+            .unwrap();
+        let StatementKind::Const { value: Some(value), .. } = &stmts[0].kind else {
+            panic!("expected a const statement, got {:?}", stmts[0].kind);
+        };
+        assert!(matches!(value.kind, ExprKind::Literal(Literal::Int(1024, _))));
+
+        // A later stage's diagnostics surface alongside the validator's.
+        let parser = Parser::new_inline("let unused = 1\nlet z = 1 / 0\nprintln(z)");
+        let validator = Validator::from(&parser);
+        let reports = parser
+            .then_pipe(validator)
+            .then_pipe(ConstFolder)
+            .finish_pipeline()
+            .unwrap_err();
+        assert!(reports.iter().any(|r| r.code == "W04"));
+        assert!(reports.iter().any(|r| r.message.contains("division by zero")));
+        Ok(())
+    }
+
+    #[test]
+    fn const_inlining_is_scoped() -> Res<()> {
+        // A `const` shadowed in a nested scope must inline as *its* value,
+        // not the outer const's -- regression test for a bug where a flat
+        // lookup returned whichever binding was pushed first.
+        let mut parser = Parser::new_inline(
+            r#"
+const x = 1
+fn f() {
+    const x = 2
+    let y = x
+}
+        "#
+            .trim(),
+        );
+        let stmts = check!(parser.parse());
+        let stmts = optimize(stmts, OptimizationLevel::Full);
+
+        let StatementKind::Fn { body, .. } = &stmts[1].kind else {
+            panic!("expected a fn statement, got {:?}", stmts[1].kind);
+        };
+        let StatementKind::Let { value: Some(value), .. } = &body[1].kind else {
+            panic!("expected a let statement, got {:?}", body[1].kind);
+        };
+        assert!(matches!(value.kind, ExprKind::Literal(Literal::Int(2, _))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_many_spans() -> Res<()> {
+        let mut parser = Parser::new_inline(
+            r#"
+let a = 1
+fn f() {
+    return a
+}
+println(f())
+        "#
+            .trim(),
+        );
+        let stmts = check!(parser.parse_many());
+        assert_eq!(stmts.len(), 3);
+        // Spans are ordered and non-overlapping.
+        for window in stmts.windows(2) {
+            assert!(window[0].1.end <= window[1].1.start);
+        }
+
+        // Garbage after a valid statement reads as trailing tokens.
+        let mut parser = Parser::new_inline("let a = 1\n) )");
+        let err = parser.parse_many().unwrap_err();
+        assert!(err.message.contains("unexpected trailing tokens"));
+        Ok(())
+    }
+
     #[test]
     fn file_parsing() -> Res<()> {
         let path: PathBuf = "../tests/test.cd".into();
@@ -480,6 +2563,7 @@ type { }
         let lexer = Token::lexer(&buf);
         let mut parser = Parser::new(CodeSource::File(path), lexer);
         let parsed = check!(parser.parse());
+        let parsed = optimize(parsed, OptimizationLevel::Full);
 
         let out = to_binary(parsed).unwrap();
         let out_path: PathBuf = "./target/file.cdt".into();
@@ -487,6 +2571,211 @@ type { }
         Ok(())
     }
 
+    #[test]
+    fn binary_header_rejects_garbage() {
+        use crate::bin::BinError;
+
+        let mut parser = Parser::new_inline("let a = 1");
+        let stmts = check!(parser.parse());
+        let bytes = to_binary(stmts).unwrap();
+        assert_eq!(&bytes[..4], b"CDT\0");
+        // version 3, raw (uncompressed) flag
+        assert_eq!(&bytes[4..7], &[3, 0, 0]);
+
+        // Truncated inside the header, wrong magic, and a version from
+        // the future must all fail gracefully, never panic.
+        assert!(matches!(from_binary(&bytes[..3]), Err(BinError::Truncated)));
+        let mut garbage = bytes.clone();
+        garbage[0] = b'X';
+        assert!(matches!(from_binary(&garbage), Err(BinError::BadMagic)));
+        let mut newer = bytes.clone();
+        newer[4] = 0xFF;
+        newer[5] = 0xFF;
+        assert!(matches!(
+            from_binary(&newer),
+            Err(BinError::VersionMismatch { found: 0xFFFF })
+        ));
+        // A payload of magic + version + noise dies on its nonsense
+        // compression flag before the decoder ever sees it.
+        let noise = [b"CDT\0\x03\x00".as_slice(), &[0xAB; 16]].concat();
+        assert!(matches!(
+            from_binary(&noise),
+            Err(BinError::UnsupportedFlags { found: 0xAB })
+        ));
+
+        // Flipping a single payload byte in an otherwise valid blob is
+        // caught by the CRC32 footer.
+        let mut flipped = bytes.clone();
+        let mid = bytes.len() / 2;
+        flipped[mid] ^= 0x01;
+        assert!(matches!(from_binary(&flipped), Err(BinError::ChecksumMismatch)));
+    }
+
+    #[cfg(feature = "flate2")]
+    #[test]
+    fn binary_compression_round_trip() {
+        use crate::bin::to_binary_compressed;
+
+        let path: PathBuf = "../tests/test.cd".into();
+        let mut buf = String::new();
+        File::open(&path).unwrap().read_to_string(&mut buf).unwrap();
+        let mut parser = Parser::new(CodeSource::File(path), Token::lexer(&buf));
+        let stmts = check!(parser.parse());
+
+        let raw = to_binary(stmts.clone()).unwrap();
+        let compressed = to_binary_compressed(stmts.clone()).unwrap();
+        assert!(compressed.len() < raw.len());
+
+        // Both encodings auto-detect through the same entry point and
+        // decode to identical trees.
+        assert_eq!(from_binary(&raw).unwrap(), stmts);
+        assert_eq!(from_binary(&compressed).unwrap(), stmts);
+    }
+
+    #[test]
+    fn parser_from_file() -> Res<()> {
+        let mut parser = check!(Parser::from_file("../tests/test.cd")) as Parser;
+        assert!(matches!(parser.src, CodeSource::File(ref path) if path.ends_with("test.cd")));
+        check!(parser.parse());
+
+        // A missing file is a report, not a panic, and it names the path.
+        let Err(err) = Parser::from_file("../tests/no-such-file.cd") else {
+            panic!("expected reading a missing file to fail");
+        };
+        assert!(err.message.contains("no-such-file.cd"));
+        assert!(matches!(err.position.src, CodeSource::File(_)));
+        Ok(())
+    }
+
+    #[test]
+    fn binary_hostile_lengths() {
+        use crate::bin::BinError;
+
+        // A payload that's just a huge collection length, with a valid
+        // header and CRC so it reaches the decoder: the length must be
+        // rejected, not allocated.
+        let payload = u64::MAX.to_le_bytes();
+        let crc = {
+            // Matches the (private) bin::crc32 — recomputed here so the
+            // fixture assembles a fully "valid" hostile file.
+            let mut crc = 0xFFFF_FFFFu32;
+            for &byte in &payload {
+                crc ^= byte as u32;
+                for _ in 0..8 {
+                    let mask = (crc & 1).wrapping_neg();
+                    crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+                }
+            }
+            !crc
+        };
+        let mut hostile = b"CDT\0\x03\x00\x00".to_vec();
+        hostile.extend_from_slice(&payload);
+        hostile.extend_from_slice(&crc.to_le_bytes());
+        assert!(matches!(
+            from_binary(&hostile),
+            Err(BinError::LengthOverflow { .. })
+        ));
+
+        // Fuzz-ish: deterministic pseudo-random buffers never panic.
+        let mut state = 0x9E37_79B9u32;
+        for len in 0..256usize {
+            let buf: Vec<u8> = (0..len)
+                .map(|_| {
+                    state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+                    (state >> 24) as u8
+                })
+                .collect();
+            let _ = from_binary(&buf);
+        }
+    }
+
+    #[test]
+    fn binary_version_migration() {
+        use crate::bin::BinError;
+
+        let mut parser = Parser::new_inline("let a = 1\nprintln(a)");
+        let stmts = check!(parser.parse());
+        let current = to_binary(stmts.clone()).unwrap();
+
+        // A synthetic v1 blob (the payload layout never changed; only
+        // the header version did) climbs the ladder and loads.
+        let mut v1 = current.clone();
+        v1[4] = 1;
+        v1[5] = 0;
+        assert_eq!(from_binary(&v1).unwrap(), stmts);
+        let mut v2 = current.clone();
+        v2[4] = 2;
+        assert_eq!(from_binary(&v2).unwrap(), stmts);
+
+        // A version below the earliest rung can't load...
+        let mut v0 = current.clone();
+        v0[4] = 0;
+        assert!(matches!(
+            from_binary(&v0),
+            Err(BinError::VersionMismatch { found: 0 })
+        ));
+
+        // ...and files from the future still refuse outright.
+        let mut newer = current.clone();
+        newer[4] = 0xFF;
+        newer[5] = 0xFF;
+        assert!(matches!(
+            from_binary(&newer),
+            Err(BinError::VersionMismatch { found: 0xFFFF })
+        ));
+    }
+
+    #[test]
+    fn binary_source_maps() -> Res<()> {
+        use crate::bin::{from_binary_with_map, to_binary_with_map};
+
+        let mut parser = check!(Parser::from_file("../tests/test.cd")) as Parser;
+        let stmts = check!(parser.parse());
+
+        // The map records every top-level statement's original area...
+        let bytes = to_binary_with_map(stmts.clone(), false).unwrap();
+        let (decoded, map) = from_binary_with_map(&bytes).unwrap();
+        assert_eq!(decoded, stmts);
+        let map = map.expect("a debug build carries its map");
+        assert_eq!(map.len(), stmts.len());
+        assert!(map.iter().zip(&stmts).all(|(area, stmt)| *area == stmt.area));
+
+        // ...plain `from_binary` still reads a mapped file, skipping the
+        // table...
+        assert_eq!(from_binary(&bytes).unwrap(), stmts);
+
+        // ...and stripping yields byte-for-byte `to_binary` output.
+        let stripped = to_binary_with_map(stmts.clone(), true).unwrap();
+        assert_eq!(stripped, to_binary(stmts.clone()).unwrap());
+        let (_, map) = from_binary_with_map(&stripped).unwrap();
+        assert!(map.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn binary_streaming() {
+        use std::io::BufReader;
+
+        use crate::bin::BinaryReader;
+
+        let mut parser = check!(Parser::from_file("../tests/test.cd")) as Parser;
+        let stmts = check!(parser.parse());
+        let bytes = to_binary(stmts).unwrap();
+        let streamed_path: PathBuf = "./target/streamed.cdt".into();
+        File::create(&streamed_path).unwrap().write_all(&bytes).unwrap();
+
+        // Streaming through a BufReader yields the same statements as
+        // decoding the whole blob at once.
+        let reader = BinaryReader::new(BufReader::new(File::open(&streamed_path).unwrap())).unwrap();
+        let streamed: Vec<_> = reader.map(|stmt| check!(stmt)).collect();
+        assert_eq!(streamed, from_binary(&bytes).unwrap());
+
+        // A stream chopped mid-statement surfaces a clean error item.
+        let reader = BinaryReader::new(&bytes[..bytes.len() - bytes.len() / 3]).unwrap();
+        let results: Vec<_> = reader.collect();
+        assert!(results.last().unwrap().is_err());
+    }
+
     #[test]
     fn binary_parsing() {
         let path: PathBuf = "target/file.cdt".into();
@@ -496,6 +2785,37 @@ type { }
         print!("{stmts:#?}")
     }
 
+    #[test]
+    fn frontmatter_blocks() -> Res<()> {
+        // A leading block parses into FileMeta and the code after it is
+        // untouched, spans staying absolute.
+        let src = "---\nname: tool\nversion: 0.1\ndeps: core, std.io\n---\nlet a = 1\nprintln(a)";
+        let mut parser = Parser::new_inline(src);
+        let meta = parser.meta.clone().expect("frontmatter should parse");
+        assert_eq!(meta.get("name"), Some("tool"));
+        assert_eq!(meta.get("version"), Some("0.1"));
+        assert_eq!(meta.get("deps"), Some("core, std.io"));
+        assert_eq!(meta.get("missing"), None);
+        let stmts = check!(parser.parse());
+        assert_eq!(stmts.len(), 2);
+        assert_eq!(
+            &src[stmts[0].area.span.0..stmts[0].area.span.1],
+            "let a = 1"
+        );
+
+        // Without a block, nothing changes.
+        let mut parser = Parser::new_inline("let a = 1\nprintln(a)");
+        assert!(parser.meta.is_none());
+        assert_eq!(check!(parser.parse()).len(), 2);
+
+        // `---` anywhere but the very top is not frontmatter.
+        let mut parser = Parser::new_inline("let a = 1\n---\nkey: value\n---");
+        assert!(parser.meta.is_none());
+        let (_, errors) = parser.parse_all_recovering();
+        assert!(!errors.is_empty());
+        Ok(())
+    }
+
     #[test]
     fn stmt_module() -> Res<()> {
         let mut parser = Parser::new_inline(
@@ -533,28 +2853,261 @@ module test
     }
 
     #[test]
-    fn for_statement() -> Res<()> {
-        let mut parser = Parser::new_inline(
-            r#"
-for i in 0..10 {
-    for nested in 0..i {
-        println("hello!")
-    }
-}
+    fn escape_validation() -> Res<()> {
+        // Bad hex digits in the fixed form point at exactly the escape.
+        let src = r#"let s = "ab\uZZZZcd""#;
+        let mut parser = Parser::new_inline(src);
+        let err = parser.parse_statement().unwrap_err();
+        assert!(err.message.contains("four hex digits"));
+        assert_eq!(err.position.span.0, src.find(r"\u").unwrap());
 
-for ele in nil {
-    println(ele)
-}
+        // The braced form rejects out-of-range scalars...
+        let mut parser = Parser::new_inline(r#"let s = "\u{110000}""#);
+        let err = parser.parse_statement().unwrap_err();
+        assert!(err.message.contains("outside the Unicode scalar range"));
 
-for 'char' in 'chars' {
+        // ...empty or non-hex contents...
+        let mut parser = Parser::new_inline(r#"let s = "\u{}""#);
+        let err = parser.parse_statement().unwrap_err();
+        assert!(err.message.contains("not a hexadecimal scalar"));
 
-}
-        "#
-            .trim(),
-        );
+        // ...and a missing closing brace.
+        let mut parser = Parser::new_inline(r#"let s = "\u{1F60""#);
+        let err = parser.parse_statement().unwrap_err();
+        assert!(err.message.contains("unterminated"));
 
-        check!(parser.parse_statement());
-        check!(parser.parse_statement());
+        // A valid braced escape decodes, and raw strings stay exempt.
+        let mut parser = Parser::new_inline(r#"let s = "\u{1F600}""#);
+        let stmt = check!(parser.parse_statement());
+        let StatementKind::Let { value: Some(value), .. } = &stmt.kind else {
+            panic!("expected a let statement");
+        };
+        let ExprKind::Literal(lit) = &value.kind else {
+            panic!("expected a string literal");
+        };
+        assert_eq!(lit.str_value().unwrap(), "😀");
+        let mut parser = Parser::new_inline(r#"let s = r"\uZZZZ""#);
+        check!(parser.parse_statement());
+
+        // A dangling `\` eats the closing quote, so the string never
+        // terminates and the lexer reports it.
+        let mut parser = Parser::new_inline("let s = \"abc\\\"");
+        let (_, errors) = parser.parse_all_recovering();
+        assert!(!errors.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn inclusive_ranges() -> Res<()> {
+        // `0..=10` is one `..=` token, not `0` `..` `=` `10`.
+        let mut lexer = Token::lexer("0..=10");
+        assert_eq!(lexer.next(), Some(Ok(Token::Int(0))));
+        assert_eq!(lexer.next(), Some(Ok(Token::DotDotEq)));
+        assert_eq!(lexer.next(), Some(Ok(Token::Int(10))));
+
+        let mut parser = Parser::new_inline("let r = 1..=5");
+        let stmt = check!(parser.parse_statement());
+        let StatementKind::Let { value: Some(value), .. } = stmt.kind else {
+            panic!("expected a let statement, got {:?}", stmt.kind);
+        };
+        assert!(matches!(
+            value.kind,
+            ExprKind::Range { inclusive: true, .. }
+        ));
+
+        let mut parser = Parser::new_inline("for i in 0..=n { println(i) }");
+        check!(parser.parse_statement());
+        Ok(())
+    }
+
+    #[test]
+    fn raw_strings() -> Res<()> {
+        // `r"\n"` denotes a backslash and an `n` — two characters, no
+        // escape processing.
+        let mut parser = Parser::new_inline(r#"let path = r"C:\temp\no\escapes""#);
+        let stmt = check!(parser.parse_statement());
+        let StatementKind::Let { value: Some(value), .. } = &stmt.kind else {
+            panic!("expected a let statement, got {:?}", stmt.kind);
+        };
+        let ExprKind::Literal(lit) = &value.kind else {
+            panic!("expected a literal, got {:?}", value.kind);
+        };
+        assert_eq!(lit.str_value().unwrap(), r"C:\temp\no\escapes");
+
+        let mut lexer = Token::lexer(r#"r"\n""#);
+        let Some(Ok(Token::Str(raw))) = lexer.next() else {
+            panic!("expected a raw string token");
+        };
+        assert_eq!(Literal::Str(raw).str_value().unwrap(), "\\n");
+
+        // Hashed delimiters let quotes appear inside...
+        let mut lexer = Token::lexer(r###"r#"with "quotes" inside"#"###);
+        let Some(Ok(Token::Str(raw))) = lexer.next() else {
+            panic!("expected a raw string token");
+        };
+        assert_eq!(
+            Literal::Str(raw).str_value().unwrap(),
+            r#"with "quotes" inside"#
+        );
+
+        // ...while the quoted form still processes its escapes.
+        assert_eq!(
+            Literal::Str("\"a\\nb\"".to_owned()).str_value().unwrap(),
+            "a\nb"
+        );
+
+        // An unterminated raw string is an error token, not a hang.
+        assert_eq!(Token::lexer(r#"r"never ends"#).next(), Some(Err(())));
+        Ok(())
+    }
+
+    #[test]
+    fn regex_literals() -> Res<()> {
+        // A `/` where an expression starts opens a regex literal, flags
+        // and all...
+        let mut parser = Parser::new_inline("let re = /[a-z]+[0-9]*/im");
+        let stmt = check!(parser.parse_statement());
+        let StatementKind::Let { value: Some(value), .. } = &stmt.kind else {
+            panic!("expected a let statement, got {:?}", stmt.kind);
+        };
+        let ExprKind::Literal(Literal::Regex { pattern, flags }) = &value.kind else {
+            panic!("expected a regex literal, got {:?}", value.kind);
+        };
+        assert_eq!(pattern, "[a-z]+[0-9]*");
+        assert_eq!(flags, "im");
+
+        // ...an escaped delimiter stays inside the pattern...
+        let mut parser = Parser::new_inline(r"let re = /a\/b/");
+        let stmt = check!(parser.parse_statement());
+        let StatementKind::Let { value: Some(value), .. } = &stmt.kind else {
+            panic!("expected a let statement, got {:?}", stmt.kind);
+        };
+        assert!(matches!(
+            &value.kind,
+            ExprKind::Literal(Literal::Regex { pattern, .. }) if pattern == r"a\/b"
+        ));
+
+        // ...and a `/` after a value is still division.
+        let mut parser = Parser::new_inline("let x = a / b / c");
+        let stmt = check!(parser.parse_statement());
+        let StatementKind::Let { value: Some(value), .. } = &stmt.kind else {
+            panic!("expected a let statement, got {:?}", stmt.kind);
+        };
+        assert!(matches!(
+            value.kind,
+            ExprKind::Binary(BinOp::Div, ..)
+        ));
+
+        // Unknown flags reject regardless of features.
+        let mut parser = Parser::new_inline("let re = /abc/q");
+        let err = parser.parse_statement().unwrap_err();
+        assert!(err.message.contains("unknown regex flag `q`"));
+
+        // A malformed pattern is caught at parse time under the `regex`
+        // feature, with the literal's span.
+        #[cfg(feature = "regex")]
+        {
+            let mut parser = Parser::new_inline("let re = /[a-z/");
+            let err = parser.parse_statement().unwrap_err();
+            assert!(err.message.contains("invalid regex literal"));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn string_interpolation() -> Res<()> {
+        use crate::ast::StringPart;
+
+        let mut parser = Parser::new_inline(r#""${a + b} and ${c}""#);
+        let expr = check!(parser.parse_expression());
+        let ExprKind::InterpolatedString(parts) = &expr.kind else {
+            panic!("expected an interpolated string, got {:?}", expr.kind);
+        };
+        assert_eq!(parts.len(), 3);
+        assert!(matches!(
+            &parts[0],
+            StringPart::Expr(Expr { kind: ExprKind::Binary(BinOp::Add, ..), .. })
+        ));
+        assert!(matches!(&parts[1], StringPart::Text(text) if text == " and "));
+        assert!(matches!(
+            &parts[2],
+            StringPart::Expr(Expr { kind: ExprKind::Ident(name), .. }) if name == "c"
+        ));
+
+        // Nested braces inside the interpolation balance correctly.
+        let mut parser = Parser::new_inline(r#""${ {value: 1}.value }""#);
+        let expr = check!(parser.parse_expression());
+        assert!(matches!(expr.kind, ExprKind::InterpolatedString(ref p) if p.len() == 1));
+
+        // An escaped `\${` stays a plain literal...
+        let mut parser = Parser::new_inline(r#""costs \${price}""#);
+        let expr = check!(parser.parse_expression());
+        assert!(matches!(expr.kind, ExprKind::Literal(Literal::Str(_))));
+
+        // ...and a dangling `${` is a real error.
+        let mut parser = Parser::new_inline(r#""${oops""#);
+        let err = parser.parse_expression().unwrap_err();
+        assert!(err.message.contains("unterminated"));
+        Ok(())
+    }
+
+    #[test]
+    fn range_steps() -> Res<()> {
+        let mut parser = Parser::new_inline("for i in 0..10 step 2 { println(i) }");
+        let stmt = check!(parser.parse_statement());
+        let StatementKind::For { iterable, .. } = &stmt.kind else {
+            panic!("expected a for statement, got {:?}", stmt.kind);
+        };
+        assert!(matches!(
+            iterable.kind,
+            ExprKind::Range { ref step, inclusive: false, .. } if step.is_some()
+        ));
+
+        // Descending with a negative step.
+        let mut parser = Parser::new_inline("for i in 10..0 step -1 { println(i) }");
+        check!(parser.parse_statement());
+
+        // No step is still a range, defaulting to one.
+        let mut parser = Parser::new_inline("let r = 1..=5");
+        check!(parser.parse_statement());
+
+        // A literal zero step can never advance.
+        let parser = Parser::new_inline("for i in 0..10 step 0 { println(i) }");
+        let validator = Validator::from(&parser);
+        let reports = parser.then_pipe(validator).finish_pipeline().unwrap_err();
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].message.contains("step of zero"));
+
+        // `step` on the next line belongs to the next statement.
+        let mut parser = Parser::new_inline("let r = 0..3\nstep(r)");
+        let stmts = check!(parser.parse());
+        assert_eq!(stmts.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn for_statement() -> Res<()> {
+        let mut parser = Parser::new_inline(
+            r#"
+for i in 0..10 {
+    for nested in 0..i {
+        println("hello!")
+    }
+}
+
+for ele in nil {
+    println(ele)
+}
+
+for 'char' in 'chars' {
+
+}
+        "#
+            .trim(),
+        );
+
+        check!(parser.parse_statement());
+        check!(parser.parse_statement());
         check!(parser.parse_statement());
 
         Ok(())
@@ -589,6 +3142,144 @@ while true ? true : false {
         Ok(())
     }
 
+    #[test]
+    fn if_let_and_while_let() -> Res<()> {
+        use crate::ast::StatementKind::{IfLet, WhileLet};
+
+        // The two forms nest freely, each binding scoped to its body.
+        let src = r#"
+fn next() { return 1 }
+fn maybe() { return nil }
+while let item = next() {
+    if let inner = maybe() {
+        println(item + inner)
+    } else {
+        println(item)
+    }
+}
+        "#
+        .trim();
+        let mut parser = Parser::new_inline(src);
+        let stmts = check!(parser.parse());
+        let WhileLet { binding, body, .. } = &stmts[2].kind else {
+            panic!("expected a while-let, got {:?}", stmts[2].kind);
+        };
+        assert_eq!(binding, "item");
+        let IfLet { binding, otherwise, .. } = &body[0].kind else {
+            panic!("expected an if-let, got {:?}", body[0].kind);
+        };
+        assert_eq!(binding, "inner");
+        assert!(otherwise.is_some());
+
+        // The validator scopes each binding to its block...
+        let parser = Parser::new_inline(src);
+        let validator = Validator::from(&parser);
+        assert!(parser.then_pipe(validator).finish_pipeline().is_ok());
+
+        // ...so using it after the block is an undefined symbol.
+        let parser = Parser::new_inline(
+            "fn maybe() { return nil }\nif let x = maybe() {\n    println(x)\n}\nprintln(x)",
+        );
+        let validator = Validator::from(&parser);
+        let reports = parser.then_pipe(validator).finish_pipeline().unwrap_err();
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].message.contains("undefined symbol `x`"));
+
+        // `break` targets a labeled `while let` like any other loop.
+        let parser = Parser::new_inline(
+            "fn next() { return 1 }\nouter: while let x = next() {\n    println(x)\n    break outer\n}",
+        );
+        let validator = Validator::from(&parser);
+        assert!(parser.then_pipe(validator).finish_pipeline().is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn loop_expressions_and_valued_break() -> Res<()> {
+        use crate::ast::StatementKind::Break;
+
+        // `break 5` carries its value...
+        let mut parser = Parser::new_inline("while true {\n    break 5\n}");
+        let stmt = check!(parser.parse_statement());
+        let StatementKind::While { body, .. } = &stmt.kind else {
+            panic!("expected a while statement, got {:?}", stmt.kind);
+        };
+        let Break { label, value: Some(value) } = &body[0].kind else {
+            panic!("expected a valued break, got {:?}", body[0].kind);
+        };
+        assert!(label.is_none());
+        assert!(matches!(value.kind, ExprKind::Literal(Literal::Int(5, _))));
+
+        // ...a label can ride along...
+        let mut parser = Parser::new_inline("outer: while true {\n    break outer 5\n}");
+        let stmt = check!(parser.parse_statement());
+        let StatementKind::While { body, .. } = &stmt.kind else {
+            panic!("expected a while statement, got {:?}", stmt.kind);
+        };
+        assert!(matches!(
+            &body[0].kind,
+            Break { label: Some(label), value: Some(_) } if label == "outer"
+        ));
+
+        // ...and a loop in expression position wraps the statement.
+        let mut parser = Parser::new_inline("let x = while true {\n    break 5\n}");
+        let stmt = check!(parser.parse_statement());
+        let StatementKind::Let { value: Some(value), .. } = &stmt.kind else {
+            panic!("expected a let statement, got {:?}", stmt.kind);
+        };
+        let ExprKind::Loop(inner) = &value.kind else {
+            panic!("expected a loop expression, got {:?}", value.kind);
+        };
+        assert!(matches!(inner.kind, StatementKind::While { .. }));
+
+        // Valued breaks in one loop must agree on their type; the loop's
+        // value feeds the annotation check.
+        let parser = Parser::new_inline(
+            "let x: num = while true {\n    break 5\n}\nprintln(x)",
+        );
+        let validator = Validator::from(&parser);
+        assert!(parser.then_pipe(validator).finish_pipeline().is_ok());
+
+        let parser = Parser::new_inline(
+            "let cond = true\nlet x = while cond {\n    if cond {\n        break 5\n    }\n    break \"str\"\n}\nprintln(x)",
+        );
+        let validator = Validator::from(&parser);
+        let reports = parser.then_pipe(validator).finish_pipeline().unwrap_err();
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].message.contains("type mismatch"));
+        Ok(())
+    }
+
+    #[test]
+    fn do_while_statement() -> Res<()> {
+        let mut parser = Parser::new_inline(
+            r#"
+do {
+    do {
+        break
+    } while false
+} while true
+
+do {
+    continue
+} while flag ? true : false
+        "#
+            .trim(),
+        );
+        let stmt = check!(parser.parse_statement());
+        let StatementKind::DoWhile { body, .. } = &stmt.kind else {
+            panic!("expected a do/while, got {:?}", stmt.kind);
+        };
+        assert!(matches!(body[0].kind, StatementKind::DoWhile { .. }));
+        check!(parser.parse_statement());
+
+        // `break`/`continue` inside count as being in a loop.
+        let parser = Parser::new_inline("do {\n    break\n} while true");
+        let validator = Validator::from(&parser);
+        assert!(parser.then_pipe(validator).finish_pipeline().is_ok());
+        Ok(())
+    }
+
     #[test]
     fn throw_statement() -> Res<()> {
         let mut parser = Parser::new_inline(
@@ -609,6 +3300,47 @@ throw {}
         Ok(())
     }
 
+    #[test]
+    fn stmt_assert() -> Res<()> {
+        let mut parser = Parser::new_inline("assert x > 0");
+        let stmt = check!(parser.parse_statement());
+        assert!(matches!(
+            stmt.kind,
+            StatementKind::Assert { ref rhs, ref message, .. }
+                if rhs.is_none() && message.is_none()
+        ));
+
+        let mut parser = Parser::new_inline(r#"assert_eq a, b, "mismatch""#);
+        let stmt = check!(parser.parse_statement());
+        let StatementKind::Assert { rhs, message, .. } = &stmt.kind else {
+            panic!("expected an assert statement, got {:?}", stmt.kind);
+        };
+        assert!(rhs.is_some());
+        assert!(matches!(
+            message.as_ref().unwrap().kind,
+            ExprKind::Literal(Literal::Str(_))
+        ));
+
+        // `assert_eq` needs both operands.
+        let mut parser = Parser::new_inline("assert_eq a");
+        assert!(parser.parse_statement().is_err());
+
+        // A message of a known non-string type is a validation error.
+        let parser = Parser::new_inline("let a = 1\nassert a > 0, 123");
+        let validator = Validator::from(&parser);
+        let reports = parser.then_pipe(validator).finish_pipeline().unwrap_err();
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].message.contains("type mismatch"));
+
+        // A string message (and no message at all) both pass.
+        let parser = Parser::new_inline(
+            "let a = 1\nassert a > 0, \"must be positive\"\nassert_eq a, 1",
+        );
+        let validator = Validator::from(&parser);
+        assert!(parser.then_pipe(validator).finish_pipeline().is_ok());
+        Ok(())
+    }
+
     #[test]
     fn try_catch_statement() -> Res<()> {
         let mut parser = Parser::new_inline(
@@ -648,6 +3380,45 @@ try {
         Ok(())
     }
 
+    #[test]
+    fn multi_catch_clauses() -> Res<()> {
+        use crate::ast::CatchPattern;
+
+        // Two types share one clause, still mixing with `*` and `catch?`.
+        let mut parser = Parser::new_inline(
+            r#"
+try {
+    throw nil
+} catch IoError | std.parse.ParseError as e {
+    println(e)
+} catch * as other {
+    println(other)
+} catch? {
+    println("nil")
+}
+"#
+            .trim(),
+        );
+        let stmt = check!(parser.parse_statement());
+        let StatementKind::Try { catches, .. } = &stmt.kind else {
+            panic!("expected a try statement, got {:?}", stmt.kind);
+        };
+        assert_eq!(catches.len(), 3);
+        let CatchPattern::Type(types) = &catches[0].pattern else {
+            panic!("expected a typed catch, got {:?}", catches[0].pattern);
+        };
+        assert_eq!(types.len(), 2);
+        assert_eq!(types[0], ["IoError"]);
+        assert_eq!(types[1], ["std", "parse", "ParseError"]);
+        assert!(matches!(catches[1].pattern, CatchPattern::Any));
+        assert!(matches!(catches[2].pattern, CatchPattern::Nil));
+
+        // A dangling `|` with no type after it is an error.
+        let mut parser = Parser::new_inline("try { } catch IoError | as e { }");
+        assert!(parser.parse_statement().is_err());
+        Ok(())
+    }
+
     #[test]
     fn export_statement() -> Res<()> {
         let mut parser = Parser::new_inline(
@@ -666,6 +3437,220 @@ export __self__
         Ok(())
     }
 
+    #[test]
+    fn sexpr_snapshot() -> Res<()> {
+        use crate::ast::to_sexpr;
+
+        let mut parser = Parser::new_inline(
+            r#"
+fn f(a, b = 2) {
+    let x = 1 + d()
+    if x { return x ** 2 } else { throw nil }
+}
+let pick = flag ? [1, 2] : (3,)
+        "#
+            .trim(),
+        );
+        let stmts = check!(parser.parse());
+        // Any grammar change that reshapes these nodes shows up as a
+        // one-line diff here.
+        assert_eq!(
+            to_sexpr(&stmts),
+            "(fn f (a (= b 2)) (let x (+ 1 (call d))) (if (x (return (** x 2))) (else (throw nil))))\n\
+             (let pick (if? flag (array 1 2) (tuple 3)))"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn formatting_is_idempotent() -> Res<()> {
+        use crate::fmt::format;
+
+        let mut parser = Parser::new_inline(
+            r#"
+fn greet(name:str):str{let msg="Hello, "+name
+return msg}
+let pick=(flag)=>{return flag?[1,2,3,]:{nested:{abc:123,},}}
+let range = 0..10
+const BIG=1_000*2
+        "#
+            .trim(),
+        );
+        let stmts = check!(parser.parse());
+        let once = format(&stmts);
+
+        // Spacing and indentation are normalized...
+        assert!(once.contains("fn greet(name: str): str {"));
+        assert!(once.contains("    let msg = \"Hello, \" + name"));
+        assert!(once.contains("0..10"));
+
+        // ...and reformatting the formatter's own output changes nothing.
+        let mut parser = Parser::new_inline(&once);
+        let reparsed = check!(parser.parse());
+        assert!(parser.take_errors().is_empty());
+        assert_eq!(format(&reparsed), once);
+        Ok(())
+    }
+
+    #[test]
+    fn walk_mut_rewrites_in_place() -> Res<()> {
+        use std::ops::ControlFlow;
+
+        use crate::ast::{walk_block_mut, walk_expression_mut, VisitorMut};
+
+        struct Rename;
+        impl VisitorMut for Rename {
+            fn visit_expression(&mut self, expr: &mut Expr) -> ControlFlow<()> {
+                if let ExprKind::Ident(name) = &mut expr.kind {
+                    if name == "a" {
+                        "b".clone_into(name);
+                    }
+                }
+                walk_expression_mut(self, expr)
+            }
+        }
+
+        let src = "let a = 1\nfn f(a) {\n    return a + a\n}\nprintln(f(a))";
+        let mut parser = Parser::new_inline(src);
+        let mut stmts = check!(parser.parse());
+        let spans_before: Vec<_> = stmts.iter().map(|s| s.area.span).collect();
+        let _ = walk_block_mut(&mut Rename, &mut stmts);
+
+        // Every identifier `a` became `b` — bindings are names on
+        // statements, not expressions, so they stay (the pass rewrote
+        // reads, which is exactly what it visited)...
+        let formatted = crate::fmt::format(&stmts);
+        assert!(formatted.contains("return b + b"));
+        assert!(formatted.contains("println(f(b))"));
+
+        // ...and no span moved.
+        let spans_after: Vec<_> = stmts.iter().map(|s| s.area.span).collect();
+        assert_eq!(spans_before, spans_after);
+        Ok(())
+    }
+
+    #[test]
+    fn visitor_walks_and_short_circuits() -> Res<()> {
+        use std::ops::ControlFlow;
+
+        use crate::ast::{walk_block, walk_expression, Visitor};
+
+        // Counts identifier references, stopping early at a sentinel.
+        struct IdentCounter {
+            count: usize,
+            stop_at: &'static str,
+        }
+
+        impl Visitor for IdentCounter {
+            fn visit_expression(&mut self, expr: &Expr) -> ControlFlow<()> {
+                if let ExprKind::Ident(name) = &expr.kind {
+                    if name == self.stop_at {
+                        return ControlFlow::Break(());
+                    }
+                    self.count += 1;
+                }
+                walk_expression(self, expr)
+            }
+        }
+
+        let mut parser = Parser::new_inline(
+            r#"
+fn f(a) {
+    let b = a + c
+    if b { println(stop + d) }
+}
+        "#
+            .trim(),
+        );
+        let stmts = check!(parser.parse());
+
+        // `a`, `c`, `b`, `println` are counted, then `stop` breaks the
+        // walk before `d` is ever visited.
+        let mut counter = IdentCounter { count: 0, stop_at: "stop" };
+        assert_eq!(walk_block(&mut counter, &stmts), ControlFlow::Break(()));
+        assert_eq!(counter.count, 4);
+
+        let mut counter = IdentCounter { count: 0, stop_at: "<none>" };
+        assert_eq!(walk_block(&mut counter, &stmts), ControlFlow::Continue(()));
+        assert_eq!(counter.count, 6);
+        Ok(())
+    }
+
+    #[test]
+    fn circular_imports() {
+        use crate::validate::check_import_cycles;
+
+        // a → b → c → a: three inline "modules" resolved from a map.
+        let modules: std::collections::HashMap<&str, &str> = [
+            ("a", "import b"),
+            ("b", "import c"),
+            ("c", "import a"),
+        ]
+        .into();
+        let resolve = |_from: &CodeSource, path: &str| {
+            modules
+                .get(path)
+                .map(|text| (CodeSource::File(path.into()), (*text).to_owned()))
+        };
+
+        let root = CodeSource::File("a".into());
+        let reports = check_import_cycles(&root, modules["a"], &resolve);
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].message.contains("a → b → c → a"));
+        // One label per import statement along the cycle, plus the
+        // closing one.
+        assert!(reports[0].labels.len() >= 3);
+
+        // A diamond (a → b, a → c, b → d, c → d) is not a cycle.
+        let modules: std::collections::HashMap<&str, &str> = [
+            ("a", "import b\nimport c"),
+            ("b", "import d"),
+            ("c", "import d"),
+            ("d", ""),
+        ]
+        .into();
+        let resolve = |_from: &CodeSource, path: &str| {
+            modules
+                .get(path)
+                .map(|text| (CodeSource::File(path.into()), (*text).to_owned()))
+        };
+        let root = CodeSource::File("a".into());
+        assert!(check_import_cycles(&root, modules["a"], &resolve).is_empty());
+    }
+
+    #[test]
+    fn module_resolvers() {
+        use crate::validate::{check_import_cycles, FsResolver, ModuleResolver};
+
+        // An in-memory resolver backs imports with a plain map.
+        struct MemoryResolver(std::collections::HashMap<&'static str, &'static str>);
+
+        impl ModuleResolver for MemoryResolver {
+            fn resolve(&self, _from: &CodeSource, path: &str) -> Option<(CodeSource, String)> {
+                self.0
+                    .get(path)
+                    .map(|text| (CodeSource::File(path.into()), (*text).to_owned()))
+            }
+        }
+
+        let memory = MemoryResolver([("x", "import y"), ("y", "import x")].into());
+        let reports =
+            check_import_cycles(&CodeSource::File("x".into()), "import y", &memory);
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].message.contains("x → y → x"));
+
+        // The filesystem resolver loads quoted paths relative to the
+        // importing file, and leaves dotted module paths alone.
+        let from = CodeSource::File("../tests/main.cd".into());
+        let resolved = FsResolver.resolve(&from, "'test.cd'");
+        assert!(matches!(
+            resolved,
+            Some((CodeSource::File(ref path), ref text))
+                if path.ends_with("test.cd") && !text.is_empty()
+        ));
+        assert!(FsResolver.resolve(&from, "std.io").is_none());
+    }
+
     #[test]
     fn validation() -> Res<()> {
         let parser = Parser::new_inline(
@@ -682,7 +3667,7 @@ fn hello(name) {
     println("Hello, " + undefined)
 }
 
-let nil_check = nil!!
+let _nil_check = nil!!
 
 // attempting to reassign constant here
 abc = true
@@ -693,11 +3678,604 @@ if false {
 "#
             .trim(),
         );
+        let validator = Validator::from(&parser).implicit_core(true);
+        // `undefined` is never declared, `abc` is a reassigned const, and
+        // `import std.io` is never referenced (`println` is a builtin), so
+        // this source is expected to fail validation with all three
+        // reports. `import core` is exempted by `implicit_core`.
+        let reports = parser.then_pipe(validator).finish_pipeline().unwrap_err();
+        assert_eq!(reports.len(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn unused_imports() -> Res<()> {
+        let parser = Parser::new_inline(
+            r#"
+import std.io as io
+import std.ffi
+import std.net
+export std.net
+
+let _x = io.read()
+"#
+            .trim(),
+        );
+        let validator = Validator::from(&parser);
+        // `io` is referenced, `std.net` is re-exported; only `std.ffi`
+        // should be flagged, as a warning rather than a hard error.
+        let reports = parser.then_pipe(validator).finish_pipeline().unwrap_err();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].code, "W01");
+        assert!(reports[0].message.contains("unused import `std.ffi`"));
+        Ok(())
+    }
+
+    #[test]
+    fn match_statement_position() -> Res<()> {
+        // `match` is an expression, so it works in statement position too;
+        // note the trailing comma after the last arm.
+        let mut parser = Parser::new_inline(
+            r#"
+match x {
+    1 => "one",
+    other => other,
+    _ => "many",
+}
+        "#
+            .trim(),
+        );
+        let stmt = check!(parser.parse_statement());
+        assert!(matches!(
+            stmt.kind,
+            StatementKind::Expr(Expr {
+                kind: ExprKind::Match { ref arms, .. },
+                ..
+            }) if arms.len() == 3
+        ));
+
+        // An arm without its `=>` gets a dedicated diagnostic.
+        let mut parser = Parser::new_inline("match x { 1 \"one\" }");
+        let err = parser.parse_statement().unwrap_err();
+        assert!(err.message.contains("=>"));
+        Ok(())
+    }
+
+    #[test]
+    fn shadowing_warnings() -> Res<()> {
+        let parser = Parser::new_inline(
+            r#"
+let x = 1
+if x == 1 {
+    let x = 2
+    println(x)
+}
+for x in 0..3 {
+    println(x)
+}
+        "#
+            .trim(),
+        );
+        let validator = Validator::from(&parser);
+        // The `if` body's `let x` and the `for` binding both shadow the
+        // outer `x`; each warning carries both definition sites.
+        let reports = parser.then_pipe(validator).finish_pipeline().unwrap_err();
+        assert_eq!(reports.len(), 2);
+        assert!(reports.iter().all(|r| r.code == "W03"));
+        assert!(reports.iter().all(|r| r.labels.len() == 2));
+        Ok(())
+    }
+
+    #[test]
+    fn scopes_resolution() {
+        use crate::validate::{BindKind, Scopes};
+
+        let area = CodeArea {
+            src: CodeSource::Inline(String::new()),
+            span: (0, 1),
+        };
+        let mut scopes: Scopes<u32> = Scopes::new();
+        assert!(scopes.define("a", BindKind::Let, area.clone(), 1).is_none());
+        scopes.push();
+        // Shadowing reports the outer site; resolution prefers the inner.
+        assert!(scopes.define("a", BindKind::Param, area.clone(), 2).is_some());
+        assert_eq!(scopes.resolve("a").map(|d| d.data), Some(2));
+        scopes.pop();
+        assert_eq!(scopes.resolve("a").map(|d| d.data), Some(1));
+        assert_eq!(scopes.resolve("a").map(|d| d.kind), Some(BindKind::Let));
+    }
+
+    #[test]
+    fn structural_type_checker() -> Res<()> {
+        use crate::validate::TypeChecker;
+
+        // Conforming annotations pass: scalars, arrays, compound shapes,
+        // parameter defaults, and annotated returns.
+        let src = r#"
+let n: num = 1
+let s: str = "hello"
+let xs: num[] = [1, 2, 3]
+let shape: type { hello: num, world: str } = { world: "w", hello: 1 }
+fn greet(name: str = "you"): str {
+    return "hi"
+}
+        "#
+        .trim();
+        let parser = Parser::new_inline(src);
+        let checker = TypeChecker::from(&parser);
+        assert!(parser.then_pipe(checker).finish_pipeline().is_ok());
+
+        // Non-conforming ones report each mismatch.
+        let src = r#"
+let n: num = "oops"
+let xs: num[] = ["a"]
+fn f(): num {
+    return "nope"
+}
+        "#
+        .trim();
+        let parser = Parser::new_inline(src);
+        let checker = TypeChecker::from(&parser);
+        let reports = parser.then_pipe(checker).finish_pipeline().unwrap_err();
+        assert_eq!(reports.len(), 3);
+        assert!(reports
+            .iter()
+            .all(|r| r.message.contains("type mismatch")));
+        Ok(())
+    }
+
+    #[test]
+    fn errors_collector() -> Res<()> {
+        use crate::err::Errors;
+
+        // An empty collector settles to Ok...
+        let mut errors = Errors::new();
+        assert!(errors.is_empty());
+        errors.extend(Vec::new());
+        assert!(errors.into_result().is_ok());
+
+        // ...and three independent validation problems all surface in
+        // one batch, in source order.
+        let parser = Parser::new_inline(
+            "const c = 1\nc = 2\nundefined()\nprintln(c)\nbreak",
+        );
+        let validator = Validator::from(&parser);
+        let reports = parser.then_pipe(validator).finish_pipeline().unwrap_err();
+        assert_eq!(reports.len(), 3);
+        assert!(reports[0].message.contains("cannot reassign const"));
+        assert!(reports[1].message.contains("undefined symbol"));
+        assert!(reports[2].message.contains("`break` outside of a loop"));
+
+        // Pushing reports and settling up carries the whole batch.
+        let mut errors = Errors::new();
+        for report in reports {
+            errors.push(report);
+        }
+        assert_eq!(errors.len(), 3);
+        assert_eq!(errors.into_result().unwrap_err().len(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn deny_warnings_promotes() -> Res<()> {
+        let src = "let unused = 1\nprintln(2)";
+
+        // Allowed: the unused variable stays a warning...
+        let parser = Parser::new_inline(src);
+        let validator = Validator::from(&parser);
+        let reports = parser.then_pipe(validator).finish_pipeline().unwrap_err();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].severity, Severity::Warning);
+        assert_eq!(reports[0].code, "W04");
+
+        // ...denied: same report, promoted to an error, the W-code
+        // telling it apart from a genuine one.
+        let parser = Parser::new_inline(src);
+        let validator = Validator::from(&parser).deny_warnings(true);
+        let reports = parser.then_pipe(validator).finish_pipeline().unwrap_err();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].severity, Severity::Error);
+        assert_eq!(reports[0].code, "W04");
+
+        // A suppressed warning doesn't fail even a denying build.
+        let parser = Parser::new_inline(
+            "// conduct:ignore unused-var\nlet unused = 1\nprintln(2)",
+        );
+        let validator = Validator::from(&parser).deny_warnings(true);
+        assert!(parser.then_pipe(validator).finish_pipeline().is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn constant_conditions() -> Res<()> {
+        // Opted in, `if false { }` warns with the condition labeled...
+        let parser = Parser::new_inline("if false {\n    println(1)\n}");
+        let validator = Validator::from(&parser).check_const_conditions(true);
+        let reports = parser.then_pipe(validator).finish_pipeline().unwrap_err();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].code, "W08");
+        assert!(reports[0].message.contains("always false"));
+
+        // ...folded comparisons count too (`while 1 < 2`)...
+        let parser = Parser::new_inline("while 1 < 2 {\n    break\n}");
+        let validator = Validator::from(&parser).check_const_conditions(true);
+        let reports = parser.then_pipe(validator).finish_pipeline().unwrap_err();
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].message.contains("always true"));
+
+        // ...a variable condition stays silent...
+        let parser = Parser::new_inline("let x = true\nif x {\n    println(1)\n}");
+        let validator = Validator::from(&parser).check_const_conditions(true);
+        assert!(parser.then_pipe(validator).finish_pipeline().is_ok());
+
+        // ...and without the opt-in, nothing fires at all.
+        let parser = Parser::new_inline("if false {\n    println(1)\n}");
+        let validator = Validator::from(&parser);
+        assert!(parser.then_pipe(validator).finish_pipeline().is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn unreachable_branches() -> Res<()> {
+        // A `catch` after a `catch *` can never match.
+        let parser = Parser::new_inline(
+            "try {\n    let a = 1\n    println(a)\n} catch * as e {\n    println(e)\n} catch IoError as io {\n    println(io)\n}",
+        );
+        let validator = Validator::from(&parser);
+        let reports = parser.then_pipe(validator).finish_pipeline().unwrap_err();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].code, "W02");
+        assert!(reports[0].message.contains("after a catch-all"));
+
+        // An `else` after `if true` is dead...
+        let parser = Parser::new_inline("if true {\n    println(1)\n} else {\n    println(2)\n}");
+        let validator = Validator::from(&parser);
+        let reports = parser.then_pipe(validator).finish_pipeline().unwrap_err();
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].message.contains("unreachable `else`"));
+
+        // ...and so is a later `else if` branch.
+        let parser = Parser::new_inline(
+            "let n = 1\nif true {\n    println(1)\n} else if n > 0 {\n    println(2)\n}",
+        );
+        let validator = Validator::from(&parser);
+        let reports = parser.then_pipe(validator).finish_pipeline().unwrap_err();
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].message.contains("always-true"));
+
+        // A computed condition stays conservative (and `if false { .. }`
+        // remains the accepted guard idiom), and ordinary catch arms
+        // don't warn.
+        let parser = Parser::new_inline(
+            "let flag = true\nif flag {\n    println(1)\n} else {\n    println(2)\n}\nif false {\n    println(4)\n}\ntry {\n    println(3)\n} catch IoError as io {\n    println(io)\n} catch * as e {\n    println(e)\n}",
+        );
+        let validator = Validator::from(&parser);
+        assert!(parser.then_pipe(validator).finish_pipeline().is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn identifier_interning() {
+        use std::time::Instant;
+
+        use crate::validate::{BindKind, Interner, Scopes};
+
+        let mut interner = Interner::default();
+        let alpha = interner.intern("alpha");
+        let beta = interner.intern("beta");
+        assert_ne!(alpha, beta);
+        // Interning is stable, and symbols resolve back for messages.
+        assert_eq!(interner.intern("alpha"), alpha);
+        assert_eq!(interner.resolve(alpha), "alpha");
+        assert_eq!(interner.get("beta"), Some(beta));
+        assert_eq!(interner.get("gamma"), None);
+
+        // A synthetic file's worth of identifiers across a deep scope
+        // stack: each resolution hashes its string once, then walks the
+        // frames on `u32`s. Mostly a smoke benchmark — the assert is
+        // about correctness, the timing prints under `--nocapture`.
+        let area = CodeArea {
+            src: CodeSource::Inline(String::new()),
+            span: (0, 1),
+        };
+        let mut scopes: Scopes<()> = Scopes::new();
+        for frame in 0..64 {
+            scopes.push();
+            for i in 0..64 {
+                scopes.define(
+                    &format!("ident_{frame}_{i}"),
+                    BindKind::Let,
+                    area.clone(),
+                    (),
+                );
+            }
+        }
+        let names: Vec<String> = (0..64).map(|i| format!("ident_0_{i}")).collect();
+        let misses: Vec<String> = (0..64).map(|i| format!("missing_{i}")).collect();
+        let start = Instant::now();
+        for _ in 0..100 {
+            for (name, miss) in names.iter().zip(&misses) {
+                // The hit walks all 65 frames; the miss exits after one
+                // interner probe without touching any frame.
+                assert!(scopes.resolve(name).is_some());
+                assert!(scopes.resolve(miss).is_none());
+            }
+        }
+        println!("12.8k deep-scope resolutions in {:?}", start.elapsed());
+    }
+
+    #[test]
+    fn break_outside_loop() -> Res<()> {
+        // A bare top-level `break` has nothing to break out of.
+        let parser = Parser::new_inline("break");
+        let validator = Validator::from(&parser);
+        let reports = parser.then_pipe(validator).finish_pipeline().unwrap_err();
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].message.contains("`break` outside of a loop"));
+
+        // A function boundary resets the loop context, even when the
+        // function is defined inside a loop.
+        let parser = Parser::new_inline(
+            r#"
+while true {
+    let f = () => {
+        continue
+    }
+    f()
+}
+        "#
+            .trim(),
+        );
+        let validator = Validator::from(&parser);
+        let reports = parser.then_pipe(validator).finish_pipeline().unwrap_err();
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].message.contains("`continue` outside of a loop"));
+
+        // Inside a loop both are fine.
+        let parser = Parser::new_inline(
+            r#"
+for i in 0..10 {
+    if i == 5 {
+        break
+    }
+    continue
+}
+        "#
+            .trim(),
+        );
+        let validator = Validator::from(&parser);
+        assert!(parser.then_pipe(validator).finish_pipeline().is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn labeled_loops() -> Res<()> {
+        // Labels parse on both loop forms and thread down to their
+        // `break`/`continue`.
+        let mut parser = Parser::new_inline(
+            r#"
+outer: for i in 0..10 {
+    inner: while true {
+        break outer
+        continue inner
+    }
+}
+        "#
+            .trim(),
+        );
+        let stmt = check!(parser.parse_statement());
+        let StatementKind::For { label, body, .. } = stmt.kind else {
+            panic!("expected a for statement, got {:?}", stmt.kind);
+        };
+        assert_eq!(label.as_deref(), Some("outer"));
+        assert!(matches!(
+            body[0].kind,
+            StatementKind::While { ref label, .. } if label.as_deref() == Some("inner")
+        ));
+
+        // A `break` whose label names no enclosing loop is an error.
+        let parser = Parser::new_inline(
+            r#"
+outer: for i in 0..10 {
+    break elsewhere
+}
+        "#
+            .trim(),
+        );
+        let validator = Validator::from(&parser);
+        let reports = parser.then_pipe(validator).finish_pipeline().unwrap_err();
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].message.contains("label `elsewhere` is not in scope"));
+        Ok(())
+    }
+
+    #[test]
+    fn unused_variables() -> Res<()> {
+        let parser = Parser::new_inline(
+            r#"
+let used = 1
+let never = 2
+let _scratch = 3
+println(used)
+        "#
+            .trim(),
+        );
+        let validator = Validator::from(&parser);
+        // `never` is flagged; the `_`-prefixed one is deliberate by
+        // convention and stays quiet.
+        let reports = parser.then_pipe(validator).finish_pipeline().unwrap_err();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].code, "W04");
+        assert!(reports[0].message.contains("unused variable `never`"));
+
+        // Parameters are exempt unless opted in.
+        let src = "fn f(ignored) { return 1 }\nf(1)";
+        let parser = Parser::new_inline(src);
+        let validator = Validator::from(&parser);
+        assert!(parser.then_pipe(validator).finish_pipeline().is_ok());
+
+        let parser = Parser::new_inline(src);
+        let validator = Validator::from(&parser).check_params(true);
+        let reports = parser.then_pipe(validator).finish_pipeline().unwrap_err();
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].message.contains("unused variable `ignored`"));
+        Ok(())
+    }
+
+    #[test]
+    fn suppression_comments() -> Res<()> {
+        let parser = Parser::new_inline(
+            r#"
+// conduct:ignore unused-var
+let never = 2
+println(xyz)
+        "#
+            .trim(),
+        );
+        let validator = Validator::from(&parser);
+        // The unused-variable warning is suppressed; the hard error for
+        // the undefined assignment target is not.
+        let reports = parser.then_pipe(validator).finish_pipeline().unwrap_err();
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].message.contains("undefined symbol"));
+        assert_eq!(reports[0].severity, Severity::Error);
+
+        // Without the comment the warning is back.
+        let parser = Parser::new_inline("let never = 2\nprintln(xyz)");
         let validator = Validator::from(&parser);
-        // `undefined` is never declared and `abc` is a reassigned const, so
-        // this source is expected to fail validation with both reports.
         let reports = parser.then_pipe(validator).finish_pipeline().unwrap_err();
         assert_eq!(reports.len(), 2);
+        assert!(reports
+            .iter()
+            .any(|r| r.severity == Severity::Warning && r.code == "W04"));
+        Ok(())
+    }
+
+    #[test]
+    fn param_reassignment_lint() -> Res<()> {
+        let src = "fn f(x) {\n    x = 5\n    return x\n}\nf(1)";
+
+        // Off by default...
+        let parser = Parser::new_inline(src);
+        let validator = Validator::from(&parser);
+        assert!(parser.then_pipe(validator).finish_pipeline().is_ok());
+
+        // ...flagged when opted in, with both spans labeled.
+        let parser = Parser::new_inline(src);
+        let validator = Validator::from(&parser).check_param_reassign(true);
+        let reports = parser.then_pipe(validator).finish_pipeline().unwrap_err();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].code, "W06");
+        assert_eq!(reports[0].labels.len(), 2);
+
+        // A `let x` over the parameter is a fresh binding, not a
+        // reassignment: nothing to flag.
+        let src = "fn g(x) {\n    let x = 5\n    return x\n}\ng(1)";
+        let parser = Parser::new_inline(src);
+        let validator = Validator::from(&parser).check_param_reassign(true);
+        assert!(parser.then_pipe(validator).finish_pipeline().is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn use_before_declaration() -> Res<()> {
+        // `x` exists in this scope, but not yet.
+        let parser = Parser::new_inline("println(x)\nlet x = 1\nprintln(x)");
+        let validator = Validator::from(&parser);
+        let reports = parser.then_pipe(validator).finish_pipeline().unwrap_err();
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].message.contains("used before its declaration"));
+        assert_eq!(reports[0].labels.len(), 2);
+
+        // Function declarations hoist, so mutual recursion is fine.
+        let parser = Parser::new_inline(
+            r#"
+fn even(n) {
+    return n == 0 ? true : odd(n - 1)
+}
+
+fn odd(n) {
+    return n == 0 ? false : even(n - 1)
+}
+
+even(4)
+        "#
+            .trim(),
+        );
+        let validator = Validator::from(&parser);
+        assert!(parser.then_pipe(validator).finish_pipeline().is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn unreachable_code() -> Res<()> {
+        let parser = Parser::new_inline(
+            r#"
+fn f() {
+    return 1
+    println("dead")
+    println("also dead")
+}
+        "#
+            .trim(),
+        );
+        let validator = Validator::from(&parser);
+        // One warning for the whole dead tail, pointing at the first dead
+        // statement with the `return` labeled as the cause.
+        let reports = parser.then_pipe(validator).finish_pipeline().unwrap_err();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].code, "W02");
+        assert!(reports[0].message.contains("unreachable"));
+        assert_eq!(reports[0].labels.len(), 2);
+
+        // A `return` that only happens in one branch of an `if` doesn't
+        // make the following statements unreachable.
+        let parser = Parser::new_inline(
+            r#"
+fn g(flag) {
+    if flag {
+        return 1
+    }
+    return 2
+}
+        "#
+            .trim(),
+        );
+        let validator = Validator::from(&parser);
+        assert!(parser.then_pipe(validator).finish_pipeline().is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn non_exhaustive_match() -> Res<()> {
+        let parser = Parser::new_inline(
+            r#"
+let x = 1
+let _y = match x {
+    1 => "a",
+    2 => "b",
+}
+        "#
+            .trim(),
+        );
+        let validator = Validator::from(&parser);
+        // No `_`/binding arm covers the remaining int values, so this
+        // should be flagged as non-exhaustive.
+        let reports = parser.then_pipe(validator).finish_pipeline().unwrap_err();
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].message.contains("non-exhaustive"));
+        Ok(())
+    }
+
+    #[test]
+    fn type_mismatch() -> Res<()> {
+        let parser = Parser::new_inline(r#"let _x: num = "hello""#);
+        let validator = Validator::from(&parser);
+        // `x` is declared `num` but given a `str` value, which should be
+        // reported as a type mismatch rather than silently accepted.
+        let reports = parser.then_pipe(validator).finish_pipeline().unwrap_err();
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].message.contains("type mismatch"));
         Ok(())
     }
 }
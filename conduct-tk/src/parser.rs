@@ -0,0 +1,2633 @@
+//! Recursive-descent parser: turns a [`Token`] stream into an [`ast`] tree.
+//!
+//! The parser never gives up at the first syntax error. Each `parse_*`
+//! entry point tries to recover in place so a single `parse()` call can
+//! surface every problem in a file instead of just the first one; see
+//! [`Parser::synchronize`].
+
+use logos::{Lexer, Logos, Span};
+
+use crate::{
+    ast::*,
+    err::{codes::Code, CodeArea, CodeSource, ErrorReport, Res, Severity},
+    reparse::{retarget_expr, shift_expr},
+    tk::Token,
+    validate::Validator,
+};
+
+/// One lexed token paired with its byte span in the source.
+#[derive(Debug, Clone)]
+pub struct Spanned {
+    pub token: Token,
+    pub span: Span,
+}
+
+impl Spanned {
+    /// The span from this token through `other`, for labels that should
+    /// underline a whole construct rather than a single token.
+    pub fn to(&self, other: &Spanned) -> Span {
+        merge_spans(&self.span, &other.span)
+    }
+}
+
+/// The first sub-expression of `expr` that a compile-time evaluator
+/// couldn't reduce, depth-first in source order; `None` when the whole
+/// tree is literals and operators over them. See
+/// [`Parser::parse_const_value`].
+fn first_non_constant(expr: &Expr) -> Option<&Expr> {
+    match &expr.kind {
+        ExprKind::Literal(_) => None,
+        ExprKind::Unary(_, inner) | ExprKind::TypeOf(inner) => first_non_constant(inner),
+        ExprKind::Binary(_, lhs, rhs) | ExprKind::NullCoalesce(lhs, rhs) => {
+            first_non_constant(lhs).or_else(|| first_non_constant(rhs))
+        }
+        ExprKind::Ternary {
+            cond,
+            then,
+            otherwise,
+        } => first_non_constant(cond)
+            .or_else(|| first_non_constant(then))
+            .or_else(|| first_non_constant(otherwise)),
+        ExprKind::Array(elements) | ExprKind::Tuple(elements) | ExprKind::Set(elements) => {
+            elements.iter().find_map(first_non_constant)
+        }
+        ExprKind::Map(entries) => entries
+            .iter()
+            .find_map(|(key, value)| first_non_constant(key).or_else(|| first_non_constant(value))),
+        ExprKind::Compound(fields) => fields.iter().find_map(|(key, value)| {
+            match key {
+                CompoundKey::Computed(key) => first_non_constant(key),
+                CompoundKey::Static(_) => None,
+            }
+            .or_else(|| first_non_constant(value))
+        }),
+        ExprKind::Range {
+            start, end, step, ..
+        } => first_non_constant(start)
+            .or_else(|| first_non_constant(end))
+            .or_else(|| step.as_deref().and_then(first_non_constant)),
+        _ => Some(expr),
+    }
+}
+
+/// The smallest span covering both `a` and `b`.
+pub fn merge_spans(a: &Span, b: &Span) -> Span {
+    a.start.min(b.start)..a.end.max(b.end)
+}
+
+/// What one REPL input turned out to be; see
+/// [`Parser::parse_repl_line`].
+#[derive(Debug)]
+pub enum ReplItem {
+    Statement(Box<Statement>),
+    Expression(Box<Expr>),
+}
+
+/// Per-file metadata from an optional leading frontmatter block:
+///
+/// ```text
+/// ---
+/// name: tool
+/// version: 0.1
+/// ---
+/// ```
+///
+/// Only recognized at the very top of a file; build tooling reads it off
+/// [`Parser::meta`], and the language itself never sees the block.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FileMeta {
+    /// The `key: value` pairs in file order. Lines without a `:` are
+    /// ignored rather than erroring — frontmatter is tooling's business,
+    /// not the grammar's.
+    pub entries: Vec<(String, String)>,
+}
+
+impl FileMeta {
+    /// The value of the first entry named `key`, if any.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(entry, _)| entry == key)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Recognizes a frontmatter block at the very start of `source`,
+/// returning the parsed metadata and the byte offset where code resumes.
+/// Anything that isn't exactly `---`, pairs, `---` reads as ordinary
+/// (likely ill-formed) source instead.
+fn frontmatter(source: &str) -> Option<(FileMeta, usize)> {
+    let body = source.strip_prefix("---\n")?;
+    let mut close = None;
+    let mut search_from = 0;
+    while let Some(at) = body[search_from..].find("\n---") {
+        let at = search_from + at;
+        let after = &body[at + 4..];
+        if after.is_empty() || after.starts_with('\n') {
+            close = Some(at);
+            break;
+        }
+        search_from = at + 1;
+    }
+    let close = close?;
+    let entries = body[..close]
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            Some((key.trim().to_owned(), value.trim().to_owned()))
+        })
+        .collect();
+    // `---\n` + block + `\n---`, plus the newline after the closing
+    // fence when there is one.
+    let mut end = 4 + close + 4;
+    if source[end..].starts_with('\n') {
+        end += 1;
+    }
+    Some((FileMeta { entries }, end))
+}
+
+pub struct Parser {
+    pub src: CodeSource,
+    pub current_module: String,
+    /// The full source text, kept alongside the token stream so
+    /// [`Parser::parse_postfix`] can tell whether a newline separates two
+    /// tokens (captured before [`Lexer::spanned`] consumes the lexer).
+    /// Crate-visible so the validator can scan `conduct:ignore` comments.
+    pub(crate) source: String,
+    tokens: Vec<Spanned>,
+    pos: usize,
+    /// Diagnostics accumulated by panic-mode recovery; drained by
+    /// [`Parser::take_errors`] rather than returned from every call.
+    errors: Vec<ErrorReport>,
+    /// Comments collected by [`Parser::new_keep_comments`], distributed
+    /// over the statements at the end of [`Parser::parse`]. Empty for a
+    /// normal parse.
+    comments: Vec<Comment>,
+    /// Metadata from a leading frontmatter block, when the file opens
+    /// with one; see [`FileMeta`].
+    pub meta: Option<FileMeta>,
+    /// How deep [`Parser::parse_expression`] may recurse before giving up
+    /// with a "maximum nesting depth exceeded" report instead of blowing
+    /// the stack on input like thousands of nested parentheses. The
+    /// default is deliberately conservative: one nesting level costs a
+    /// dozen-plus frames through the precedence chain, and the default
+    /// has to leave a debug build room on the ~2 MiB stacks test threads
+    /// get. Callers with deeper stacks can raise it before parsing.
+    pub max_depth: usize,
+    /// The current [`Parser::parse_expression`] recursion depth, checked
+    /// against [`Parser::max_depth`].
+    depth: usize,
+}
+
+impl Parser {
+    pub fn new_inline(src: &str) -> Self {
+        Self::new_inline_named("main", src)
+    }
+
+    /// Like [`Parser::new_inline`], but stamps diagnostics with a module
+    /// name of the caller's choosing — tests, REPLs, and embedded
+    /// snippets otherwise all report as the anonymous `main`.
+    pub fn new_inline_named(name: &str, src: &str) -> Self {
+        let mut parser = Self::new(CodeSource::Inline(src.to_owned()), Token::lexer(src));
+        parser.current_module = name.to_owned();
+        // Lexing already ran inside `new`; re-stamp anything it queued
+        // (overflowing literals, stray bytes) with the real name.
+        for report in &mut parser.errors {
+            report.current_module = parser.current_module.clone();
+        }
+        parser
+    }
+
+    /// Like [`Parser::new_inline`], but stamps diagnostics with a REPL
+    /// input line (`in:<line>`) instead of the anonymous inline marker.
+    pub fn new_repl(src: &str, line: usize) -> Self {
+        Self::new(CodeSource::Repl { line }, Token::lexer(src))
+    }
+
+    /// Reads `path` and builds a parser over its contents, with
+    /// diagnostics pointing at that file. An unreadable path comes back
+    /// as an ordinary [`ErrorReport`] rather than a panic, so the CLI and
+    /// tests can surface it like any other diagnostic.
+    pub fn from_file(path: impl AsRef<std::path::Path>) -> Res<Self> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path).map_err(|err| {
+            let area = CodeArea {
+                src: CodeSource::File(path.to_path_buf()),
+                span: (0, 0),
+            };
+            Box::new(ErrorReport {
+                code: Code::UnreadableFile.as_str(),
+                severity: Severity::Error,
+                call_stack: vec![],
+                notes: Vec::new(),
+                help: None,
+                current_module: "main".to_owned(),
+                position: area,
+                message: format!("couldn't read {}: {err}", path.display()),
+                labels: vec![],
+            })
+        })?;
+        Ok(Self::new(
+            CodeSource::File(path.to_path_buf()),
+            Token::lexer(&source),
+        ))
+    }
+
+    pub fn new(src: CodeSource, lexer: Lexer<Token>) -> Self {
+        let source = lexer.source().to_owned();
+        let mut tokens: Vec<Spanned> = Vec::new();
+        let mut errors = Vec::new();
+        // A frontmatter block isn't part of the language: its bytes (and
+        // whatever tokens the lexer made of them) are skipped wholesale.
+        let (meta, body_start) = match frontmatter(&source) {
+            Some((meta, end)) => (Some(meta), end),
+            None => (None, 0),
+        };
+        // Bytes consumed by an assembled regex literal; the lexer's own
+        // tokens inside that range are stale and skipped.
+        let mut skip_until = body_start;
+        for (res, span) in lexer.spanned() {
+            if span.start < skip_until {
+                continue;
+            }
+            let token = match res {
+                Ok(token) => token,
+                Err(()) => {
+                    let slice = source.get(span.clone()).unwrap_or("");
+                    let Some(value) = crate::tk::overflowed_int(slice) else {
+                        // Any other unlexable input gets its own report
+                        // labeling exactly the offending bytes. Lexing
+                        // has already resumed past them, so every bad
+                        // character in a file reports, not just the
+                        // first.
+                        let area = CodeArea {
+                            src: src.clone(),
+                            span: (span.start, span.end),
+                        };
+                        errors.push(ErrorReport {
+                            code: Code::SyntaxError.as_str(),
+                            severity: Severity::Error,
+                            call_stack: vec![],
+                            notes: Vec::new(),
+                            help: None,
+                            current_module: "main".to_owned(),
+                            position: area.clone(),
+                            message: format!("unexpected character `{slice}`"),
+                            labels: vec![(area, "not part of any token".to_owned())],
+                        });
+                        continue;
+                    };
+                    // `-9223372036854775808` is representable even though
+                    // its magnitude alone is not: fold a *prefix* minus
+                    // into the literal. A minus after a value is binary
+                    // subtraction and gets no such treatment.
+                    let prefix_minus = matches!(
+                        tokens.last().map(|s| &s.token),
+                        Some(Token::Minus)
+                    ) && !tokens
+                        .len()
+                        .checked_sub(2)
+                        .and_then(|i| tokens.get(i))
+                        .is_some_and(|s| s.token.ends_value());
+                    if value == i64::MAX as u128 + 1 && prefix_minus {
+                        let minus = tokens.pop().expect("matched Minus above");
+                        tokens.push(Spanned {
+                            token: Token::Int(i64::MIN),
+                            span: minus.span.start..span.end,
+                        });
+                    } else {
+                        let area = CodeArea {
+                            src: src.clone(),
+                            span: (span.start, span.end),
+                        };
+                        errors.push(ErrorReport {
+                            code: Code::SyntaxError.as_str(),
+                            severity: Severity::Error,
+                            call_stack: vec![],
+                            notes: Vec::new(),
+                            help: None,
+                            current_module: "main".to_owned(),
+                            position: area.clone(),
+                            message: format!(
+                                "integer literal `{slice}` is too large for a 64-bit integer"
+                            ),
+                            labels: vec![(area, "does not fit in an i64".to_owned())],
+                        });
+                    }
+                    continue;
+                }
+            };
+            // A `/` where an expression starts opens a regex literal,
+            // not a division; assemble it straight from the source.
+            if matches!(token, Token::Slash)
+                && !tokens.last().is_some_and(|s| s.token.ends_value())
+            {
+                if let Some((pattern, flags, end)) = crate::tk::scan_regex(&source, span.end) {
+                    tokens.push(Spanned {
+                        token: Token::Regex(crate::tk::RegexLiteral { pattern, flags }),
+                        span: span.start..end,
+                    });
+                    skip_until = end;
+                    continue;
+                }
+            }
+            tokens.push(Spanned { token, span });
+        }
+
+        Self {
+            src,
+            current_module: "main".to_owned(),
+            source,
+            tokens,
+            pos: 0,
+            errors,
+            comments: Vec::new(),
+            meta,
+            max_depth: 32,
+            depth: 0,
+        }
+    }
+
+    /// Like [`Parser::new`], but additionally collects every comment the
+    /// lexer skipped and attaches each to the nearest statement during
+    /// [`Parser::parse`]: trailing if it shares a line with the statement
+    /// before it, otherwise leading on the statement after it.
+    pub fn new_keep_comments(src: CodeSource, lexer: Lexer<Token>) -> Self {
+        let mut parser = Self::new(src, lexer);
+        // Comments only ever live in the gaps between tokens (plus the
+        // stretches before the first and after the last), so scan those.
+        let mut from = 0;
+        for spanned in &parser.tokens {
+            scan_comments(&parser.source, from, spanned.span.start, &mut parser.comments);
+            from = spanned.span.end;
+        }
+        scan_comments(&parser.source, from, parser.source.len(), &mut parser.comments);
+        parser
+    }
+
+    /// Parses one REPL input without making the user say what it is: a
+    /// bare expression (`1 + 2`) comes back as
+    /// [`ReplItem::Expression`], anything else as a statement. A
+    /// genuine error *inside* a statement (`let x = )`) propagates
+    /// untouched — the expression fallback only runs when the statement
+    /// parse died on its very first token.
+    pub fn parse_repl_line(&mut self) -> Res<ReplItem> {
+        let checkpoint = self.pos;
+        match self.parse_statement() {
+            Ok(Statement {
+                kind: StatementKind::Expr(expr),
+                ..
+            }) => Ok(ReplItem::Expression(Box::new(expr))),
+            Ok(stmt) => Ok(ReplItem::Statement(Box::new(stmt))),
+            Err(err) if self.pos == checkpoint => {
+                self.parse_expression()
+                    .map(|expr| ReplItem::Expression(Box::new(expr)))
+                    // Two identical failures: the statement's report
+                    // already points at the right token.
+                    .map_err(|_| err)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Drains every diagnostic collected since the last call. The caller
+    /// (CLI, validator pipeline, LSP bridge) owns printing them.
+    pub fn take_errors(&mut self) -> Vec<ErrorReport> {
+        std::mem::take(&mut self.errors)
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|s| &s.token)
+    }
+
+    /// The next token, without consuming it — the public face of the
+    /// internal `peek`, for plugin grammars and recovery heuristics
+    /// built on top of the parser.
+    pub fn peek_kind(&self) -> Option<&Token> {
+        self.peek()
+    }
+
+    /// The token `n` positions ahead (`peek_nth(0)` is what
+    /// [`Parser::peek_kind`] sees), without consuming anything. The
+    /// whole stream is lexed up front, so arbitrary lookahead is O(1)
+    /// and never disturbs positions or spans.
+    pub fn peek_nth(&self, n: usize) -> Option<&Token> {
+        self.tokens.get(self.pos + n).map(|s| &s.token)
+    }
+
+    fn peek_span(&self) -> Span {
+        self.tokens
+            .get(self.pos)
+            .map(|s| s.span.clone())
+            .unwrap_or_else(|| {
+                let end = self.tokens.last().map(|s| s.span.end).unwrap_or(0);
+                end..end
+            })
+    }
+
+    fn advance(&mut self) -> Option<Token> {
+        let tok = self.tokens.get(self.pos).map(|s| s.token.clone());
+        if tok.is_some() {
+            self.pos += 1;
+        }
+        tok
+    }
+
+    fn check(&self, token: &Token) -> bool {
+        self.peek() == Some(token)
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.check(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Runs `f` and pairs its result with the byte span it consumed: the
+    /// start of the token at the current position through the end of the
+    /// last token `f` advanced past. Spares parse methods (and external
+    /// tooling driving the parser) the boilerplate of recording start
+    /// offsets by hand.
+    pub fn spanned<T>(&mut self, f: impl FnOnce(&mut Self) -> Res<T>) -> Res<(T, Span)> {
+        let start = self.peek_span().start;
+        let value = f(self)?;
+        let end = self
+            .pos
+            .checked_sub(1)
+            .and_then(|i| self.tokens.get(i))
+            .map(|s| s.span.end)
+            .unwrap_or(start);
+        Ok((value, start..end))
+    }
+
+    /// The [`CodeArea`] for a span captured by [`Parser::spanned`].
+    fn area_from(&self, span: Span) -> CodeArea {
+        CodeArea {
+            src: self.src.clone(),
+            span: (span.start, span.end),
+        }
+    }
+
+    fn area(&self, start: usize) -> CodeArea {
+        let end = self.pos.checked_sub(1).and_then(|i| self.tokens.get(i));
+        let end_byte = end.map(|s| s.span.end).unwrap_or(start);
+        CodeArea {
+            src: self.src.clone(),
+            span: (start, end_byte),
+        }
+    }
+
+    fn error(&mut self, message: impl Into<String>) -> ErrorReport {
+        let span = self.peek_span();
+        let area = CodeArea {
+            src: self.src.clone(),
+            span: (span.start, span.end),
+        };
+        ErrorReport {
+            code: Code::SyntaxError.as_str(),
+            severity: Severity::Error,
+            call_stack: vec![],
+            notes: Vec::new(),
+            help: None,
+            current_module: self.current_module.clone(),
+            position: area.clone(),
+            message: message.into(),
+            labels: vec![(area, "unexpected token here".to_owned())],
+        }
+    }
+
+    /// Like [`Parser::error`], but labeling everything from `from`
+    /// through the current position, for constructs whose problem spans
+    /// many tokens (an unterminated call, not just the token where the
+    /// parse gave up).
+    fn error_from(&mut self, from: &Span, message: impl Into<String>) -> ErrorReport {
+        let span = merge_spans(from, &self.peek_span());
+        let area = CodeArea {
+            src: self.src.clone(),
+            span: (span.start, span.end),
+        };
+        ErrorReport {
+            code: Code::SyntaxError.as_str(),
+            severity: Severity::Error,
+            call_stack: vec![],
+            notes: Vec::new(),
+            help: None,
+            current_module: self.current_module.clone(),
+            position: area.clone(),
+            message: message.into(),
+            labels: vec![(area, "this construct is never finished".to_owned())],
+        }
+    }
+
+    /// Advances until the next token is one of `kinds` — compared by
+    /// variant, so `Token::Ident(..)` in the list matches any identifier
+    /// — or the end of input, returning how many tokens were skipped.
+    /// The freestanding synchronization primitive for embedders building
+    /// their own recovery loops; [`Parser::parse_all_recovering`] uses
+    /// the keyword-aware [`Parser::synchronize`] flavor of the same idea.
+    pub fn recover_to(&mut self, kinds: &[Token]) -> usize {
+        let mut skipped = 0;
+        while let Some(token) = self.peek() {
+            if kinds
+                .iter()
+                .any(|kind| std::mem::discriminant(kind) == std::mem::discriminant(token))
+            {
+                break;
+            }
+            self.pos += 1;
+            skipped += 1;
+        }
+        skipped
+    }
+
+    /// Discards tokens until the next statement keyword or a balanced
+    /// block/terminator boundary, per `Token::is_recovery_boundary`. Called
+    /// after a parse error so the caller can keep parsing the rest of the
+    /// file instead of bailing out entirely.
+    fn synchronize(&mut self) {
+        let mut depth = 0i32;
+        while let Some(tok) = self.peek() {
+            match tok {
+                Token::LBrace => depth += 1,
+                Token::RBrace if depth > 0 => depth -= 1,
+                Token::RBrace | Token::Semi if depth == 0 => {
+                    self.advance();
+                    return;
+                }
+                _ if depth == 0 && tok.is_recovery_boundary() => return,
+                _ => {}
+            }
+            self.advance();
+        }
+    }
+
+    // ---- statements ---------------------------------------------------
+
+    /// Parses the whole token stream, recovering at statement boundaries,
+    /// and returns the partial tree together with every diagnostic hit
+    /// along the way. This is [`Parser::parse`] + [`Parser::take_errors`]
+    /// in one call, for callers that want both halves without juggling the
+    /// parser's internal error buffer.
+    pub fn parse_all_recovering(&mut self) -> (Vec<Statement>, Vec<ErrorReport>) {
+        let stmts = self.parse().expect("parse() always returns Ok");
+        let errors = self.take_errors();
+        (stmts, errors)
+    }
+
+    /// Parses every top-level statement *without* panic-mode recovery,
+    /// pairing each with its byte span, and verifies the whole token
+    /// stream was consumed. A syntax error after at least one good
+    /// statement is reported as trailing-token garbage; recovery-style
+    /// callers should use [`Parser::parse`] / [`Parser::parse_all_recovering`]
+    /// instead.
+    pub fn parse_many(&mut self) -> Res<Vec<(Statement, Span)>> {
+        let mut stmts = Vec::new();
+        while self.peek().is_some() {
+            match self.parse_statement() {
+                Ok(stmt) => {
+                    let span = stmt.area.span.0..stmt.area.span.1;
+                    stmts.push((stmt, span));
+                }
+                Err(mut report) => {
+                    if !stmts.is_empty() {
+                        report.message =
+                            format!("unexpected trailing tokens: {}", report.message);
+                    }
+                    return Err(report);
+                }
+            }
+        }
+        Ok(stmts)
+    }
+
+    pub fn parse(&mut self) -> Res<Vec<Statement>> {
+        let mut stmts = Vec::new();
+        while self.peek().is_some() {
+            match self.parse_statement() {
+                Ok(stmt) => stmts.push(stmt),
+                Err(report) => {
+                    let start = report.position.span.0;
+                    self.errors.push(*report);
+                    stmts.push(Statement {
+                        kind: StatementKind::Error,
+                        area: self.area(start),
+                        comments: Vec::new(),
+                        annotations: Vec::new(),
+                    });
+                    self.synchronize();
+                }
+            }
+        }
+        if !self.comments.is_empty() {
+            let comments = std::mem::take(&mut self.comments);
+            for comment in comments {
+                attach_comment(&mut stmts, comment, &self.source);
+            }
+        }
+        Ok(stmts)
+    }
+
+    pub fn parse_statement(&mut self) -> Res<Statement> {
+        let start = self.peek_span().start;
+        let annotations = self.parse_annotations()?;
+        let kind = match self.peek() {
+            Some(Token::Import) => self.parse_import()?,
+            Some(Token::Include) => {
+                self.advance();
+                let path = self.parse_dotted_or_string()?;
+                self.eat(&Token::Semi);
+                StatementKind::Include(path)
+            }
+            Some(Token::Export) => self.parse_export()?,
+            Some(Token::Module) => self.parse_module()?,
+            Some(Token::Let) => self.parse_let()?,
+            Some(Token::Const) => self.parse_const(false)?,
+            Some(Token::Native) => self.parse_native()?,
+            Some(Token::Fn) => self.parse_fn(false)?,
+            Some(Token::If) => self.parse_if()?,
+            Some(Token::For) => self.parse_for(None)?,
+            Some(Token::While) => self.parse_while(None)?,
+            Some(Token::Do) => self.parse_do_while()?,
+            Some(Token::Return) => self.parse_return()?,
+            Some(Token::Break) => {
+                self.advance();
+                let label = self.parse_loop_label();
+                // `break 5`: a same-line expression after the optional
+                // label is the break's value. A bare identifier was
+                // already taken as a label — `break (x)` carries one out.
+                let value = if self.value_follows_on_line() {
+                    Some(self.parse_expression()?)
+                } else {
+                    None
+                };
+                self.eat(&Token::Semi);
+                StatementKind::Break { label, value }
+            }
+            Some(Token::Continue) => {
+                self.advance();
+                let label = self.parse_loop_label();
+                self.eat(&Token::Semi);
+                StatementKind::Continue(label)
+            }
+            Some(Token::Throw) => {
+                self.advance();
+                let expr = self.parse_expression()?;
+                self.eat(&Token::Semi);
+                StatementKind::Throw(expr)
+            }
+            Some(Token::Assert) => {
+                self.advance();
+                let lhs = self.parse_expression()?;
+                let message = if self.eat(&Token::Comma) {
+                    Some(self.parse_expression()?)
+                } else {
+                    None
+                };
+                self.eat(&Token::Semi);
+                StatementKind::Assert {
+                    lhs,
+                    rhs: None,
+                    message,
+                }
+            }
+            Some(Token::AssertEq) => {
+                self.advance();
+                let lhs = self.parse_expression()?;
+                self.expect(&Token::Comma)?;
+                let rhs = self.parse_expression()?;
+                let message = if self.eat(&Token::Comma) {
+                    Some(self.parse_expression()?)
+                } else {
+                    None
+                };
+                self.eat(&Token::Semi);
+                StatementKind::Assert {
+                    lhs,
+                    rhs: Some(rhs),
+                    message,
+                }
+            }
+            Some(Token::Try) => self.parse_try()?,
+            Some(Token::Enum) => self.parse_enum()?,
+            Some(Token::Struct) => self.parse_struct()?,
+            // `outer: for ..` / `outer: while ..`: a loop label. The
+            // two-token lookahead keeps plain `ident`-led expression
+            // statements (and ternaries) out of this arm.
+            Some(Token::Ident(_))
+                if matches!(
+                    self.tokens.get(self.pos + 1).map(|s| &s.token),
+                    Some(Token::Colon)
+                ) && matches!(
+                    self.tokens.get(self.pos + 2).map(|s| &s.token),
+                    Some(Token::For | Token::While)
+                ) =>
+            {
+                let label = self.expect_ident()?;
+                self.advance();
+                match self.peek() {
+                    Some(Token::For) => self.parse_for(Some(label))?,
+                    _ => self.parse_while(Some(label))?,
+                }
+            }
+            _ => self.parse_expr_or_assign()?,
+        };
+        if !annotations.is_empty()
+            && !matches!(
+                kind,
+                StatementKind::Fn { .. } | StatementKind::Const { .. } | StatementKind::Let { .. }
+            )
+        {
+            return Err(Box::new(self.error(
+                "annotations may only precede `fn`, `const`, or `let` declarations",
+            )));
+        }
+        Ok(Statement {
+            kind,
+            area: self.area(start),
+            comments: Vec::new(),
+            annotations,
+        })
+    }
+
+    /// Parses any `@name` / `@name(args...)` annotations preceding a
+    /// declaration.
+    fn parse_annotations(&mut self) -> Res<Vec<Annotation>> {
+        let mut annotations = Vec::new();
+        while self.check(&Token::At) {
+            let start = self.peek_span().start;
+            self.advance();
+            let name = self.expect_ident()?;
+            let mut args = Vec::new();
+            if self.eat(&Token::LParen) {
+                while !self.check(&Token::RParen) {
+                    args.push(self.parse_expression()?);
+                    if !self.eat(&Token::Comma) {
+                        break;
+                    }
+                }
+                self.expect(&Token::RParen)?;
+            }
+            annotations.push(Annotation {
+                name,
+                args,
+                area: self.area(start),
+            });
+        }
+        Ok(annotations)
+    }
+
+    fn parse_import(&mut self) -> Res<StatementKind> {
+        self.advance();
+        if self.check(&Token::LBrace) {
+            return self.parse_selective_import();
+        }
+        let path = self.parse_dotted_or_string()?;
+        let alias = if matches!(self.peek(), Some(Token::Ident(word)) if word == "as") {
+            self.advance();
+            Some(self.expect_ident()?)
+        } else {
+            None
+        };
+        self.eat(&Token::Semi);
+        Ok(StatementKind::Import { path, alias })
+    }
+
+    fn parse_selective_import(&mut self) -> Res<StatementKind> {
+        self.expect(&Token::LBrace)?;
+        if self.check(&Token::RBrace) {
+            return Err(Box::new(self.error(
+                "selective import lists nothing: name at least one item, \
+                 e.g. `import { read } from std.io`",
+            )));
+        }
+        let mut names = Vec::new();
+        while !self.check(&Token::RBrace) {
+            let name = self.expect_ident()?;
+            let alias = if matches!(self.peek(), Some(Token::Ident(word)) if word == "as") {
+                self.advance();
+                Some(self.expect_ident()?)
+            } else {
+                None
+            };
+            names.push((name, alias));
+            if !self.eat(&Token::Comma) {
+                break;
+            }
+        }
+        self.expect(&Token::RBrace)?;
+        match self.peek() {
+            Some(Token::Ident(word)) if word == "from" => {
+                self.advance();
+            }
+            _ => {
+                return Err(Box::new(
+                    self.error("expected `from` after the selective import list"),
+                ))
+            }
+        }
+        let path = self.parse_dotted_or_string()?;
+        self.eat(&Token::Semi);
+        Ok(StatementKind::SelectiveImport { names, path })
+    }
+
+    fn parse_export(&mut self) -> Res<StatementKind> {
+        self.advance();
+        if self.check(&Token::LBrace) {
+            return self.parse_selective_export();
+        }
+        let path = self.parse_dotted_or_string()?;
+        // `export foo as publicFoo`: rename one local definition.
+        if matches!(self.peek(), Some(Token::Ident(word)) if word == "as") {
+            self.advance();
+            let alias = self.expect_ident()?;
+            self.eat(&Token::Semi);
+            return Ok(StatementKind::SelectiveExport {
+                names: vec![(path, Some(alias))],
+                from: None,
+            });
+        }
+        self.eat(&Token::Semi);
+        Ok(StatementKind::Export(path))
+    }
+
+    /// The `export { a as b, c } from module` form, mirroring
+    /// [`Parser::parse_selective_import`].
+    fn parse_selective_export(&mut self) -> Res<StatementKind> {
+        self.expect(&Token::LBrace)?;
+        if self.check(&Token::RBrace) {
+            return Err(Box::new(self.error(
+                "selective export lists nothing: name at least one item, \
+                 e.g. `export { read } from internal`",
+            )));
+        }
+        let mut names = Vec::new();
+        while !self.check(&Token::RBrace) {
+            let name = self.expect_ident()?;
+            let alias = if matches!(self.peek(), Some(Token::Ident(word)) if word == "as") {
+                self.advance();
+                Some(self.expect_ident()?)
+            } else {
+                None
+            };
+            names.push((name, alias));
+            if !self.eat(&Token::Comma) {
+                break;
+            }
+        }
+        self.expect(&Token::RBrace)?;
+        match self.peek() {
+            Some(Token::Ident(word)) if word == "from" => {
+                self.advance();
+            }
+            _ => {
+                return Err(Box::new(
+                    self.error("expected `from` after the selective export list"),
+                ));
+            }
+        }
+        let from = self.parse_dotted_or_string()?;
+        self.eat(&Token::Semi);
+        Ok(StatementKind::SelectiveExport {
+            names,
+            from: Some(from),
+        })
+    }
+
+    fn parse_module(&mut self) -> Res<StatementKind> {
+        self.advance();
+        let name = self.parse_dotted_or_string()?;
+        self.eat(&Token::Semi);
+        Ok(StatementKind::Module(name))
+    }
+
+    fn parse_dotted_or_string(&mut self) -> Res<String> {
+        if let Some(Token::Str(s)) = self.peek().cloned() {
+            self.advance();
+            return Ok(s);
+        }
+        let mut parts = Vec::new();
+        loop {
+            match self.advance() {
+                Some(Token::Ident(name)) => parts.push(name),
+                _ => return Err(Box::new(self.error("expected a module path"))),
+            }
+            if !self.eat(&Token::Dot) {
+                break;
+            }
+        }
+        Ok(parts.join("."))
+    }
+
+    fn parse_let(&mut self) -> Res<StatementKind> {
+        self.advance();
+        if self.eat(&Token::LParen) {
+            // `let (a, b) = pair`: tuple destructuring.
+            let mut names = Vec::new();
+            while !self.check(&Token::RParen) {
+                names.push(self.expect_ident()?);
+                if !self.eat(&Token::Comma) {
+                    break;
+                }
+            }
+            self.expect(&Token::RParen)?;
+            if names.is_empty() {
+                return Err(Box::new(
+                    self.error("tuple destructuring needs at least one binding"),
+                ));
+            }
+            self.expect(&Token::Eq)?;
+            let value = self.parse_expression()?;
+            self.eat(&Token::Semi);
+            return Ok(StatementKind::LetTuple { names, value });
+        }
+        let name = self.expect_ident()?;
+        let ty = self.parse_optional_type_annotation()?;
+        let value = if self.eat(&Token::Eq) {
+            Some(self.parse_expression()?)
+        } else {
+            None
+        };
+        self.eat(&Token::Semi);
+        Ok(StatementKind::Let { name, ty, value })
+    }
+
+    fn parse_const(&mut self, native: bool) -> Res<StatementKind> {
+        self.advance();
+        let name = self.expect_ident()?;
+        let ty = self.parse_optional_type_annotation()?;
+        let value = if self.eat(&Token::Eq) {
+            Some(self.parse_expression()?)
+        } else {
+            None
+        };
+        self.eat(&Token::Semi);
+        Ok(StatementKind::Const {
+            name,
+            ty,
+            value,
+            native,
+        })
+    }
+
+    /// Parses a `: <type>` suffix if present (gradual type annotation on a
+    /// `let`/`const`/function parameter), otherwise leaves the cursor
+    /// untouched. A type is either a `type { .. }` structural literal, a
+    /// bare name (`num`, `str`, an alias, ...), or `name[]` for an array.
+    fn parse_optional_type_annotation(&mut self) -> Res<Option<Expr>> {
+        if !self.eat(&Token::Colon) {
+            return Ok(None);
+        }
+        let start = self.peek_span().start;
+        if self.eat(&Token::Type) {
+            return Ok(Some(self.parse_type_def(start)?));
+        }
+        let Some(Token::Ident(name)) = self.peek().cloned() else {
+            return Err(Box::new(self.error(
+                "expected a type after `:` — a name like `num` or an inline `type { .. }`",
+            )));
+        };
+        self.advance();
+        let mut ty = Expr {
+            kind: ExprKind::Ident(name),
+            area: self.area(start),
+        };
+        while self.check(&Token::LBracket)
+            && matches!(
+                self.tokens.get(self.pos + 1).map(|s| &s.token),
+                Some(Token::RBracket)
+            )
+        {
+            self.advance();
+            self.advance();
+            ty = Expr {
+                kind: ExprKind::Array(vec![ty]),
+                area: self.area(start),
+            };
+        }
+        Ok(Some(ty))
+    }
+
+    fn parse_native(&mut self) -> Res<StatementKind> {
+        self.advance();
+        match self.peek() {
+            Some(Token::Const) => self.parse_const(true),
+            Some(Token::Fn) => self.parse_fn(true),
+            _ => Err(Box::new(self.error("`native` may only prefix `const` or `fn`"))),
+        }
+    }
+
+    fn parse_fn(&mut self, native: bool) -> Res<StatementKind> {
+        self.advance();
+        let name = self.expect_ident()?;
+        let type_params = self.parse_type_params()?;
+        let params = self.parse_params()?;
+        let ret = self.parse_optional_type_annotation()?;
+        let body = if native {
+            self.eat(&Token::Semi);
+            Vec::new()
+        } else {
+            self.parse_block()?
+        };
+        Ok(StatementKind::Fn {
+            name,
+            type_params,
+            params,
+            ret,
+            body,
+            native,
+        })
+    }
+
+    /// The optional `<T, U>` list after a declaration's name. In this
+    /// position a `<` can only open type parameters — no expression
+    /// follows a declaration name — so there's no clash with the
+    /// less-than operator.
+    fn parse_type_params(&mut self) -> Res<Vec<String>> {
+        if !self.eat(&Token::Lt) {
+            return Ok(Vec::new());
+        }
+        let mut names = Vec::new();
+        while !self.check(&Token::Gt) {
+            names.push(self.expect_ident()?);
+            if !self.eat(&Token::Comma) {
+                break;
+            }
+        }
+        self.expect(&Token::Gt)?;
+        if names.is_empty() {
+            return Err(Box::new(
+                self.error("a type-parameter list needs at least one name"),
+            ));
+        }
+        Ok(names)
+    }
+
+    /// Parses a parenthesized parameter list: `name[: ty][= default]`,
+    /// comma-separated. Defaulted parameters must come after all required
+    /// ones, so call sites can fill a suffix of missing arguments.
+    fn parse_params(&mut self) -> Res<Vec<Param>> {
+        self.expect(&Token::LParen)?;
+        let mut params = Vec::new();
+        let mut seen_default = false;
+        while !self.check(&Token::RParen) {
+            let variadic = self.eat(&Token::DotDotDot);
+            let name = self.expect_ident()?;
+            let ty = self.parse_optional_type_annotation()?;
+            let default = if !variadic && self.eat(&Token::Eq) {
+                Some(self.parse_expression()?)
+            } else {
+                None
+            };
+            // A variadic parameter is exempt from the defaults-last rule:
+            // it never takes a default of its own.
+            if default.is_none() && seen_default && !variadic {
+                return Err(Box::new(self.error(format!(
+                    "parameter `{name}` without a default follows a defaulted parameter"
+                ))));
+            }
+            seen_default |= default.is_some();
+            params.push(Param {
+                name,
+                ty,
+                default,
+                variadic,
+            });
+            let more = self.eat(&Token::Comma);
+            if variadic && !self.check(&Token::RParen) {
+                return Err(Box::new(
+                    self.error("a variadic parameter must be the last parameter"),
+                ));
+            }
+            if !more {
+                break;
+            }
+        }
+        self.expect(&Token::RParen)?;
+        Ok(params)
+    }
+
+    fn parse_block(&mut self) -> Res<Vec<Statement>> {
+        self.expect(&Token::LBrace)?;
+        let mut stmts = Vec::new();
+        while !self.check(&Token::RBrace) && self.peek().is_some() {
+            match self.parse_statement() {
+                Ok(stmt) => stmts.push(stmt),
+                Err(report) => {
+                    let start = report.position.span.0;
+                    self.errors.push(*report);
+                    stmts.push(Statement {
+                        kind: StatementKind::Error,
+                        area: self.area(start),
+                        comments: Vec::new(),
+                        annotations: Vec::new(),
+                    });
+                    self.synchronize();
+                }
+            }
+        }
+        self.expect(&Token::RBrace)?;
+        self.eat(&Token::Semi);
+        Ok(stmts)
+    }
+
+    fn parse_if(&mut self) -> Res<StatementKind> {
+        let mut branches = Vec::new();
+        self.advance();
+        if self.check(&Token::Let) {
+            return self.parse_if_let();
+        }
+        let cond = self.parse_expression()?;
+        let body = self.parse_block()?;
+        branches.push((cond, body));
+        let mut otherwise = None;
+        while self.eat(&Token::Else) {
+            if self.eat(&Token::If) {
+                let cond = self.parse_expression()?;
+                let body = self.parse_block()?;
+                branches.push((cond, body));
+            } else {
+                otherwise = Some(self.parse_block()?);
+                break;
+            }
+        }
+        Ok(StatementKind::If { branches, otherwise })
+    }
+
+    /// An optional loop label following `break`/`continue`. Only an
+    /// identifier on the *same line* counts, mirroring the newline rule in
+    /// [`Parser::parse_postfix`] — the next statement may well start with
+    /// an identifier of its own.
+    fn parse_loop_label(&mut self) -> Option<String> {
+        let prev_end = self
+            .pos
+            .checked_sub(1)
+            .and_then(|i| self.tokens.get(i))
+            .map(|s| s.span.end)?;
+        let next = self.tokens.get(self.pos)?;
+        if self.newline_before(prev_end, next.span.start) {
+            return None;
+        }
+        if let Token::Ident(name) = &next.token {
+            let name = name.clone();
+            self.advance();
+            Some(name)
+        } else {
+            None
+        }
+    }
+
+    /// Whether a same-line expression-starting token follows — the test
+    /// `break` uses to decide whether a value rides along.
+    fn value_follows_on_line(&self) -> bool {
+        let Some(prev_end) = self
+            .pos
+            .checked_sub(1)
+            .and_then(|i| self.tokens.get(i))
+            .map(|s| s.span.end)
+        else {
+            return false;
+        };
+        let Some(next) = self.tokens.get(self.pos) else {
+            return false;
+        };
+        !self.newline_before(prev_end, next.span.start) && next.token.starts_expression()
+    }
+
+    fn parse_for(&mut self, label: Option<String>) -> Res<StatementKind> {
+        self.advance();
+        let binding = match self.advance() {
+            Some(Token::Ident(name)) => name,
+            Some(Token::Str(s)) => s,
+            _ => return Err(Box::new(self.error("expected a loop binding"))),
+        };
+        self.expect(&Token::In)?;
+        let iterable = self.parse_expression()?;
+        let body = self.parse_block()?;
+        Ok(StatementKind::For {
+            binding,
+            iterable,
+            body,
+            label,
+        })
+    }
+
+    fn parse_while(&mut self, label: Option<String>) -> Res<StatementKind> {
+        self.advance();
+        if self.eat(&Token::Let) {
+            let binding = self.expect_ident()?;
+            self.expect(&Token::Eq)?;
+            let value = self.parse_expression()?;
+            let body = self.parse_block()?;
+            return Ok(StatementKind::WhileLet {
+                binding,
+                value,
+                body,
+                label,
+            });
+        }
+        let cond = self.parse_expression()?;
+        let body = self.parse_block()?;
+        Ok(StatementKind::While { cond, body, label })
+    }
+
+    /// The `if let x = expr { .. }` form, the `if` already consumed. An
+    /// `else if` after the body nests as the single statement of the
+    /// `otherwise` block, so arbitrary chains still parse.
+    fn parse_if_let(&mut self) -> Res<StatementKind> {
+        self.advance();
+        let binding = self.expect_ident()?;
+        self.expect(&Token::Eq)?;
+        let value = self.parse_expression()?;
+        let body = self.parse_block()?;
+        let otherwise = if self.eat(&Token::Else) {
+            if self.check(&Token::If) {
+                Some(vec![self.parse_statement()?])
+            } else {
+                Some(self.parse_block()?)
+            }
+        } else {
+            None
+        };
+        Ok(StatementKind::IfLet {
+            binding,
+            value,
+            body,
+            otherwise,
+        })
+    }
+
+    fn parse_do_while(&mut self) -> Res<StatementKind> {
+        self.advance();
+        let body = self.parse_block()?;
+        self.expect(&Token::While)?;
+        let cond = self.parse_expression()?;
+        self.eat(&Token::Semi);
+        Ok(StatementKind::DoWhile { body, cond })
+    }
+
+    fn parse_return(&mut self) -> Res<StatementKind> {
+        self.advance();
+        let value = if self.check(&Token::Semi) || self.at_stmt_boundary() {
+            None
+        } else {
+            Some(self.parse_expression()?)
+        };
+        self.eat(&Token::Semi);
+        Ok(StatementKind::Return(value))
+    }
+
+    fn at_stmt_boundary(&self) -> bool {
+        match self.peek() {
+            None => true,
+            Some(token) => token.is_recovery_boundary(),
+        }
+    }
+
+    fn parse_try(&mut self) -> Res<StatementKind> {
+        self.advance();
+        let body = self.parse_block()?;
+        let mut catches = Vec::new();
+        while self.eat(&Token::Catch) {
+            if self.eat(&Token::Question) {
+                let body = self.parse_block()?;
+                catches.push(CatchArm {
+                    pattern: CatchPattern::Nil,
+                    binding: None,
+                    body,
+                });
+                continue;
+            }
+            let pattern = if self.eat(&Token::Star) {
+                CatchPattern::Any
+            } else {
+                // One or more `|`-separated dotted type paths handled by
+                // the same clause: `catch IoError | ParseError as e`.
+                let mut types = Vec::new();
+                loop {
+                    let mut parts = vec![self.expect_ident()?];
+                    while self.eat(&Token::Dot) {
+                        parts.push(self.expect_ident()?);
+                    }
+                    types.push(parts);
+                    if !self.eat(&Token::Pipe) {
+                        break;
+                    }
+                }
+                CatchPattern::Type(types)
+            };
+            let binding = if self.eat(&Token::In) {
+                None
+            } else {
+                self.expect_keyword_as()?;
+                Some(self.expect_ident_or_underscore()?)
+            };
+            let body = self.parse_block()?;
+            catches.push(CatchArm {
+                pattern,
+                binding,
+                body,
+            });
+        }
+        Ok(StatementKind::Try { body, catches })
+    }
+
+    fn expect_keyword_as(&mut self) -> Res<()> {
+        match self.peek() {
+            Some(Token::Ident(word)) if word == "as" => {
+                self.advance();
+                Ok(())
+            }
+            _ => Err(Box::new(self.error("expected `as`"))),
+        }
+    }
+
+    fn expect_ident_or_underscore(&mut self) -> Res<String> {
+        // `_` lexes as a plain `Ident` (see `tk::Token`), so this is really
+        // just `expect_ident` under a name that documents the `catch _` use.
+        self.expect_ident()
+    }
+
+    fn parse_struct(&mut self) -> Res<StatementKind> {
+        self.advance();
+        let name = self.expect_ident()?;
+        let type_params = self.parse_type_params()?;
+        self.expect(&Token::LBrace)?;
+        let fields = self.parse_type_fields()?;
+        self.eat(&Token::Semi);
+        Ok(StatementKind::Struct {
+            name,
+            type_params,
+            fields,
+        })
+    }
+
+    fn parse_enum(&mut self) -> Res<StatementKind> {
+        self.advance();
+        let name = self.expect_ident()?;
+        self.expect(&Token::LBrace)?;
+        if self.check(&Token::RBrace) {
+            return Err(Box::new(
+                self.error(format!("enum `{name}` needs at least one variant")),
+            ));
+        }
+        let mut variants = Vec::new();
+        while !self.check(&Token::RBrace) {
+            let variant = self.expect_ident()?;
+            let mut fields = Vec::new();
+            if self.eat(&Token::LParen) {
+                while !self.check(&Token::RParen) {
+                    fields.push(self.expect_ident()?);
+                    if !self.eat(&Token::Comma) {
+                        break;
+                    }
+                }
+                self.expect(&Token::RParen)?;
+            }
+            variants.push(EnumVariant {
+                name: variant,
+                fields,
+            });
+            if !self.eat(&Token::Comma) {
+                break;
+            }
+        }
+        self.expect(&Token::RBrace)?;
+        self.eat(&Token::Semi);
+        Ok(StatementKind::Enum { name, variants })
+    }
+
+    fn parse_expr_or_assign(&mut self) -> Res<StatementKind> {
+        let expr = self.parse_expression()?;
+        // `a, b = b, a`: parallel assignment, most useful as a swap.
+        if self.check(&Token::Comma) {
+            let mut targets = vec![expr];
+            while self.eat(&Token::Comma) {
+                targets.push(self.parse_expression()?);
+            }
+            self.expect(&Token::Eq)?;
+            let mut values = vec![self.parse_expression()?];
+            while self.eat(&Token::Comma) {
+                values.push(self.parse_expression()?);
+            }
+            if targets.len() != values.len() {
+                return Err(Box::new(self.error(format!(
+                    "parallel assignment has {} targets but {} values",
+                    targets.len(),
+                    values.len()
+                ))));
+            }
+            self.eat(&Token::Semi);
+            return Ok(StatementKind::AssignParallel { targets, values });
+        }
+        let op = match self.peek() {
+            Some(Token::Eq) => Some(None),
+            Some(Token::PlusEq) => Some(Some(BinOp::Add)),
+            Some(Token::MinusEq) => Some(Some(BinOp::Sub)),
+            Some(Token::StarEq) => Some(Some(BinOp::Mul)),
+            Some(Token::SlashEq) => Some(Some(BinOp::Div)),
+            Some(Token::PercentEq) => Some(Some(BinOp::Mod)),
+            Some(Token::StarStarEq) => Some(Some(BinOp::Pow)),
+            _ => None,
+        };
+        let kind = if let Some(op) = op {
+            self.advance();
+            let mut value = self.parse_expression()?;
+            // `a = b = 0` chains: what looked like the value is another
+            // target as long as an `=` keeps following.
+            if op.is_none() && self.check(&Token::Eq) {
+                let mut targets = vec![expr];
+                while self.eat(&Token::Eq) {
+                    targets.push(value);
+                    value = self.parse_expression()?;
+                }
+                self.eat(&Token::Semi);
+                return Ok(StatementKind::AssignChain { targets, value });
+            }
+            StatementKind::Assign {
+                target: expr,
+                op,
+                value,
+            }
+        } else {
+            StatementKind::Expr(expr)
+        };
+        self.eat(&Token::Semi);
+        Ok(kind)
+    }
+
+    fn expect_ident(&mut self) -> Res<String> {
+        match self.advance() {
+            Some(Token::Ident(name)) => Ok(name),
+            _ => Err(Box::new(self.error("expected an identifier"))),
+        }
+    }
+
+    fn expect(&mut self, token: &Token) -> Res<()> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            let found = match self.peek() {
+                Some(found) => format!(", found {found}"),
+                None => ", found the end of input".to_owned(),
+            };
+            Err(Box::new(self.error(format!("expected {token}{found}"))))
+        }
+    }
+
+    // ---- expressions ----------------------------------------------------
+
+    pub fn parse_expression(&mut self) -> Res<Expr> {
+        // Parenthesized groups, array/map elements, call arguments, and
+        // index expressions all re-enter here, so this one guard bounds
+        // the recursion nested input can force.
+        if self.depth >= self.max_depth {
+            return Err(Box::new(self.error("maximum nesting depth exceeded")));
+        }
+        self.depth += 1;
+        let result = self.parse_ternary();
+        self.depth -= 1;
+        result
+    }
+
+    fn parse_ternary(&mut self) -> Res<Expr> {
+        let start = self.peek_span().start;
+        let cond = self.parse_null_coalesce()?;
+        if self.eat(&Token::Question) {
+            let then = self.parse_ternary()?;
+            self.expect(&Token::Colon)?;
+            let otherwise = self.parse_ternary()?;
+            Ok(Expr {
+                kind: ExprKind::Ternary {
+                    cond: Box::new(cond),
+                    then: Box::new(then),
+                    otherwise: Box::new(otherwise),
+                },
+                area: self.area(start),
+            })
+        } else {
+            Ok(cond)
+        }
+    }
+
+    /// `??` binds looser than every binary operator but tighter than the
+    /// ternary, and chains to the left: `a ?? b ?? c` is `(a ?? b) ?? c`.
+    fn parse_null_coalesce(&mut self) -> Res<Expr> {
+        let start = self.peek_span().start;
+        let mut lhs = self.parse_binary(0)?;
+        while self.eat(&Token::QuestionQuestion) {
+            let rhs = self.parse_binary(0)?;
+            lhs = Expr {
+                kind: ExprKind::NullCoalesce(Box::new(lhs), Box::new(rhs)),
+                area: self.area(start),
+            };
+        }
+        Ok(lhs)
+    }
+
+    const BINARY_LEVELS: usize = 10;
+
+    /// The precedence row of [`Parser::binop_for`] holding the comparison
+    /// operators, whose chaining is rejected in [`Parser::parse_binary`].
+    const COMPARISON_LEVEL: usize = 2;
+
+    /// One row per precedence level, loosest binding first, following the
+    /// C family: `||`, `&&`, comparisons, ranges, then `|` < `^` < `&` <
+    /// shifts < additive < multiplicative.
+    fn binop_for(level: usize, token: &Token) -> Option<BinOp> {
+        match (level, token) {
+            (0, Token::OrOr) => Some(BinOp::Or),
+            (1, Token::AndAnd) => Some(BinOp::And),
+            (2, Token::EqEq) => Some(BinOp::Eq),
+            (2, Token::NotEq) => Some(BinOp::NotEq),
+            (2, Token::Lt) => Some(BinOp::Lt),
+            (2, Token::LtEq) => Some(BinOp::LtEq),
+            (2, Token::Gt) => Some(BinOp::Gt),
+            (2, Token::GtEq) => Some(BinOp::GtEq),
+            (4, Token::Pipe) => Some(BinOp::BitOr),
+            (5, Token::Caret) => Some(BinOp::BitXor),
+            (6, Token::Amp) => Some(BinOp::BitAnd),
+            (7, Token::Shl) => Some(BinOp::Shl),
+            (7, Token::Shr) => Some(BinOp::Shr),
+            (8, Token::Plus) => Some(BinOp::Add),
+            (8, Token::Minus) => Some(BinOp::Sub),
+            (9, Token::Star) => Some(BinOp::Mul),
+            (9, Token::Slash) => Some(BinOp::Div),
+            (9, Token::Percent) => Some(BinOp::Mod),
+            (9, Token::StarStar) => Some(BinOp::Pow),
+            _ => None,
+        }
+    }
+
+    /// The precedence level ranges occupy (between comparisons and the
+    /// bitwise ladder); handled by [`Parser::parse_range`] since ranges
+    /// carry more than a plain binary node (inclusivity, optional step).
+    const RANGE_LEVEL: usize = 3;
+
+    fn parse_binary(&mut self, level: usize) -> Res<Expr> {
+        if level == Self::RANGE_LEVEL {
+            return self.parse_range(level);
+        }
+        if level >= Self::BINARY_LEVELS {
+            return self.parse_unary();
+        }
+        let start = self.peek_span().start;
+        let mut lhs = self.parse_binary(level + 1)?;
+        let mut compared = false;
+        while let Some(tok) = self.peek() {
+            let Some(op) = Self::binop_for(level, tok) else {
+                break;
+            };
+            // Comparisons don't chain: `a < b < c` would compare a bool
+            // against `c`, which is never what's meant. Reject it with
+            // the spelled-out fix rather than silently mis-evaluating.
+            if level == Self::COMPARISON_LEVEL && compared {
+                return Err(Box::new(self.error(
+                    "comparison operators can't be chained: \
+                     write `a < b && b < c` instead of `a < b < c`",
+                )));
+            }
+            compared = true;
+            self.advance();
+            let rhs = self.parse_binary(level + 1)?;
+            lhs = Expr {
+                kind: ExprKind::Binary(op, Box::new(lhs), Box::new(rhs)),
+                area: self.area(start),
+            };
+        }
+        Ok(lhs)
+    }
+
+    fn parse_range(&mut self, level: usize) -> Res<Expr> {
+        let start_pos = self.peek_span().start;
+        let start = self.parse_binary(level + 1)?;
+        let inclusive = if self.eat(&Token::DotDotEq) {
+            true
+        } else if self.eat(&Token::DotDot) {
+            false
+        } else {
+            return Ok(start);
+        };
+        let end = self.parse_binary(level + 1)?;
+        // `step` is a soft keyword, and only on the same line — the next
+        // statement may legitimately start with an identifier.
+        let step = match (self.prev_token_end(), self.peek().cloned()) {
+            (Some(prev_end), Some(Token::Ident(word)))
+                if word == "step" && !self.newline_before(prev_end, self.peek_span().start) =>
+            {
+                self.advance();
+                Some(Box::new(self.parse_binary(level + 1)?))
+            }
+            _ => None,
+        };
+        Ok(Expr {
+            kind: ExprKind::Range {
+                start: Box::new(start),
+                end: Box::new(end),
+                inclusive,
+                step,
+            },
+            area: self.area(start_pos),
+        })
+    }
+
+    fn prev_token_end(&self) -> Option<usize> {
+        self.pos
+            .checked_sub(1)
+            .and_then(|i| self.tokens.get(i))
+            .map(|s| s.span.end)
+    }
+
+    fn parse_unary(&mut self) -> Res<Expr> {
+        let start = self.peek_span().start;
+        let op = match self.peek() {
+            Some(Token::Bang) => Some(UnaryOp::Not),
+            Some(Token::Minus) => Some(UnaryOp::Neg),
+            Some(Token::Tilde) => Some(UnaryOp::BitNot),
+            _ => None,
+        };
+        if let Some(op) = op {
+            self.advance();
+            let operand = self.parse_unary()?;
+            return Ok(Expr {
+                kind: ExprKind::Unary(op, Box::new(operand)),
+                area: self.area(start),
+            });
+        }
+        if self.eat(&Token::Typeof) {
+            // Covers both `typeof x` and the call-style `typeof(x)` — the
+            // parenthesized form is just a grouped operand.
+            let operand = self.parse_unary()?;
+            return Ok(Expr {
+                kind: ExprKind::TypeOf(Box::new(operand)),
+                area: self.area(start),
+            });
+        }
+        self.parse_postfix()
+    }
+
+    /// Whether a newline appears anywhere between two byte offsets in the
+    /// source. Used to stop [`Parser::parse_postfix`] from gluing a postfix
+    /// token (`[`, `(`, `.`, `!!`) from the *next* statement onto the one
+    /// just parsed, since the grammar has no statement terminator of its own.
+    fn newline_before(&self, prev_end: usize, next_start: usize) -> bool {
+        self.source
+            .get(prev_end..next_start)
+            .is_some_and(|gap| gap.contains('\n'))
+    }
+
+    fn parse_postfix(&mut self) -> Res<Expr> {
+        let start = self.peek_span().start;
+        let mut expr = self.parse_primary()?;
+        loop {
+            let prev_end = self.pos.checked_sub(1).and_then(|i| self.tokens.get(i)).map(|s| s.span.end);
+            if let (Some(prev_end), Some(next_start)) =
+                (prev_end, self.tokens.get(self.pos).map(|s| s.span.start))
+            {
+                // A `.`/`?.` after a line break is always a chain
+                // continuation — no statement can begin with one — which
+                // lets the formatter wrap long chains. Every other
+                // postfix token belongs to the next statement.
+                if self.newline_before(prev_end, next_start)
+                    && !matches!(self.peek(), Some(Token::Dot | Token::QuestionDot))
+                {
+                    break;
+                }
+            }
+            expr = match self.peek() {
+                Some(Token::Dot) => {
+                    self.advance();
+                    let name = self.expect_ident()?;
+                    Expr {
+                        kind: ExprKind::Member {
+                            target: Box::new(expr),
+                            name,
+                        },
+                        area: self.area(start),
+                    }
+                }
+                Some(Token::LBracket) => {
+                    self.advance();
+                    let index = self.parse_expression()?;
+                    self.expect(&Token::RBracket)?;
+                    Expr {
+                        kind: ExprKind::Index {
+                            target: Box::new(expr),
+                            index: Box::new(index),
+                        },
+                        area: self.area(start),
+                    }
+                }
+                Some(Token::LParen) => {
+                    let open = self.peek_span();
+                    self.advance();
+                    let mut args = Vec::new();
+                    while !self.check(&Token::RParen) {
+                        args.push(self.parse_spreadable()?);
+                        if !self.eat(&Token::Comma) {
+                            break;
+                        }
+                    }
+                    if !self.eat(&Token::RParen) {
+                        // Underline the whole call, opening paren through
+                        // wherever the parse gave up.
+                        return Err(Box::new(
+                            self.error_from(&open, "unterminated call: missing `)`"),
+                        ));
+                    }
+                    Expr {
+                        kind: ExprKind::Call {
+                            callee: Box::new(expr),
+                            args,
+                        },
+                        area: self.area(start),
+                    }
+                }
+                Some(Token::QuestionDot) => {
+                    self.advance();
+                    let name = self.expect_ident()?;
+                    Expr {
+                        kind: ExprKind::OptionalAccess {
+                            target: Box::new(expr),
+                            name,
+                        },
+                        area: self.area(start),
+                    }
+                }
+                Some(Token::BangBang) => {
+                    self.advance();
+                    Expr {
+                        kind: ExprKind::Unary(UnaryOp::Unwrap, Box::new(expr)),
+                        area: self.area(start),
+                    }
+                }
+                Some(Token::PlusPlus) => {
+                    self.advance();
+                    Expr {
+                        kind: ExprKind::PostIncrement(Box::new(expr)),
+                        area: self.area(start),
+                    }
+                }
+                Some(Token::MinusMinus) => {
+                    self.advance();
+                    Expr {
+                        kind: ExprKind::PostDecrement(Box::new(expr)),
+                        area: self.area(start),
+                    }
+                }
+                // A lone `?` (never `?.`/`??`, which lex as their own
+                // tokens) is postfix propagation unless a ternary's
+                // then-branch follows; see `question_is_propagation`.
+                Some(Token::Question) if self.question_is_propagation() => {
+                    self.advance();
+                    Expr {
+                        kind: ExprKind::Try(Box::new(expr)),
+                        area: self.area(start),
+                    }
+                }
+                _ => break,
+            };
+        }
+        Ok(expr)
+    }
+
+    /// An array element or call argument: a plain expression, or
+    /// `...expr` splicing a collection in place. A lone `...` falls
+    /// through to the usual "expected an expression" report.
+    fn parse_spreadable(&mut self) -> Res<Expr> {
+        let start = self.peek_span().start;
+        if self.eat(&Token::DotDotDot) {
+            let inner = self.parse_expression()?;
+            return Ok(Expr {
+                kind: ExprKind::Spread(Box::new(inner)),
+                area: self.area(start),
+            });
+        }
+        self.parse_expression()
+    }
+
+    /// Whether the `?` at the current position is postfix error
+    /// propagation (`mayFail()?`) rather than the opening of a ternary:
+    /// a ternary's then-branch must follow its `?` on the same line, so
+    /// a `?` before a line break, a non-expression token, or the end of
+    /// input propagates.
+    fn question_is_propagation(&self) -> bool {
+        let Some(question) = self.tokens.get(self.pos) else {
+            return false;
+        };
+        match self.tokens.get(self.pos + 1) {
+            Some(next) => {
+                !next.token.starts_expression()
+                    || self.newline_before(question.span.end, next.span.start)
+            }
+            None => true,
+        }
+    }
+
+    fn parse_primary(&mut self) -> Res<Expr> {
+        let start = self.peek_span().start;
+        // `(a, b) => { .. }` vs a parenthesized expression `(expr)` both
+        // start with `(`; look ahead for the arrow to disambiguate.
+        if self.check(&Token::LParen) && self.looks_like_arrow_fn() {
+            return self.parse_arrow_fn();
+        }
+        // `x => ..`: a single-parameter arrow without parentheses.
+        if matches!(self.peek(), Some(Token::Ident(_)))
+            && matches!(self.peek_nth(1), Some(Token::FatArrow))
+        {
+            return self.parse_arrow_fn();
+        }
+        if self.check(&Token::Match) {
+            return self.parse_match();
+        }
+        // Peek-and-match rather than `self.advance()`-and-match: on the
+        // catch-all below we must *not* have consumed the unexpected token,
+        // or `synchronize()` starts scanning one token too late and can
+        // skip right over a recovery-boundary keyword (e.g. the `let` that
+        // starts the next statement), silently dropping it instead of
+        // reporting or recovering it.
+        let kind = match self.peek().cloned() {
+            Some(Token::Int(n)) => {
+                let slice = self
+                    .source
+                    .get(self.peek_span())
+                    .unwrap_or_default()
+                    .to_owned();
+                self.advance();
+                ExprKind::Literal(Literal::Int(n, IntBase::from_source(&slice)))
+            }
+            Some(Token::Float(n)) => {
+                self.advance();
+                ExprKind::Literal(Literal::Float(n))
+            }
+            Some(Token::Str(s)) => {
+                let token_start = self.peek_span().start;
+                self.check_string_escapes(&s, token_start)?;
+                self.advance();
+                // Raw strings are verbatim by definition; quoted strings
+                // with an unescaped `${` split into interpolation parts.
+                if !s.starts_with('r') && s.contains("${") {
+                    if let Some(parts) = self.interpolation_parts(&s, token_start)? {
+                        return Ok(Expr {
+                            kind: ExprKind::InterpolatedString(parts),
+                            area: self.area(start),
+                        });
+                    }
+                }
+                ExprKind::Literal(Literal::Str(s))
+            }
+            Some(Token::True) => {
+                self.advance();
+                ExprKind::Literal(Literal::Bool(true))
+            }
+            Some(Token::False) => {
+                self.advance();
+                ExprKind::Literal(Literal::Bool(false))
+            }
+            Some(Token::Nil) => {
+                self.advance();
+                ExprKind::Literal(Literal::Nil)
+            }
+            Some(Token::Regex(regex)) => {
+                let crate::tk::RegexLiteral { pattern, flags } = regex;
+                // Checks run before `advance` so the report points at
+                // the literal itself. Flags are always validated; the
+                // pattern only under the `regex` feature.
+                for flag in flags.chars() {
+                    if !matches!(flag, 'i' | 'm' | 's' | 'u' | 'x') {
+                        return Err(Box::new(
+                            self.error(format!("unknown regex flag `{flag}`")),
+                        ));
+                    }
+                }
+                #[cfg(feature = "regex")]
+                if let Err(err) = regex_syntax::Parser::new().parse(&pattern) {
+                    // The syntax error's Display is a multi-line caret
+                    // rendering; its last line holds the actual reason.
+                    let text = err.to_string();
+                    let reason = text.lines().last().unwrap_or("malformed pattern").trim();
+                    return Err(Box::new(
+                        self.error(format!("invalid regex literal: {reason}")),
+                    ));
+                }
+                self.advance();
+                ExprKind::Literal(Literal::Regex { pattern, flags })
+            }
+            Some(Token::Ident(name)) => {
+                self.advance();
+                ExprKind::Ident(name)
+            }
+            Some(Token::LParen) => {
+                self.advance();
+                if self.eat(&Token::RParen) {
+                    ExprKind::Tuple(Vec::new())
+                } else {
+                    let inner = self.parse_expression()?;
+                    if self.eat(&Token::Comma) {
+                        // The comma makes it a tuple; `(expr)` without one
+                        // stays plain grouping.
+                        let mut elements = vec![inner];
+                        while !self.check(&Token::RParen) {
+                            elements.push(self.parse_expression()?);
+                            if !self.eat(&Token::Comma) {
+                                break;
+                            }
+                        }
+                        self.expect(&Token::RParen)?;
+                        ExprKind::Tuple(elements)
+                    } else {
+                        self.expect(&Token::RParen)?;
+                        inner.kind
+                    }
+                }
+            }
+            Some(Token::LBracket) => {
+                self.advance();
+                let mut elements = Vec::new();
+                while !self.check(&Token::RBracket) {
+                    elements.push(self.parse_spreadable()?);
+                    if !self.eat(&Token::Comma) {
+                        break;
+                    }
+                }
+                self.expect(&Token::RBracket)?;
+                ExprKind::Array(elements)
+            }
+            Some(Token::LBrace) => {
+                self.advance();
+                return self.parse_compound_body(start);
+            }
+            Some(Token::HashBrace) => {
+                self.advance();
+                return self.parse_set_or_map(start);
+            }
+            Some(Token::Type) => {
+                self.advance();
+                return self.parse_type_def(start);
+            }
+            // A loop in expression position yields whatever `break`s it.
+            Some(Token::While) => {
+                let kind = self.parse_while(None)?;
+                let stmt = Statement {
+                    kind,
+                    area: self.area(start),
+                    comments: Vec::new(),
+                    annotations: Vec::new(),
+                };
+                ExprKind::Loop(Box::new(stmt))
+            }
+            Some(Token::For) => {
+                let kind = self.parse_for(None)?;
+                let stmt = Statement {
+                    kind,
+                    area: self.area(start),
+                    comments: Vec::new(),
+                    annotations: Vec::new(),
+                };
+                ExprKind::Loop(Box::new(stmt))
+            }
+            _ => return Err(Box::new(self.error("expected an expression"))),
+        };
+        Ok(Expr {
+            kind,
+            area: self.area(start),
+        })
+    }
+
+    /// Splits a quoted string literal into interpolation parts. Returns
+    /// `Ok(None)` when every `${` turns out to be escaped, in which case
+    /// the caller keeps the plain literal. Embedded expressions are
+    /// parsed in full and their spans shifted so they point back into
+    /// the real source.
+    fn interpolation_parts(
+        &mut self,
+        literal: &str,
+        token_start: usize,
+    ) -> Res<Option<Vec<StringPart>>> {
+        let content = &literal[1..literal.len() - 1];
+        let base = token_start + 1;
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut i = 0;
+        while i < content.len() {
+            let ch = content[i..].chars().next().expect("i is a char boundary");
+            // `\${` (or any other escape) passes through as literal text.
+            if ch == '\\' {
+                let escape_len = ch.len_utf8()
+                    + content[i + ch.len_utf8()..]
+                        .chars()
+                        .next()
+                        .map(char::len_utf8)
+                        .unwrap_or(0);
+                text.push_str(&content[i..i + escape_len]);
+                i += escape_len;
+                continue;
+            }
+            if ch == '$' && content[i + 1..].starts_with('{') {
+                let expr_start = i + 2;
+                let rest = &content[expr_start..];
+                // Balance braces so `${ {a: 1}.a }` style nesting works.
+                let mut depth = 1usize;
+                let mut end = None;
+                for (j, c) in rest.char_indices() {
+                    match c {
+                        '{' => depth += 1,
+                        '}' => {
+                            depth -= 1;
+                            if depth == 0 {
+                                end = Some(j);
+                                break;
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                let Some(end) = end else {
+                    return Err(Box::new(
+                        self.error("unterminated `${..}` string interpolation"),
+                    ));
+                };
+                if !text.is_empty() {
+                    parts.push(StringPart::Text(std::mem::take(&mut text)));
+                }
+                let expr_text = &rest[..end];
+                let mut sub = Parser::new(
+                    CodeSource::Inline(expr_text.to_owned()),
+                    Token::lexer(expr_text),
+                );
+                let mut expr = sub.parse_expression()?;
+                shift_expr(&mut expr, (base + expr_start) as isize);
+                retarget_expr(&mut expr, &self.src);
+                parts.push(StringPart::Expr(expr));
+                i = expr_start + end + 1;
+                continue;
+            }
+            text.push(ch);
+            i += ch.len_utf8();
+        }
+        // Only escaped `${`s were found: not an interpolation after all.
+        if parts.iter().all(|part| matches!(part, StringPart::Text(_))) {
+            return Ok(None);
+        }
+        if !text.is_empty() {
+            parts.push(StringPart::Text(text));
+        }
+        Ok(Some(parts))
+    }
+
+    /// Rejects a quoted string literal with a malformed `\u` escape,
+    /// the report's span covering exactly the bad sequence inside the
+    /// token. Raw strings never decode escapes, so they're exempt.
+    fn check_string_escapes(&mut self, slice: &str, token_start: usize) -> Res<()> {
+        if slice.starts_with('r') {
+            return Ok(());
+        }
+        let Some(((from, to), message)) = crate::ast::check_escapes(slice) else {
+            return Ok(());
+        };
+        let area = CodeArea {
+            src: self.src.clone(),
+            span: (token_start + from, token_start + to),
+        };
+        Err(Box::new(ErrorReport {
+            code: Code::SyntaxError.as_str(),
+            severity: Severity::Error,
+            call_stack: vec![],
+            notes: Vec::new(),
+            help: None,
+            current_module: self.current_module.clone(),
+            position: area.clone(),
+            message,
+            labels: vec![(area, "this escape".to_owned())],
+        }))
+    }
+
+    /// Entry point used by tests that parse a bare value (array/compound/
+    /// literal) without going through a full expression/statement.
+    pub fn parse_value(&mut self) -> Res<Expr> {
+        self.parse_expression()
+    }
+
+    /// Parses an expression and insists it's compile-time constant:
+    /// literals and operations over literals only. Calls, variable
+    /// references, and arrow functions reject with the report pointing
+    /// at the first non-constant sub-expression. The dedicated entry
+    /// point for literal-only contexts (enum discriminants, embedder
+    /// configuration) — ordinary `const` initializers deliberately stay
+    /// full expressions.
+    pub fn parse_const_value(&mut self) -> Res<Expr> {
+        let expr = self.parse_expression()?;
+        if let Some(offender) = first_non_constant(&expr) {
+            let area = offender.area.clone();
+            return Err(Box::new(ErrorReport {
+                code: Code::SyntaxError.as_str(),
+                severity: Severity::Error,
+                call_stack: vec![],
+                notes: Vec::new(),
+                help: None,
+                current_module: self.current_module.clone(),
+                position: area.clone(),
+                message: "not a compile-time constant: only literals and \
+                          operations over literals are allowed here"
+                    .to_owned(),
+                labels: vec![(area, "this is only known at runtime".to_owned())],
+            }));
+        }
+        Ok(expr)
+    }
+
+    fn parse_compound_body(&mut self, start: usize) -> Res<Expr> {
+        let mut fields = Vec::new();
+        while !self.check(&Token::RBrace) {
+            let key_span = self.peek_span();
+            // `[keyExpr]: value` computes its key when the literal is
+            // built; everything else is a static name.
+            if self.eat(&Token::LBracket) {
+                let key = self.parse_expression()?;
+                self.expect(&Token::RBracket)?;
+                self.expect(&Token::Colon)?;
+                let value = self.parse_expression()?;
+                fields.push((CompoundKey::Computed(key), value));
+                if !self.eat(&Token::Comma) {
+                    break;
+                }
+                continue;
+            }
+            match self.advance() {
+                Some(Token::Ident(name)) => {
+                    if self.eat(&Token::Colon) {
+                        let value = self.parse_expression()?;
+                        fields.push((CompoundKey::Static(name), value));
+                    } else {
+                        // `{ name }` is shorthand for `{ name: name }`:
+                        // the value is a reference to the same identifier.
+                        let value = Expr {
+                            kind: ExprKind::Ident(name.clone()),
+                            area: CodeArea {
+                                src: self.src.clone(),
+                                span: (key_span.start, key_span.end),
+                            },
+                        };
+                        fields.push((CompoundKey::Static(name), value));
+                    }
+                }
+                Some(Token::Str(key)) => {
+                    if !self.eat(&Token::Colon) {
+                        return Err(Box::new(self.error(
+                            "shorthand fields need a plain identifier; a quoted key \
+                             must spell out its `: value`",
+                        )));
+                    }
+                    let value = self.parse_expression()?;
+                    fields.push((CompoundKey::Static(key), value));
+                }
+                _ => return Err(Box::new(self.error("expected a field name"))),
+            }
+            if !self.eat(&Token::Comma) {
+                break;
+            }
+        }
+        self.expect(&Token::RBrace)?;
+        Ok(Expr {
+            kind: ExprKind::Compound(fields),
+            area: self.area(start),
+        })
+    }
+
+    /// Parses a `#{..}` literal, the `#{` already consumed. A `=>` after
+    /// an item makes the literal a map; without one it's a set (and the
+    /// empty `#{}` is the empty set, since a map needs at least one
+    /// entry). The two shapes can't mix.
+    fn parse_set_or_map(&mut self, start: usize) -> Res<Expr> {
+        let mut elements = Vec::new();
+        let mut entries = Vec::new();
+        while !self.check(&Token::RBrace) {
+            // A bare-ident key directly before `=>` would otherwise read
+            // as a single-parameter arrow function; inside `#{..}` the
+            // map entry wins (write `(x => e)` for an arrow element).
+            let first = if matches!(self.peek(), Some(Token::Ident(_)))
+                && matches!(self.peek_nth(1), Some(Token::FatArrow)) {
+                let key_start = self.peek_span().start;
+                let name = self.expect_ident()?;
+                Expr {
+                    kind: ExprKind::Ident(name),
+                    area: self.area(key_start),
+                }
+            } else {
+                self.parse_expression()?
+            };
+            if self.eat(&Token::FatArrow) {
+                if !elements.is_empty() {
+                    return Err(Box::new(self.error(
+                        "cannot mix set elements and `key => value` map entries in one `#{..}` literal",
+                    )));
+                }
+                entries.push((first, self.parse_expression()?));
+            } else {
+                if !entries.is_empty() {
+                    return Err(Box::new(self.error(
+                        "cannot mix set elements and `key => value` map entries in one `#{..}` literal",
+                    )));
+                }
+                elements.push(first);
+            }
+            if !self.eat(&Token::Comma) {
+                break;
+            }
+        }
+        self.expect(&Token::RBrace)?;
+        let kind = if entries.is_empty() {
+            ExprKind::Set(elements)
+        } else {
+            ExprKind::Map(entries)
+        };
+        Ok(Expr {
+            kind,
+            area: self.area(start),
+        })
+    }
+
+    fn parse_type_def(&mut self, start: usize) -> Res<Expr> {
+        self.expect(&Token::LBrace)?;
+        let fields = self.parse_type_fields()?;
+        Ok(Expr {
+            kind: ExprKind::TypeDef(fields),
+            area: self.area(start),
+        })
+    }
+
+    /// The shared `name: type, ..` field list of `type { .. }` literals
+    /// and `struct` declarations, up to and including the closing brace.
+    fn parse_type_fields(&mut self) -> Res<Vec<(String, String)>> {
+        let mut fields = Vec::new();
+        while !self.check(&Token::RBrace) {
+            let key = match self.advance() {
+                Some(Token::Ident(name)) => name,
+                Some(Token::Str(s)) => s,
+                _ => return Err(Box::new(self.error("expected a field name"))),
+            };
+            self.expect(&Token::Colon)?;
+            let ty = self.expect_ident()?;
+            fields.push((key, ty));
+            if !self.eat(&Token::Comma) {
+                break;
+            }
+        }
+        self.expect(&Token::RBrace)?;
+        Ok(fields)
+    }
+
+    fn looks_like_arrow_fn(&self) -> bool {
+        let mut depth = 0i32;
+        let mut i = self.pos;
+        while let Some(spanned) = self.tokens.get(i) {
+            match spanned.token {
+                Token::LParen => depth += 1,
+                Token::RParen => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return matches!(
+                            self.tokens.get(i + 1).map(|s| &s.token),
+                            Some(Token::FatArrow)
+                        );
+                    }
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+        false
+    }
+
+    fn parse_arrow_fn(&mut self) -> Res<Expr> {
+        let (kind, span) = self.spanned(|parser| {
+            // `x => ..` binds a single parameter without parentheses; the
+            // parenthesized form goes through the full parameter grammar.
+            let params = if matches!(parser.peek(), Some(Token::Ident(_))) {
+                vec![Param {
+                    name: parser.expect_ident()?,
+                    ty: None,
+                    default: None,
+                    variadic: false,
+                }]
+            } else {
+                parser.parse_params()?
+            };
+            parser.expect(&Token::FatArrow)?;
+            // A `{` after `=>` is always a block body, never a compound
+            // literal — to return one directly, parenthesize: `x => ({a: 1})`.
+            let (body, expr_body) = if parser.check(&Token::LBrace) {
+                (parser.parse_block()?, false)
+            } else {
+                let expr = parser.parse_expression()?;
+                let stmt = Statement {
+                    area: expr.area.clone(),
+                    kind: StatementKind::Return(Some(expr)),
+                    comments: Vec::new(),
+                    annotations: Vec::new(),
+                };
+                (vec![stmt], true)
+            };
+            Ok(ExprKind::ArrowFn {
+                params,
+                body,
+                expr_body,
+            })
+        })?;
+        Ok(Expr {
+            kind,
+            area: self.area_from(span),
+        })
+    }
+
+    // ---- match expressions (see ast::Pattern) ---------------------------
+
+    fn parse_match(&mut self) -> Res<Expr> {
+        let (kind, span) = self.spanned(|parser| {
+            parser.advance();
+            let subject = parser.parse_expression()?;
+            parser.expect(&Token::LBrace)?;
+            let mut arms = Vec::new();
+            while !parser.check(&Token::RBrace) {
+                let pattern = parser.parse_pattern()?;
+                if !parser.eat(&Token::FatArrow) {
+                    return Err(Box::new(
+                        parser.error("expected `=>` between a match arm's pattern and its body"),
+                    ));
+                }
+                let body = parser.parse_expression()?;
+                arms.push(MatchArm {
+                    pattern,
+                    body: Box::new(body),
+                });
+                if !parser.eat(&Token::Comma) {
+                    break;
+                }
+            }
+            parser.expect(&Token::RBrace)?;
+            Ok(ExprKind::Match {
+                subject: Box::new(subject),
+                arms,
+            })
+        })?;
+        Ok(Expr {
+            kind,
+            area: self.area_from(span),
+        })
+    }
+
+    fn parse_pattern(&mut self) -> Res<Pattern> {
+        match self.peek().cloned() {
+            Some(Token::Ident(name)) if name == "_" => {
+                self.advance();
+                Ok(Pattern::Wildcard)
+            }
+            Some(Token::Int(n)) => {
+                let slice = self
+                    .source
+                    .get(self.peek_span())
+                    .unwrap_or_default()
+                    .to_owned();
+                self.advance();
+                Ok(Pattern::Literal(Literal::Int(n, IntBase::from_source(&slice))))
+            }
+            Some(Token::Float(n)) => {
+                self.advance();
+                Ok(Pattern::Literal(Literal::Float(n)))
+            }
+            Some(Token::Str(s)) => {
+                self.advance();
+                Ok(Pattern::Literal(Literal::Str(s)))
+            }
+            Some(Token::True) => {
+                self.advance();
+                Ok(Pattern::Literal(Literal::Bool(true)))
+            }
+            Some(Token::False) => {
+                self.advance();
+                Ok(Pattern::Literal(Literal::Bool(false)))
+            }
+            Some(Token::Nil) => {
+                self.advance();
+                Ok(Pattern::Literal(Literal::Nil))
+            }
+            Some(Token::Ident(name)) => {
+                self.advance();
+                Ok(Pattern::Binding(name))
+            }
+            Some(Token::LBracket) => {
+                self.advance();
+                let mut elements = Vec::new();
+                let mut rest = None;
+                while !self.check(&Token::RBracket) {
+                    if let Some(Token::Ident(name)) = self.peek().cloned() {
+                        if matches!(self.tokens.get(self.pos + 1).map(|s| &s.token), Some(Token::DotDot)) {
+                            self.advance();
+                            self.advance();
+                            rest = Some(name);
+                            break;
+                        }
+                    }
+                    elements.push(self.parse_pattern()?);
+                    if !self.eat(&Token::Comma) {
+                        break;
+                    }
+                }
+                self.expect(&Token::RBracket)?;
+                Ok(Pattern::Array { elements, rest })
+            }
+            Some(Token::LBrace) => {
+                self.advance();
+                let mut fields = Vec::new();
+                let mut open = false;
+                while !self.check(&Token::RBrace) {
+                    if self.eat(&Token::DotDot) {
+                        open = true;
+                        break;
+                    }
+                    let name = self.expect_ident()?;
+                    let binding = if self.eat(&Token::Colon) {
+                        self.parse_pattern()?
+                    } else {
+                        Pattern::Binding(name.clone())
+                    };
+                    fields.push((name, binding));
+                    if !self.eat(&Token::Comma) {
+                        break;
+                    }
+                }
+                self.expect(&Token::RBrace)?;
+                Ok(Pattern::Compound { fields, open })
+            }
+            _ => Err(Box::new(self.error("expected a pattern"))),
+        }
+    }
+
+    // ---- pipeline integration (see `validate`, `optimize`) --------------
+
+    /// Chains a stage onto this parser, producing a [`ParserPipeline`].
+    /// More stages can follow via [`ParserPipeline::then_pipe`], and
+    /// [`ParserPipeline::finish_pipeline`] runs them all in order,
+    /// merging every stage's diagnostics.
+    pub fn then_pipe(self, stage: impl PipelineStage + 'static) -> ParserPipeline {
+        ParserPipeline {
+            parser: self,
+            stages: vec![Box::new(stage)],
+        }
+    }
+}
+
+/// Extracts every `//`/`/* */` comment in `source[from..to]`, a gap the
+/// lexer guarantees holds only whitespace and comments.
+fn scan_comments(source: &str, from: usize, to: usize, out: &mut Vec<Comment>) {
+    let Some(gap) = source.get(from..to) else {
+        return;
+    };
+    let bytes = gap.as_bytes();
+    let mut i = 0;
+    while i + 1 < bytes.len() {
+        if bytes[i] == b'/' && bytes[i + 1] == b'/' {
+            let end = gap[i..].find('\n').map(|n| i + n).unwrap_or(gap.len());
+            out.push(Comment {
+                text: gap[i..end].to_owned(),
+                span: (from + i, from + end),
+                doc: gap[i..].starts_with("///"),
+            });
+            i = end;
+        } else if bytes[i] == b'/' && bytes[i + 1] == b'*' {
+            let end = gap[i..].find("*/").map(|n| i + n + 2).unwrap_or(gap.len());
+            out.push(Comment {
+                text: gap[i..end].to_owned(),
+                span: (from + i, from + end),
+                doc: false,
+            });
+            i = end;
+        } else {
+            i += 1;
+        }
+    }
+}
+
+/// Routes one comment to its statement: trailing on the statement it
+/// shares a line with, otherwise leading on the next statement (recursing
+/// into nested blocks when the comment sits inside one), falling back to
+/// the last statement for a comment after everything.
+fn attach_comment(stmts: &mut [Statement], comment: Comment, source: &str) -> Option<Comment> {
+    for i in 0..stmts.len() {
+        let (start, end) = stmts[i].area.span;
+        if start >= comment.span.1 {
+            if i > 0 {
+                let prev_end = stmts[i - 1].area.span.1;
+                let same_line = source
+                    .get(prev_end..comment.span.0)
+                    .is_some_and(|gap| !gap.contains('\n'));
+                if same_line {
+                    stmts[i - 1].comments.push(comment);
+                    return None;
+                }
+            }
+            stmts[i].comments.push(comment);
+            return None;
+        }
+        if end > comment.span.0 {
+            let comment = descend_comment(&mut stmts[i], comment, source)?;
+            stmts[i].comments.push(comment);
+            return None;
+        }
+    }
+    match stmts.last_mut() {
+        Some(last) => {
+            last.comments.push(comment);
+            None
+        }
+        None => Some(comment),
+    }
+}
+
+/// Tries each nested statement block of `stmt` for a home for `comment`.
+fn descend_comment(stmt: &mut Statement, comment: Comment, source: &str) -> Option<Comment> {
+    let mut comment = Some(comment);
+    let try_block = |body: &mut [Statement], comment: &mut Option<Comment>| {
+        if let Some(c) = comment.take() {
+            *comment = attach_comment(body, c, source);
+        }
+    };
+    match &mut stmt.kind {
+        StatementKind::Fn { body, .. }
+        | StatementKind::For { body, .. }
+        | StatementKind::While { body, .. }
+        | StatementKind::DoWhile { body, .. } => try_block(body, &mut comment),
+        StatementKind::If { branches, otherwise } => {
+            for (_, body) in branches {
+                try_block(body, &mut comment);
+            }
+            if let Some(otherwise) = otherwise {
+                try_block(otherwise, &mut comment);
+            }
+        }
+        StatementKind::Try { body, catches } => {
+            try_block(body, &mut comment);
+            for arm in catches {
+                try_block(&mut arm.body, &mut comment);
+            }
+        }
+        _ => {}
+    }
+    comment
+}
+
+/// One stage of the parse pipeline: takes the tree produced so far, may
+/// transform it, and contributes whatever diagnostics it finds. The
+/// [`Validator`] inspects without transforming; the optimizer's
+/// `ConstFolder` rewrites the tree. Stages chain via
+/// [`Parser::then_pipe`]/[`ParserPipeline::then_pipe`] and run in the
+/// order they were queued.
+pub trait PipelineStage {
+    fn run(&mut self, stmts: Vec<Statement>) -> (Vec<Statement>, Vec<ErrorReport>);
+}
+
+impl PipelineStage for Validator {
+    fn run(&mut self, stmts: Vec<Statement>) -> (Vec<Statement>, Vec<ErrorReport>) {
+        let reports = self.validate(&stmts);
+        (stmts, reports)
+    }
+}
+
+/// A parser bound to the pipeline stages queued after it; see
+/// [`Parser::then_pipe`].
+pub struct ParserPipeline {
+    parser: Parser,
+    stages: Vec<Box<dyn PipelineStage>>,
+}
+
+impl ParserPipeline {
+    /// Queues another stage after the ones already chained.
+    pub fn then_pipe(mut self, stage: impl PipelineStage + 'static) -> Self {
+        self.stages.push(Box::new(stage));
+        self
+    }
+
+    /// Runs parsing then every queued stage, threading the (possibly
+    /// transformed) tree through and merging every diagnostic. Unlike a
+    /// single `Res<T>`, failure here carries the *whole* batch of reports
+    /// rather than just the first one, matching the error-recovery
+    /// philosophy of the stages it chains together.
+    pub fn finish_pipeline(mut self) -> Result<Vec<Statement>, Vec<ErrorReport>> {
+        // `parse()` never returns `Err` itself; parse errors are collected
+        // into `self.errors` (panic-mode recovery) and drained below.
+        let mut stmts = self.parser.parse().expect("parse() always returns Ok");
+        let mut reports = self.parser.take_errors();
+        for stage in &mut self.stages {
+            let (transformed, found) = stage.run(stmts);
+            stmts = transformed;
+            reports.extend(found);
+        }
+        if reports.is_empty() {
+            Ok(stmts)
+        } else {
+            Err(reports)
+        }
+    }
+}
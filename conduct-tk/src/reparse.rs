@@ -0,0 +1,654 @@
+//! Incremental reparsing for editor/LSP integration: apply a single-edit
+//! diff to a previously parsed document and only redo the work the edit
+//! actually invalidates.
+//!
+//! [`Parser::new`] always re-lexes and re-parses a whole buffer, which is
+//! fine for a one-shot `compile`/`check` but too slow to run on every
+//! keystroke. [`ReparseSession`] keeps the last parse around, re-lexes only
+//! the text spanning the edited statements, reparses just those, and shifts
+//! every span after the edit by the length delta so the rest of the tree
+//! stays byte-accurate without being touched.
+
+use std::ops::Range;
+
+use logos::Logos;
+
+use crate::{
+    ast::{CatchArm, Expr, ExprKind, Statement, StatementKind},
+    err::CodeSource,
+    parser::Parser,
+    tk::Token,
+};
+
+/// A parsed document plus enough state to cheaply reparse it after a
+/// single text edit.
+pub struct ReparseSession {
+    source: String,
+    src: CodeSource,
+    statements: Vec<Statement>,
+}
+
+/// The result of [`ReparseSession::apply_edit`]: the statements whose
+/// spans moved or whose contents were reparsed, as `(start, end)` byte
+/// ranges in the *new* source. A caller (LSP diagnostics loop) only needs
+/// to re-validate these, not the whole document.
+pub struct ReparseOutcome {
+    pub changed_spans: Vec<(usize, usize)>,
+}
+
+impl ReparseSession {
+    /// Parses `source` from scratch and starts a session over it.
+    pub fn new(src: CodeSource, source: String) -> Self {
+        let mut parser = Parser::new(src.clone(), Token::lexer(&source));
+        let statements = parser.parse().unwrap_or_default();
+        Self {
+            source,
+            src,
+            statements,
+        }
+    }
+
+    pub fn statements(&self) -> &[Statement] {
+        &self.statements
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Applies a `(range_removed, text_inserted)` edit: the bytes in
+    /// `range_removed` (against the *current* source) are replaced with
+    /// `text_inserted`. Only the statements overlapping that range are
+    /// re-lexed and reparsed; everything else keeps its parsed subtree and
+    /// just has its span shifted by the length delta.
+    pub fn apply_edit(&mut self, range_removed: Range<usize>, text_inserted: &str) -> ReparseOutcome {
+        let delta = text_inserted.len() as isize - (range_removed.end - range_removed.start) as isize;
+
+        let mut new_source = String::with_capacity(self.source.len());
+        new_source.push_str(&self.source[..range_removed.start]);
+        new_source.push_str(text_inserted);
+        new_source.push_str(&self.source[range_removed.end..]);
+
+        // `self.src` is only a snapshot for `CodeSource::Inline` sessions
+        // (a `File` source re-reads from disk on demand); refresh it now so
+        // `retarget_statement` below stamps diagnostics against the buffer
+        // as it stands after this edit, not the one from `new()`.
+        if let CodeSource::Inline(_) = self.src {
+            self.src = CodeSource::Inline(new_source.clone());
+        }
+
+        // A bounded reparse only ever re-lexes the statements the edit's
+        // byte range overlaps, so it can't notice brace nesting changing
+        // *around* that range — e.g. inserting `if true {` before two
+        // existing statements now wraps both of them in a block the
+        // bounded region never looks at. Detect that by comparing how
+        // many braces the edit adds vs. removes: a non-zero delta means
+        // nesting shifted, and the only sound response is a full reparse.
+        let removed_text = &self.source[range_removed.clone()];
+        if brace_delta(text_inserted) != brace_delta(removed_text) {
+            return self.full_reparse(new_source);
+        }
+
+        // Find the widest contiguous run of statements whose span overlaps
+        // the edited range; they're the ones we actually reparse. Anything
+        // outside that run is reused untouched aside from a span shift.
+        // The comparisons are inclusive on both ends so an edit that lands
+        // exactly on a statement's boundary (e.g. typing right after an
+        // identifier, extending it) still pulls that statement in, instead
+        // of reparsing only the inserted text as a disconnected statement.
+        let mut dirty_start = None;
+        let mut dirty_end = 0;
+        for (i, stmt) in self.statements.iter().enumerate() {
+            let (start, end) = stmt.area.span;
+            if start <= range_removed.end && end >= range_removed.start {
+                dirty_start.get_or_insert(i);
+                dirty_end = i + 1;
+            }
+        }
+
+        let (region_start, region_end_old, replaced_range) = match dirty_start {
+            Some(start) => {
+                let region_start = self.statements[start].area.span.0;
+                let region_end_old = self.statements[dirty_end - 1].area.span.1;
+                (region_start, region_end_old, start..dirty_end)
+            }
+            // The edit landed in whitespace between statements (or the
+            // document is empty); nothing existing needs reparsing, but we
+            // still need to parse whatever text now sits at the edit site,
+            // and splice it in at the right position rather than the front.
+            None => {
+                let insert_at = self
+                    .statements
+                    .iter()
+                    .position(|stmt| stmt.area.span.0 >= range_removed.end)
+                    .unwrap_or(self.statements.len());
+                (range_removed.start, range_removed.end, insert_at..insert_at)
+            }
+        };
+        // `region_start` came from the *old* source; if the dirty run starts
+        // inside the removed range it no longer denotes anything meaningful
+        // in `new_source` (that byte range was just overwritten), so clamp
+        // it down to where the edit itself begins. Likewise `region_end_new`
+        // must cover at least the freshly inserted text, and never run past
+        // the end of the new buffer.
+        let region_start = region_start.min(range_removed.start);
+        let region_end_new = (region_end_old as isize + delta)
+            .max(region_start as isize)
+            .max((range_removed.start + text_inserted.len()) as isize) as usize;
+        let region_end_new = region_end_new.min(new_source.len());
+
+        // Shift every statement after the dirty run so its spans still
+        // line up with `new_source`; these subtrees are moved, not cloned
+        // or reparsed.
+        for stmt in self.statements.iter_mut().skip(replaced_range.end) {
+            shift_statement(stmt, delta);
+        }
+
+        let region_text = &new_source[region_start..region_end_new];
+        let mut region_parser = Parser::new(
+            CodeSource::Inline(region_text.to_owned()),
+            Token::lexer(region_text),
+        );
+        let mut reparsed = region_parser.parse().unwrap_or_default();
+        for stmt in &mut reparsed {
+            shift_statement(stmt, region_start as isize);
+        }
+
+        let changed_spans = reparsed.iter().map(|s| s.area.span).collect();
+        self.statements.splice(replaced_range, reparsed);
+        self.source = new_source;
+
+        // `CodeSource::Inline` embeds a copy of the source text, so every
+        // statement outside the reparsed region — shifted above, not
+        // reparsed — still points at whatever snapshot `self.src` held
+        // before this edit. Re-stamp the whole tree now that `self.src` is
+        // current; for `CodeSource::File` this is a no-op clone of the
+        // same path, so it's cheap to do unconditionally. For a large
+        // `Inline` document this does mean every node's source copy is
+        // recloned on every edit rather than just the reparsed ones —
+        // correctness over micro-optimizing a path `CodeSource` doesn't
+        // cheaply support today (it owns a `String`, not a shared handle).
+        let src = self.src.clone();
+        for stmt in &mut self.statements {
+            retarget_statement(stmt, &src);
+        }
+
+        ReparseOutcome { changed_spans }
+    }
+
+    /// Re-lexes and re-parses the whole document from scratch, discarding
+    /// the previous tree entirely. Used when an edit's brace-nesting change
+    /// can reach beyond what a bounded reparse inspects, so every span is
+    /// reported as changed — the caller has no narrower region to trust.
+    fn full_reparse(&mut self, new_source: String) -> ReparseOutcome {
+        let mut parser = Parser::new(self.src.clone(), Token::lexer(&new_source));
+        self.statements = parser.parse().unwrap_or_default();
+        self.source = new_source;
+        let changed_spans = self.statements.iter().map(|s| s.area.span).collect();
+        ReparseOutcome { changed_spans }
+    }
+}
+
+/// Counts `{` minus `}` occurrences in `text`. Used to detect whether an
+/// edit changes brace nesting rather than just its own contents.
+fn brace_delta(text: &str) -> isize {
+    text.chars().fold(0isize, |delta, ch| match ch {
+        '{' => delta + 1,
+        '}' => delta - 1,
+        _ => delta,
+    })
+}
+
+/// Adds `delta` to every [`crate::err::CodeArea`] span reachable from
+/// `stmt`, recursing into nested statements/expressions so the whole
+/// subtree stays consistent after surrounding text moves.
+fn shift_statement(stmt: &mut Statement, delta: isize) {
+    stmt.area.span.0 = (stmt.area.span.0 as isize + delta).max(0) as usize;
+    stmt.area.span.1 = (stmt.area.span.1 as isize + delta).max(0) as usize;
+    match &mut stmt.kind {
+        StatementKind::Let { ty, value, .. } | StatementKind::Const { ty, value, .. } => {
+            if let Some(ty) = ty {
+                shift_expr(ty, delta);
+            }
+            if let Some(value) = value {
+                shift_expr(value, delta);
+            }
+        }
+        StatementKind::LetTuple { value, .. } => shift_expr(value, delta),
+        StatementKind::Fn { params, ret, body, .. } => {
+            for param in params {
+                if let Some(ty) = &mut param.ty {
+                    shift_expr(ty, delta);
+                }
+                if let Some(default) = &mut param.default {
+                    shift_expr(default, delta);
+                }
+            }
+            if let Some(ret) = ret {
+                shift_expr(ret, delta);
+            }
+            shift_block(body, delta);
+        }
+        StatementKind::Assign { target, value, .. } => {
+            shift_expr(target, delta);
+            shift_expr(value, delta);
+        }
+        StatementKind::AssignChain { targets, value } => {
+            for target in targets {
+                shift_expr(target, delta);
+            }
+            shift_expr(value, delta);
+        }
+        StatementKind::AssignParallel { targets, values } => {
+            for target in targets {
+                shift_expr(target, delta);
+            }
+            for value in values {
+                shift_expr(value, delta);
+            }
+        }
+        StatementKind::If { branches, otherwise } => {
+            for (cond, body) in branches {
+                shift_expr(cond, delta);
+                shift_block(body, delta);
+            }
+            if let Some(otherwise) = otherwise {
+                shift_block(otherwise, delta);
+            }
+        }
+        StatementKind::For { iterable, body, .. } => {
+            shift_expr(iterable, delta);
+            shift_block(body, delta);
+        }
+        StatementKind::While { cond, body, .. } => {
+            shift_expr(cond, delta);
+            shift_block(body, delta);
+        }
+        StatementKind::IfLet {
+            value,
+            body,
+            otherwise,
+            ..
+        } => {
+            shift_expr(value, delta);
+            shift_block(body, delta);
+            if let Some(otherwise) = otherwise {
+                shift_block(otherwise, delta);
+            }
+        }
+        StatementKind::WhileLet { value, body, .. } => {
+            shift_expr(value, delta);
+            shift_block(body, delta);
+        }
+        StatementKind::DoWhile { body, cond } => {
+            shift_block(body, delta);
+            shift_expr(cond, delta);
+        }
+        StatementKind::Return(Some(expr)) | StatementKind::Throw(expr) => shift_expr(expr, delta),
+        StatementKind::Assert { lhs, rhs, message } => {
+            shift_expr(lhs, delta);
+            if let Some(rhs) = rhs {
+                shift_expr(rhs, delta);
+            }
+            if let Some(message) = message {
+                shift_expr(message, delta);
+            }
+        }
+        StatementKind::Try { body, catches } => {
+            shift_block(body, delta);
+            for arm in catches {
+                shift_catch_arm(arm, delta);
+            }
+        }
+        StatementKind::Expr(expr) => shift_expr(expr, delta),
+        StatementKind::Break { value, .. } => {
+            if let Some(value) = value {
+                shift_expr(value, delta);
+            }
+        }
+        StatementKind::Return(None)
+        | StatementKind::Continue(_)
+        | StatementKind::Module(_)
+        | StatementKind::Import { .. }
+        | StatementKind::SelectiveImport { .. }
+        | StatementKind::Include(_)
+        | StatementKind::Export(_)
+        | StatementKind::SelectiveExport { .. }
+        | StatementKind::Enum { .. }
+        | StatementKind::Struct { .. }
+        | StatementKind::Error => {}
+    }
+}
+
+fn shift_block(stmts: &mut [Statement], delta: isize) {
+    for stmt in stmts {
+        shift_statement(stmt, delta);
+    }
+}
+
+fn shift_catch_arm(arm: &mut CatchArm, delta: isize) {
+    shift_block(&mut arm.body, delta);
+}
+
+pub(crate) fn shift_expr(expr: &mut Expr, delta: isize) {
+    expr.area.span.0 = (expr.area.span.0 as isize + delta).max(0) as usize;
+    expr.area.span.1 = (expr.area.span.1 as isize + delta).max(0) as usize;
+    match &mut expr.kind {
+        ExprKind::Array(elements) | ExprKind::Tuple(elements) | ExprKind::Set(elements) => {
+            for element in elements {
+                shift_expr(element, delta);
+            }
+        }
+        ExprKind::Map(entries) => {
+            for (key, value) in entries {
+                shift_expr(key, delta);
+                shift_expr(value, delta);
+            }
+        }
+        ExprKind::InterpolatedString(parts) => {
+            for part in parts {
+                if let crate::ast::StringPart::Expr(expr) = part {
+                    shift_expr(expr, delta);
+                }
+            }
+        }
+        ExprKind::Compound(fields) => {
+            for (key, value) in fields {
+                if let crate::ast::CompoundKey::Computed(key) = key {
+                    shift_expr(key, delta);
+                }
+                shift_expr(value, delta);
+            }
+        }
+        ExprKind::Unary(_, inner)
+        | ExprKind::TypeOf(inner)
+        | ExprKind::Try(inner)
+        | ExprKind::PostIncrement(inner)
+        | ExprKind::PostDecrement(inner)
+        | ExprKind::Spread(inner) => shift_expr(inner, delta),
+        ExprKind::Loop(stmt) => shift_statement(stmt, delta),
+        ExprKind::Binary(_, lhs, rhs) => {
+            shift_expr(lhs, delta);
+            shift_expr(rhs, delta);
+        }
+        ExprKind::Ternary { cond, then, otherwise } => {
+            shift_expr(cond, delta);
+            shift_expr(then, delta);
+            shift_expr(otherwise, delta);
+        }
+        ExprKind::Call { callee, args } => {
+            shift_expr(callee, delta);
+            for arg in args {
+                shift_expr(arg, delta);
+            }
+        }
+        ExprKind::Index { target, index } => {
+            shift_expr(target, delta);
+            shift_expr(index, delta);
+        }
+        ExprKind::Member { target, .. } | ExprKind::OptionalAccess { target, .. } => {
+            shift_expr(target, delta)
+        }
+        ExprKind::Range { start, end, step, .. } => {
+            shift_expr(start, delta);
+            shift_expr(end, delta);
+            if let Some(step) = step {
+                shift_expr(step, delta);
+            }
+        }
+        ExprKind::NullCoalesce(lhs, rhs) => {
+            shift_expr(lhs, delta);
+            shift_expr(rhs, delta);
+        }
+        ExprKind::ArrowFn { params, body, .. } => {
+            for param in params {
+                if let Some(ty) = &mut param.ty {
+                    shift_expr(ty, delta);
+                }
+                if let Some(default) = &mut param.default {
+                    shift_expr(default, delta);
+                }
+            }
+            shift_block(body, delta);
+        }
+        ExprKind::Match { subject, arms } => {
+            shift_expr(subject, delta);
+            for arm in arms {
+                shift_expr(&mut arm.body, delta);
+            }
+        }
+        ExprKind::Literal(_) | ExprKind::Ident(_) | ExprKind::Path(_) | ExprKind::TypeDef(_) | ExprKind::Garbage => {}
+    }
+}
+
+/// After reparsing a region in isolation (its own `CodeSource::Inline`),
+/// re-stamps every span with the session's real source so diagnostics
+/// point at the document the editor actually has open.
+fn retarget_statement(stmt: &mut Statement, src: &CodeSource) {
+    stmt.area.src = src.clone();
+    match &mut stmt.kind {
+        StatementKind::Let { ty, value, .. } | StatementKind::Const { ty, value, .. } => {
+            if let Some(ty) = ty {
+                retarget_expr(ty, src);
+            }
+            if let Some(value) = value {
+                retarget_expr(value, src);
+            }
+        }
+        StatementKind::LetTuple { value, .. } => retarget_expr(value, src),
+        StatementKind::Fn { params, ret, body, .. } => {
+            for param in params {
+                if let Some(ty) = &mut param.ty {
+                    retarget_expr(ty, src);
+                }
+                if let Some(default) = &mut param.default {
+                    retarget_expr(default, src);
+                }
+            }
+            if let Some(ret) = ret {
+                retarget_expr(ret, src);
+            }
+            for s in body {
+                retarget_statement(s, src);
+            }
+        }
+        StatementKind::Assign { target, value, .. } => {
+            retarget_expr(target, src);
+            retarget_expr(value, src);
+        }
+        StatementKind::AssignChain { targets, value } => {
+            for target in targets {
+                retarget_expr(target, src);
+            }
+            retarget_expr(value, src);
+        }
+        StatementKind::AssignParallel { targets, values } => {
+            for target in targets {
+                retarget_expr(target, src);
+            }
+            for value in values {
+                retarget_expr(value, src);
+            }
+        }
+        StatementKind::If { branches, otherwise } => {
+            for (cond, body) in branches {
+                retarget_expr(cond, src);
+                for s in body {
+                    retarget_statement(s, src);
+                }
+            }
+            if let Some(otherwise) = otherwise {
+                for s in otherwise {
+                    retarget_statement(s, src);
+                }
+            }
+        }
+        StatementKind::For { iterable, body, .. } => {
+            retarget_expr(iterable, src);
+            for s in body {
+                retarget_statement(s, src);
+            }
+        }
+        StatementKind::While { cond, body, .. } => {
+            retarget_expr(cond, src);
+            for s in body {
+                retarget_statement(s, src);
+            }
+        }
+        StatementKind::IfLet {
+            value,
+            body,
+            otherwise,
+            ..
+        } => {
+            retarget_expr(value, src);
+            for s in body {
+                retarget_statement(s, src);
+            }
+            for s in otherwise.iter_mut().flatten() {
+                retarget_statement(s, src);
+            }
+        }
+        StatementKind::WhileLet { value, body, .. } => {
+            retarget_expr(value, src);
+            for s in body {
+                retarget_statement(s, src);
+            }
+        }
+        StatementKind::DoWhile { body, cond } => {
+            for s in body {
+                retarget_statement(s, src);
+            }
+            retarget_expr(cond, src);
+        }
+        StatementKind::Return(Some(expr)) | StatementKind::Throw(expr) => retarget_expr(expr, src),
+        StatementKind::Assert { lhs, rhs, message } => {
+            retarget_expr(lhs, src);
+            if let Some(rhs) = rhs {
+                retarget_expr(rhs, src);
+            }
+            if let Some(message) = message {
+                retarget_expr(message, src);
+            }
+        }
+        StatementKind::Try { body, catches } => {
+            for s in body {
+                retarget_statement(s, src);
+            }
+            for arm in catches {
+                for s in &mut arm.body {
+                    retarget_statement(s, src);
+                }
+            }
+        }
+        StatementKind::Expr(expr) => retarget_expr(expr, src),
+        StatementKind::Break { value, .. } => {
+            if let Some(value) = value {
+                retarget_expr(value, src);
+            }
+        }
+        StatementKind::Return(None)
+        | StatementKind::Continue(_)
+        | StatementKind::Module(_)
+        | StatementKind::Import { .. }
+        | StatementKind::SelectiveImport { .. }
+        | StatementKind::Include(_)
+        | StatementKind::Export(_)
+        | StatementKind::SelectiveExport { .. }
+        | StatementKind::Enum { .. }
+        | StatementKind::Struct { .. }
+        | StatementKind::Error => {}
+    }
+}
+
+pub(crate) fn retarget_expr(expr: &mut Expr, src: &CodeSource) {
+    expr.area.src = src.clone();
+    match &mut expr.kind {
+        ExprKind::Array(elements) | ExprKind::Tuple(elements) | ExprKind::Set(elements) => {
+            for element in elements {
+                retarget_expr(element, src);
+            }
+        }
+        ExprKind::Map(entries) => {
+            for (key, value) in entries {
+                retarget_expr(key, src);
+                retarget_expr(value, src);
+            }
+        }
+        ExprKind::InterpolatedString(parts) => {
+            for part in parts {
+                if let crate::ast::StringPart::Expr(expr) = part {
+                    retarget_expr(expr, src);
+                }
+            }
+        }
+        ExprKind::Compound(fields) => {
+            for (key, value) in fields {
+                if let crate::ast::CompoundKey::Computed(key) = key {
+                    retarget_expr(key, src);
+                }
+                retarget_expr(value, src);
+            }
+        }
+        ExprKind::Unary(_, inner)
+        | ExprKind::TypeOf(inner)
+        | ExprKind::Try(inner)
+        | ExprKind::PostIncrement(inner)
+        | ExprKind::PostDecrement(inner)
+        | ExprKind::Spread(inner) => retarget_expr(inner, src),
+        ExprKind::Loop(stmt) => retarget_statement(stmt, src),
+        ExprKind::Binary(_, lhs, rhs) => {
+            retarget_expr(lhs, src);
+            retarget_expr(rhs, src);
+        }
+        ExprKind::Ternary { cond, then, otherwise } => {
+            retarget_expr(cond, src);
+            retarget_expr(then, src);
+            retarget_expr(otherwise, src);
+        }
+        ExprKind::Call { callee, args } => {
+            retarget_expr(callee, src);
+            for arg in args {
+                retarget_expr(arg, src);
+            }
+        }
+        ExprKind::Index { target, index } => {
+            retarget_expr(target, src);
+            retarget_expr(index, src);
+        }
+        ExprKind::Member { target, .. } | ExprKind::OptionalAccess { target, .. } => {
+            retarget_expr(target, src)
+        }
+        ExprKind::Range { start, end, step, .. } => {
+            retarget_expr(start, src);
+            retarget_expr(end, src);
+            if let Some(step) = step {
+                retarget_expr(step, src);
+            }
+        }
+        ExprKind::NullCoalesce(lhs, rhs) => {
+            retarget_expr(lhs, src);
+            retarget_expr(rhs, src);
+        }
+        ExprKind::ArrowFn { params, body, .. } => {
+            for param in params {
+                if let Some(ty) = &mut param.ty {
+                    retarget_expr(ty, src);
+                }
+                if let Some(default) = &mut param.default {
+                    retarget_expr(default, src);
+                }
+            }
+            for s in body {
+                retarget_statement(s, src);
+            }
+        }
+        ExprKind::Match { subject, arms } => {
+            retarget_expr(subject, src);
+            for arm in arms {
+                retarget_expr(&mut arm.body, src);
+            }
+        }
+        ExprKind::Literal(_) | ExprKind::Ident(_) | ExprKind::Path(_) | ExprKind::TypeDef(_) | ExprKind::Garbage => {}
+    }
+}
@@ -0,0 +1,523 @@
+//! Lexical tokens produced by [`logos`] for the Conduct language.
+
+use logos::Logos;
+
+/// A single lexical token, along with the trivia Conduct chooses to skip
+/// (whitespace and comments never reach the parser).
+#[derive(Logos, Debug, Clone, PartialEq)]
+#[logos(skip r"[ \t\r\n\f]+")]
+#[logos(skip r"//[^\n]*")]
+#[logos(skip r"/\*([^*]|\*[^/])*\*/")]
+pub enum Token {
+    // `_` on its own lexes as an `Ident` too (there's no separate wildcard
+    // token) since a dedicated `#[token("_")]` variant would match the same
+    // input as this regex with no way for logos to prioritize between them;
+    // callers special-case the string `"_"` wherever they need a wildcard.
+    #[regex(r"[A-Za-z_][A-Za-z0-9_]*", |lex| lex.slice().to_owned())]
+    Ident(String),
+
+    #[regex(r"0[xX][0-9a-fA-F_]+", |lex| int_literal(&lex.slice()[2..], 16))]
+    #[regex(r"0[bB][01_]+", |lex| int_literal(&lex.slice()[2..], 2))]
+    #[regex(r"0[oO][0-7_]+", |lex| int_literal(&lex.slice()[2..], 8))]
+    #[regex(r"[0-9][0-9_]*", |lex| int_literal(lex.slice(), 10))]
+    Int(i64),
+
+    // A float needs a digit on *both* sides of its dot unless the dot
+    // leads (`.5`), so `1.method()` keeps lexing as `1` `.` `method` and
+    // `0..10` as `0` `..` `10` rather than as malformed floats.
+    // C99 hex floats: `0x1.8p3` is 1.5 * 2^3. The exponent marker is
+    // required (it's what separates these from hex-int member access);
+    // its digits are validated in the callback so `0x1.8p` rejects.
+    #[regex(r"0[xX][0-9a-fA-F]+(\.[0-9a-fA-F]+)?[pP][+-]?[0-9]*", hex_float)]
+    #[regex(r"[0-9]+\.[0-9]+([eE][+-]?[0-9]+)?", |lex| lex.slice().parse().ok())]
+    #[regex(r"\.[0-9]+([eE][+-]?[0-9]+)?", |lex| lex.slice().parse().ok())]
+    #[regex(r"[0-9]+[eE][+-]?[0-9]+", |lex| lex.slice().parse().ok())]
+    Float(f64),
+
+    // Like the quoted forms, raw strings keep their full source slice
+    // (delimiters included); `Literal::str_value` in the ast module is
+    // what decodes any of them into actual text.
+    #[regex(r#""([^"\\]|\\.)*""#, |lex| lex.slice().to_owned())]
+    #[regex(r"'([^'\\]|\\.)*'", |lex| lex.slice().to_owned())]
+    #[regex(r##"r#*""##, raw_string)]
+    Str(String),
+
+    #[token("true")]
+    True,
+    #[token("false")]
+    False,
+    #[token("nil")]
+    Nil,
+
+    #[token("let")]
+    Let,
+    #[token("const")]
+    Const,
+    #[token("native")]
+    Native,
+    #[token("fn")]
+    #[token("fun")]
+    Fn,
+    #[token("if")]
+    If,
+    #[token("else")]
+    Else,
+    #[token("for")]
+    For,
+    #[token("while")]
+    While,
+    #[token("do")]
+    Do,
+    #[token("in")]
+    In,
+    #[token("return")]
+    Return,
+    #[token("break")]
+    Break,
+    #[token("continue")]
+    Continue,
+    #[token("throw")]
+    Throw,
+    #[token("try")]
+    Try,
+    #[token("catch")]
+    Catch,
+    #[token("module")]
+    Module,
+    #[token("import")]
+    Import,
+    #[token("include")]
+    Include,
+    #[token("export")]
+    Export,
+    #[token("type")]
+    Type,
+    #[token("match")]
+    Match,
+    #[token("typeof")]
+    Typeof,
+    #[token("enum")]
+    Enum,
+    #[token("assert")]
+    Assert,
+    #[token("assert_eq")]
+    AssertEq,
+    #[token("struct")]
+    Struct,
+
+    #[token("+")]
+    Plus,
+    #[token("++")]
+    PlusPlus,
+    #[token("-")]
+    Minus,
+    #[token("--")]
+    MinusMinus,
+    #[token("*")]
+    Star,
+    #[token("**")]
+    StarStar,
+    #[token("/")]
+    Slash,
+    #[token("%")]
+    Percent,
+    #[token("==")]
+    EqEq,
+    #[token("!=")]
+    NotEq,
+    #[token("<")]
+    Lt,
+    #[token("<=")]
+    LtEq,
+    #[token(">")]
+    Gt,
+    #[token(">=")]
+    GtEq,
+    #[token("&&")]
+    AndAnd,
+    #[token("||")]
+    OrOr,
+    #[token("&")]
+    Amp,
+    #[token("|")]
+    Pipe,
+    #[token("^")]
+    Caret,
+    #[token("<<")]
+    Shl,
+    #[token(">>")]
+    Shr,
+    #[token("~")]
+    Tilde,
+    #[token("!")]
+    Bang,
+    #[token("!!")]
+    BangBang,
+    #[token("?")]
+    Question,
+    #[token("?.")]
+    QuestionDot,
+    #[token("??")]
+    QuestionQuestion,
+    #[token("=")]
+    Eq,
+    #[token("+=")]
+    PlusEq,
+    #[token("-=")]
+    MinusEq,
+    #[token("*=")]
+    StarEq,
+    #[token("/=")]
+    SlashEq,
+    #[token("%=")]
+    PercentEq,
+    #[token("**=")]
+    StarStarEq,
+    #[token("=>")]
+    FatArrow,
+    #[token("..")]
+    DotDot,
+    #[token("..=")]
+    DotDotEq,
+    #[token("...")]
+    DotDotDot,
+
+    #[token("(")]
+    LParen,
+    #[token(")")]
+    RParen,
+    #[token("[")]
+    LBracket,
+    #[token("]")]
+    RBracket,
+    #[token("{")]
+    LBrace,
+    #[token("}")]
+    RBrace,
+    /// Opens a set (`#{1, 2}`) or map (`#{ k => v }`) literal.
+    #[token("#{")]
+    HashBrace,
+    #[token(",")]
+    Comma,
+    #[token(".")]
+    Dot,
+    #[token(":")]
+    Colon,
+    #[token(";")]
+    Semi,
+    #[token("$")]
+    Dollar,
+    #[token("@")]
+    At,
+
+    /// Unrecognized input. The derive itself never produces this (logos
+    /// reports errors out-of-band); [`lex_with_spans`] substitutes it so
+    /// stream consumers see the offending bytes instead of a gap.
+    Error,
+
+    /// A regex literal `/[a-z]+/i`. Never produced by the derive — the
+    /// lexer alone can't tell a regex delimiter from division — but
+    /// assembled by the parser's token prefilter when a `/` sits where
+    /// an expression starts; see [`scan_regex`].
+    Regex(RegexLiteral),
+}
+
+/// The payload of [`Token::Regex`]: the pattern between the delimiters
+/// and the trailing flag characters.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegexLiteral {
+    pub pattern: String,
+    pub flags: String,
+}
+
+/// Decodes a C99 hexadecimal float literal (`0x1.921fb6p+1`): hex
+/// mantissa digits, power-of-two exponent. Empty exponent digits reject
+/// the literal.
+fn hex_float(lex: &mut logos::Lexer<Token>) -> Option<f64> {
+    let body = &lex.slice()[2..];
+    let (mantissa, exponent) = body.split_once(['p', 'P'])?;
+    let exp: i32 = match exponent.strip_prefix('+') {
+        Some(digits) => digits,
+        None => exponent,
+    }
+    .parse()
+    .ok()?;
+    let (int_part, frac_part) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let mut value = 0.0f64;
+    for digit in int_part.chars() {
+        value = value * 16.0 + digit.to_digit(16)? as f64;
+    }
+    let mut scale = 1.0 / 16.0;
+    for digit in frac_part.chars() {
+        value += digit.to_digit(16)? as f64 * scale;
+        scale /= 16.0;
+    }
+    Some(value * 2f64.powi(exp))
+}
+
+/// Completes a raw string literal: the regex has matched the opening
+/// `r#*"`, and this scans forward to the matching `"#*` with the same
+/// number of hashes, taking everything between verbatim (no escapes).
+/// An unterminated raw string rejects the token.
+fn raw_string(lex: &mut logos::Lexer<Token>) -> Option<String> {
+    let hashes = lex.slice().len() - 2;
+    let terminator = format!("\"{}", "#".repeat(hashes));
+    let end = lex.remainder().find(&terminator)?;
+    lex.bump(end + terminator.len());
+    Some(lex.slice().to_owned())
+}
+
+/// Parses an integer literal's digits in the given radix, allowing `_`
+/// digit separators between digits (`1_000`, `0xFF_AA_FF`). A separator
+/// that doesn't sit between two digits — leading, trailing, or doubled —
+/// rejects the whole literal, which logos surfaces as an error token.
+/// A literal that overflows `i64` is rejected the same way, and the
+/// parser turns it into an explicit "too large" report (see
+/// [`overflowed_int`]) — wraparound would silently mean a different
+/// number, which is worse than either erroring or rejecting.
+fn int_literal(digits: &str, radix: u32) -> Option<i64> {
+    if digits.starts_with('_') || digits.ends_with('_') || digits.contains("__") {
+        return None;
+    }
+    // The overwhelmingly common literal has no separators: parse the
+    // slice in place and only allocate a stripped copy when underscores
+    // actually appear.
+    if !digits.contains('_') {
+        return i64::from_str_radix(digits, radix).ok();
+    }
+    i64::from_str_radix(&digits.replace('_', ""), radix).ok()
+}
+
+/// The numeric value of an integer literal the lexer rejected, when the
+/// rejection was pure overflow: well-formed digits that simply exceed
+/// `i64::MAX`. `None` for any other malformation (bad separators, not a
+/// number at all), which should keep reading as a generic syntax error.
+pub(crate) fn overflowed_int(slice: &str) -> Option<u128> {
+    let (digits, radix) = match slice.as_bytes().first_chunk::<2>() {
+        Some(b"0x" | b"0X") => (&slice[2..], 16),
+        Some(b"0b" | b"0B") => (&slice[2..], 2),
+        Some(b"0o" | b"0O") => (&slice[2..], 8),
+        _ => (slice, 10),
+    };
+    if digits.is_empty()
+        || digits.starts_with('_')
+        || digits.ends_with('_')
+        || digits.contains("__")
+    {
+        return None;
+    }
+    let clean = digits.replace('_', "");
+    u128::from_str_radix(&clean, radix)
+        .ok()
+        .filter(|&value| value > i64::MAX as u128)
+}
+
+/// Scans the text of a regex literal whose opening `/` ends at `from`:
+/// the pattern runs to the next unescaped `/` on the same line, and any
+/// ASCII-lowercase flag characters follow. Returns the pattern, the
+/// flags, and the offset one past the literal — or `None` when no
+/// closing delimiter exists and the `/` was an ordinary (if misplaced)
+/// division after all.
+pub(crate) fn scan_regex(source: &str, from: usize) -> Option<(String, String, usize)> {
+    let bytes = source.as_bytes();
+    let mut i = from;
+    let mut escaped = false;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\n' => return None,
+            b'\\' if !escaped => escaped = true,
+            b'/' if !escaped => {
+                let pattern = source[from..i].to_owned();
+                let mut end = i + 1;
+                while end < bytes.len() && bytes[end].is_ascii_lowercase() {
+                    end += 1;
+                }
+                let flags = source[i + 1..end].to_owned();
+                return Some((pattern, flags, end));
+            }
+            _ => escaped = false,
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Runs the lexer over `src` and pairs every token with its byte span —
+/// the raw stream syntax highlighters and other token-level tools want,
+/// as opposed to the parsed tree. Whitespace and comments are trivia the
+/// lexer skips, so they never appear in the output; their bytes surface
+/// only as gaps between consecutive spans. Input the lexer doesn't
+/// recognize yields a [`Token::Error`] covering the offending bytes
+/// rather than being silently dropped.
+pub fn lex_with_spans(src: &str) -> Vec<(Token, logos::Span)> {
+    Token::lexer(src)
+        .spanned()
+        .map(|(token, span)| (token.unwrap_or(Token::Error), span))
+        .collect()
+}
+
+impl Token {
+    /// Whether this token can begin an expression. The parser uses it to
+    /// tell a ternary's `?` (followed by its then-branch) apart from the
+    /// postfix `?` of error propagation.
+    pub fn starts_expression(&self) -> bool {
+        matches!(
+            self,
+            Token::Ident(_)
+                | Token::Int(_)
+                | Token::Float(_)
+                | Token::Str(_)
+                | Token::True
+                | Token::False
+                | Token::Nil
+                | Token::LParen
+                | Token::LBracket
+                | Token::LBrace
+                | Token::HashBrace
+                | Token::Type
+                | Token::Match
+                | Token::Typeof
+                | Token::Minus
+                | Token::Bang
+                | Token::Tilde
+        )
+    }
+
+    /// Whether this token can end a value, which makes a `-` right after
+    /// it a binary subtraction rather than a prefix negation.
+    pub(crate) fn ends_value(&self) -> bool {
+        matches!(
+            self,
+            Token::Ident(_)
+                | Token::Int(_)
+                | Token::Float(_)
+                | Token::Str(_)
+                | Token::True
+                | Token::False
+                | Token::Nil
+                | Token::RParen
+                | Token::RBracket
+                | Token::RBrace
+        )
+    }
+
+    /// Whether this token marks a place a recovering parser can safely resume:
+    /// the start of a new statement, or a boundary that ends the current one.
+    pub fn is_recovery_boundary(&self) -> bool {
+        matches!(
+            self,
+            Token::Semi
+                | Token::RBrace
+                | Token::Let
+                | Token::Const
+                | Token::Fn
+                | Token::If
+                | Token::For
+                | Token::While
+                | Token::Return
+                | Token::Module
+                | Token::Import
+                | Token::Export
+                | Token::Enum
+                | Token::Struct
+        )
+    }
+}
+
+impl std::fmt::Display for Token {
+    /// The user-facing spelling of a token, for messages like
+    /// ``expected `)`, found `let` `` — symbols and keywords print as
+    /// written, value-carrying tokens by their class.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = match self {
+            Token::Ident(name) => return write!(f, "identifier `{name}`"),
+            Token::Int(_) => return f.write_str("an integer literal"),
+            Token::Float(_) => return f.write_str("a float literal"),
+            Token::Str(_) => return f.write_str("a string literal"),
+            Token::Regex(_) => return f.write_str("a regex literal"),
+            Token::Error => return f.write_str("unrecognized input"),
+            Token::True => "true",
+            Token::False => "false",
+            Token::Nil => "nil",
+            Token::Let => "let",
+            Token::Const => "const",
+            Token::Native => "native",
+            Token::Fn => "fn",
+            Token::If => "if",
+            Token::Else => "else",
+            Token::For => "for",
+            Token::While => "while",
+            Token::Do => "do",
+            Token::In => "in",
+            Token::Return => "return",
+            Token::Break => "break",
+            Token::Continue => "continue",
+            Token::Throw => "throw",
+            Token::Try => "try",
+            Token::Catch => "catch",
+            Token::Module => "module",
+            Token::Import => "import",
+            Token::Include => "include",
+            Token::Export => "export",
+            Token::Type => "type",
+            Token::Match => "match",
+            Token::Typeof => "typeof",
+            Token::Enum => "enum",
+            Token::Assert => "assert",
+            Token::AssertEq => "assert_eq",
+            Token::Struct => "struct",
+            Token::Plus => "+",
+            Token::PlusPlus => "++",
+            Token::Minus => "-",
+            Token::MinusMinus => "--",
+            Token::Star => "*",
+            Token::StarStar => "**",
+            Token::Slash => "/",
+            Token::Percent => "%",
+            Token::EqEq => "==",
+            Token::NotEq => "!=",
+            Token::Lt => "<",
+            Token::LtEq => "<=",
+            Token::Gt => ">",
+            Token::GtEq => ">=",
+            Token::AndAnd => "&&",
+            Token::OrOr => "||",
+            Token::Amp => "&",
+            Token::Pipe => "|",
+            Token::Caret => "^",
+            Token::Shl => "<<",
+            Token::Shr => ">>",
+            Token::Tilde => "~",
+            Token::Bang => "!",
+            Token::BangBang => "!!",
+            Token::Question => "?",
+            Token::QuestionDot => "?.",
+            Token::QuestionQuestion => "??",
+            Token::Eq => "=",
+            Token::PlusEq => "+=",
+            Token::MinusEq => "-=",
+            Token::StarEq => "*=",
+            Token::SlashEq => "/=",
+            Token::PercentEq => "%=",
+            Token::StarStarEq => "**=",
+            Token::FatArrow => "=>",
+            Token::DotDot => "..",
+            Token::DotDotEq => "..=",
+            Token::DotDotDot => "...",
+            Token::LParen => "(",
+            Token::RParen => ")",
+            Token::LBracket => "[",
+            Token::RBracket => "]",
+            Token::LBrace => "{",
+            Token::RBrace => "}",
+            Token::HashBrace => "#{",
+            Token::Comma => ",",
+            Token::Dot => ".",
+            Token::Colon => ":",
+            Token::Semi => ";",
+            Token::Dollar => "$",
+            Token::At => "@",
+        };
+        write!(f, "`{text}`")
+    }
+}
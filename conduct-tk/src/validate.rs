@@ -0,0 +1,2255 @@
+//! Static checks that run after parsing: undefined symbols, reassigning a
+//! `const`, unreachable code, and gradual type-checking (see [`Ty`]).
+
+use ahash::AHashMap;
+
+use crate::{
+    ast::{BinOp, CompoundKey, Expr, ExprKind, Literal, MatchArm, Pattern, Statement, StatementKind},
+    err::{codes::Code, CodeArea, ErrorReport, Errors, Severity},
+    parser::Parser,
+};
+
+/// How a name was introduced into scope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BindKind {
+    Let,
+    Const,
+    /// A function (or arrow-function) parameter.
+    Param,
+}
+
+/// One binding in a [`Scopes`] frame: how it was declared, where, and
+/// whatever extra data the owner attaches (the validator keeps the
+/// binding's inferred type here).
+#[derive(Debug, Clone)]
+pub struct Definition<T> {
+    pub kind: BindKind,
+    pub area: CodeArea,
+    pub data: T,
+    /// Whether anything has read this binding; flipped by passes that
+    /// track liveness (see the validator's unused-variable sweep).
+    pub used: bool,
+}
+
+/// A compact handle for an identifier interned by an [`Interner`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Symbol(u32);
+
+/// Interns identifier strings to [`Symbol`]s, `ahash`-backed, so hot
+/// paths hash a `u32` instead of re-hashing the same string over and
+/// over. [`Scopes`] interns on entry rather than the parser storing
+/// symbols in the tree: the AST (and with it the binary format) keeps
+/// plain `String` names, and only the passes where identifier lookups
+/// are hot pay for the side table.
+#[derive(Debug, Default)]
+pub struct Interner {
+    lookup: AHashMap<String, Symbol>,
+    names: Vec<String>,
+}
+
+impl Interner {
+    /// The symbol for `name`, assigning the next one on first sight.
+    pub fn intern(&mut self, name: &str) -> Symbol {
+        if let Some(&symbol) = self.lookup.get(name) {
+            return symbol;
+        }
+        let symbol = Symbol(self.names.len() as u32);
+        self.names.push(name.to_owned());
+        self.lookup.insert(name.to_owned(), symbol);
+        symbol
+    }
+
+    /// The symbol already assigned to `name`, if any — the read-only
+    /// lookup resolution paths use, hashing the string exactly once.
+    pub fn get(&self, name: &str) -> Option<Symbol> {
+        self.lookup.get(name).copied()
+    }
+
+    /// The text behind a symbol, for error messages.
+    pub fn resolve(&self, symbol: Symbol) -> &str {
+        &self.names[symbol.0 as usize]
+    }
+}
+
+/// Nested lexical scopes mapping names to [`Definition`]s. Generic over
+/// the per-binding payload so tools outside the validator can reuse the
+/// resolution rules (innermost frame wins) with their own data. Names
+/// are interned internally: resolving hashes the string once, then walks
+/// the frames comparing [`Symbol`]s.
+#[derive(Debug, Default)]
+pub struct Scopes<T> {
+    interner: Interner,
+    frames: Vec<AHashMap<Symbol, Definition<T>>>,
+}
+
+impl<T> Scopes<T> {
+    /// A scope stack with its root frame already in place.
+    pub fn new() -> Self {
+        Self {
+            interner: Interner::default(),
+            frames: vec![AHashMap::default()],
+        }
+    }
+
+    pub fn push(&mut self) {
+        self.frames.push(AHashMap::default());
+    }
+
+    pub fn pop(&mut self) {
+        self.frames.pop();
+    }
+
+    /// Defines `name` in the innermost frame. If an *outer* frame already
+    /// has a binding by that name, returns its area — the new binding
+    /// shadows it. Redefining within the same frame just replaces.
+    pub fn define(
+        &mut self,
+        name: &str,
+        kind: BindKind,
+        area: CodeArea,
+        data: T,
+    ) -> Option<CodeArea> {
+        let symbol = self.interner.intern(name);
+        let shadowed = self
+            .frames
+            .iter()
+            .rev()
+            .skip(1)
+            .find_map(|frame| frame.get(&symbol))
+            .map(|def| def.area.clone());
+        self.frames
+            .last_mut()
+            .expect("a scope stack always has a root frame")
+            .insert(
+                symbol,
+                Definition {
+                    kind,
+                    area,
+                    data,
+                    used: false,
+                },
+            );
+        shadowed
+    }
+
+    /// Resolves `name` against the innermost frame that defines it.
+    pub fn resolve(&self, name: &str) -> Option<&Definition<T>> {
+        let symbol = self.interner.get(name)?;
+        self.frames
+            .iter()
+            .rev()
+            .find_map(|frame| frame.get(&symbol))
+    }
+
+    /// Mutable twin of [`Scopes::resolve`], for passes that flip
+    /// per-binding state such as [`Definition::used`].
+    pub fn resolve_mut(&mut self, name: &str) -> Option<&mut Definition<T>> {
+        let symbol = self.interner.get(name)?;
+        self.frames
+            .iter_mut()
+            .rev()
+            .find_map(|frame| frame.get_mut(&symbol))
+    }
+
+    /// The bindings of the innermost frame, for end-of-scope sweeps.
+    pub fn innermost(&self) -> impl Iterator<Item = (&str, &Definition<T>)> {
+        self.frames
+            .last()
+            .into_iter()
+            .flat_map(|frame| {
+                frame
+                    .iter()
+                    .map(|(symbol, def)| (self.interner.resolve(*symbol), def))
+            })
+    }
+}
+
+/// A gradual type: concrete types behave like a normal static type system,
+/// but [`Ty::Any`] unifies with everything and never causes a conflict, so
+/// partially (or un-)annotated programs still pass.
+#[derive(Debug, Clone, PartialEq)]
+enum Ty {
+    Any,
+    Num,
+    Str,
+    Bool,
+    Nil,
+    Array(Box<Ty>),
+    Compound(Vec<(String, Ty)>),
+    Fn(Vec<Ty>, Box<Ty>),
+}
+
+impl Ty {
+    /// Reads a type off a parsed annotation expression (`num`, `type { .. }`,
+    /// `T[]`, ...). Anything it doesn't recognize degrades to `Any` rather
+    /// than erroring — an unknown annotation just means "don't check this".
+    fn from_annotation(expr: &Expr) -> Ty {
+        match &expr.kind {
+            ExprKind::Ident(name) => match name.as_str() {
+                "num" => Ty::Num,
+                "str" => Ty::Str,
+                "bool" => Ty::Bool,
+                "nil" => Ty::Nil,
+                _ => Ty::Any,
+            },
+            ExprKind::Array(elements) => match elements.first() {
+                Some(element) => Ty::Array(Box::new(Ty::from_annotation(element))),
+                None => Ty::Array(Box::new(Ty::Any)),
+            },
+            ExprKind::TypeDef(fields) => Ty::Compound(
+                fields
+                    .iter()
+                    .map(|(name, ty)| {
+                        (
+                            name.clone(),
+                            match ty.as_str() {
+                                "num" => Ty::Num,
+                                "str" => Ty::Str,
+                                "bool" => Ty::Bool,
+                                "nil" => Ty::Nil,
+                                _ => Ty::Any,
+                            },
+                        )
+                    })
+                    .collect(),
+            ),
+            _ => Ty::Any,
+        }
+    }
+
+    fn of_literal(lit: &Literal) -> Ty {
+        match lit {
+            Literal::Int(..) | Literal::Float(_) => Ty::Num,
+            Literal::Str(_) => Ty::Str,
+            Literal::Bool(_) => Ty::Bool,
+            Literal::Nil => Ty::Nil,
+            // No dedicated type yet; regexes stay unchecked.
+            Literal::Regex { .. } => Ty::Any,
+        }
+    }
+
+    /// Two types "meet" successfully if either is `Any`, or they're
+    /// structurally compatible: arrays by element, compounds field-wise
+    /// (order doesn't matter), functions arity- and piece-wise. Anything
+    /// else is a conflict the checker should report.
+    fn compatible(&self, other: &Ty) -> bool {
+        match (self, other) {
+            (Ty::Any, _) | (_, Ty::Any) => true,
+            (Ty::Array(a), Ty::Array(b)) => a.compatible(b),
+            (Ty::Compound(a), Ty::Compound(b)) => {
+                a.len() == b.len()
+                    && a.iter().all(|(name, ty)| {
+                        b.iter()
+                            .any(|(other_name, other_ty)| other_name == name && ty.compatible(other_ty))
+                    })
+            }
+            (Ty::Fn(a_params, a_ret), Ty::Fn(b_params, b_ret)) => {
+                a_params.len() == b_params.len()
+                    && a_params.iter().zip(b_params).all(|(a, b)| a.compatible(b))
+                    && a_ret.compatible(b_ret)
+            }
+            _ => self == other,
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Ty::Any => "any".to_owned(),
+            Ty::Num => "num".to_owned(),
+            Ty::Str => "str".to_owned(),
+            Ty::Bool => "bool".to_owned(),
+            Ty::Nil => "nil".to_owned(),
+            Ty::Array(elem) => format!("{}[]", elem.describe()),
+            Ty::Compound(_) => "type { .. }".to_owned(),
+            Ty::Fn(..) => "fn".to_owned(),
+        }
+    }
+}
+
+/// One `import` seen during validation, tracked so the validator can warn
+/// about imports that are never referenced.
+#[derive(Debug, Clone)]
+struct ImportedName {
+    /// The name the import binds locally: its alias if one was written,
+    /// otherwise the last module-path segment (or the file stem for a
+    /// string path like `'../lib/frog.cdl'`).
+    name: String,
+    path: String,
+    area: CodeArea,
+    used: bool,
+}
+
+/// The local name an `import` statement introduces; see [`ImportedName`].
+fn import_local_name(path: &str, alias: Option<&String>) -> String {
+    if let Some(alias) = alias {
+        return alias.clone();
+    }
+    if let Some(stripped) = path.strip_prefix(['\'', '"']) {
+        // A quoted file path: `'../lib/frog.cdl'` binds `frog`.
+        let trimmed = stripped.trim_end_matches(['\'', '"']);
+        let file = trimmed.rsplit('/').next().unwrap_or(trimmed);
+        file.split('.').next().unwrap_or(file).to_owned()
+    } else {
+        // A dotted module path: `std.io` binds `io`.
+        path.rsplit('.').next().unwrap_or(path).to_owned()
+    }
+}
+
+/// Walks a parsed statement tree looking for symbol-table and type
+/// mistakes. Constructed from the [`Parser`] that produced the tree so it
+/// can stamp diagnostics with the same module name.
+pub struct Validator {
+    current_module: String,
+    /// The source identity of the module being validated, so `include`
+    /// paths resolve relative to it.
+    src: crate::err::CodeSource,
+    /// What `include` statements resolve through; [`FsResolver`] unless
+    /// replaced via [`Validator::include_resolver`].
+    include_resolver: Box<dyn ModuleResolver>,
+    /// `(line, rule)` pairs gathered from `// conduct:ignore <rule>`
+    /// comments; a matching warning on that line or the next is dropped.
+    suppressions: Vec<(usize, String)>,
+    /// The source text, for mapping report offsets back to lines.
+    source: String,
+    scopes: Scopes<Ty>,
+    imports: Vec<ImportedName>,
+    /// Whether `import core` counts as used without an explicit reference
+    /// (the runtime pulls `core` in implicitly); see
+    /// [`Validator::implicit_core`].
+    implicit_core: bool,
+    /// How many `for`/`while` bodies the walk is currently inside. Function
+    /// boundaries reset it to zero: a `break` in a closure defined inside a
+    /// loop still has no loop of its own to break.
+    loop_depth: usize,
+    /// Labels of the enclosing labeled loops, innermost last; reset at
+    /// function boundaries together with `loop_depth`.
+    loop_labels: Vec<String>,
+    /// One slot per enclosing loop: the type (and site) of the first
+    /// valued `break` seen in it, so later ones can be checked for
+    /// consistency. A labeled valued break still meets the innermost
+    /// frame — close enough until labels carry values in anger.
+    loop_break_tys: Vec<Option<(Ty, CodeArea)>>,
+    /// The break-type frame the most recent [`Validator::exit_loop`]
+    /// popped; how an expression-position loop learns its result type.
+    last_loop_break: Option<Ty>,
+    /// How many function (or arrow-function) bodies the walk is inside,
+    /// so `?` propagation at the top level — with no caller to rethrow
+    /// to — can warn.
+    fn_depth: usize,
+    /// Whether function parameters join the unused-variable sweep; off
+    /// by default, see [`Validator::check_params`].
+    check_params: bool,
+    /// Annotation names the validator accepts without complaint; see
+    /// [`Validator::known_annotations`].
+    known_annotations: Vec<String>,
+    /// Whether reassigning a function parameter warns; off by default,
+    /// see [`Validator::check_param_reassign`].
+    check_param_reassign: bool,
+    /// Whether provably-constant conditions warn; off by default, see
+    /// [`Validator::check_const_conditions`].
+    check_const_conditions: bool,
+    /// Whether unused declaration type parameters warn; off by default,
+    /// see [`Validator::check_type_params`].
+    check_type_params: bool,
+    /// Whether a `let` redeclaring a name already bound in the same
+    /// scope errors; off by default, see
+    /// [`Validator::check_let_redeclare`].
+    check_let_redeclare: bool,
+    /// Whether warnings are promoted to errors; off by default, see
+    /// [`Validator::deny_warnings`].
+    deny_warnings: bool,
+    /// `let`/`const` names declared later in each enclosing block (one
+    /// frame per block), so a reference that runs before its declaration
+    /// can say so instead of claiming the name doesn't exist.
+    pending_lets: Vec<Vec<(String, CodeArea)>>,
+}
+
+impl Validator {
+    pub fn from(parser: &Parser) -> Self {
+        let mut suppressions = Vec::new();
+        for (line, text) in parser.source.lines().enumerate() {
+            if let Some(idx) = text.find("conduct:ignore") {
+                for rule in text[idx + "conduct:ignore".len()..]
+                    .split([',', ' '])
+                    .filter(|rule| !rule.is_empty())
+                {
+                    suppressions.push((line, rule.to_owned()));
+                }
+            }
+        }
+        Self {
+            current_module: parser.current_module.clone(),
+            src: parser.src.clone(),
+            include_resolver: Box::new(FsResolver),
+            suppressions,
+            source: parser.source.clone(),
+            scopes: Scopes::new(),
+            imports: Vec::new(),
+            implicit_core: false,
+            loop_depth: 0,
+            loop_labels: Vec::new(),
+            loop_break_tys: Vec::new(),
+            last_loop_break: None,
+            fn_depth: 0,
+            check_params: false,
+            known_annotations: vec!["deprecated".to_owned(), "inline".to_owned()],
+            check_param_reassign: false,
+            check_const_conditions: false,
+            check_type_params: false,
+            check_let_redeclare: false,
+            deny_warnings: false,
+            pending_lets: Vec::new(),
+        }
+    }
+
+    /// Replaces the resolver `include` statements go through; the
+    /// default [`FsResolver`] reads quoted paths relative to the
+    /// including file. Tests and embedders can pass a closure over an
+    /// in-memory map.
+    pub fn include_resolver(mut self, resolver: impl ModuleResolver + 'static) -> Self {
+        self.include_resolver = Box::new(resolver);
+        self
+    }
+
+    /// Configures whether `import core` is exempt from the unused-import
+    /// warning, for runtimes that reference `core` implicitly.
+    pub fn implicit_core(mut self, implicit: bool) -> Self {
+        self.implicit_core = implicit;
+        self
+    }
+
+    /// Replaces the set of annotation names accepted without a warning
+    /// (the default knows `deprecated` and `inline`).
+    pub fn known_annotations(mut self, names: &[&str]) -> Self {
+        self.known_annotations = names.iter().map(|name| (*name).to_owned()).collect();
+        self
+    }
+
+    /// Opts into warning when a function parameter is reassigned inside
+    /// its function — often a bug, but common enough as a style that
+    /// it's off by default.
+    pub fn check_param_reassign(mut self, check: bool) -> Self {
+        self.check_param_reassign = check;
+        self
+    }
+
+    /// Opts into warning when an `if`/`while`/ternary condition folds to
+    /// a constant (`if false`, `while 1 < 2`): the decision is made
+    /// before the program runs, which is usually a bug. Off by default —
+    /// `if false { .. }` is also a deliberate guard idiom.
+    pub fn check_const_conditions(mut self, check: bool) -> Self {
+        self.check_const_conditions = check;
+        self
+    }
+
+    /// Opts into warning when a declared type parameter is never
+    /// mentioned in its declaration's signature; off by default while
+    /// the gradual type system grows into generics.
+    pub fn check_type_params(mut self, check: bool) -> Self {
+        self.check_type_params = check;
+        self
+    }
+
+    /// Treats every warning as a hard error, `-D warnings` style, for
+    /// CI pipelines. A promoted report keeps its `W`-prefixed code, so
+    /// consumers can still tell a denied warning from a genuine error.
+    /// Suppression comments are honored first — an ignored warning
+    /// doesn't fail the build.
+    pub fn deny_warnings(mut self, deny: bool) -> Self {
+        self.deny_warnings = deny;
+        self
+    }
+
+    /// Opts into erroring when a `let` redeclares a name another `let`
+    /// already bound in the same scope; by default that's allowed as
+    /// shadowing. Redeclaring a `fn` or `const` is always an error.
+    pub fn check_let_redeclare(mut self, check: bool) -> Self {
+        self.check_let_redeclare = check;
+        self
+    }
+
+    /// Opts function parameters into the unused-variable sweep; they're
+    /// exempt by default (an unused parameter is often part of a fixed
+    /// signature rather than a mistake).
+    pub fn check_params(mut self, check: bool) -> Self {
+        self.check_params = check;
+        self
+    }
+
+    fn push_scope(&mut self) {
+        self.scopes.push();
+    }
+
+    fn pop_scope(&mut self, reports: &mut Errors) {
+        self.sweep_unused(reports);
+        self.scopes.pop();
+    }
+
+    /// Warns about bindings in the innermost frame that were never read.
+    /// `let`s always participate; parameters (and the param-like bindings
+    /// of loops, catches, and match arms) only under
+    /// [`Validator::check_params`]; `const`s and an `_`-prefixed name
+    /// never do.
+    fn sweep_unused(&mut self, reports: &mut Errors) {
+        let mut unused: Vec<(String, CodeArea)> = self
+            .scopes
+            .innermost()
+            .filter(|(name, def)| {
+                let swept = match def.kind {
+                    BindKind::Let => true,
+                    BindKind::Param => self.check_params,
+                    BindKind::Const => false,
+                };
+                swept && !def.used && !name.starts_with('_')
+            })
+            .map(|(name, def)| (name.to_owned(), def.area.clone()))
+            .collect();
+        // The frame is a hash map; order by position so output is stable.
+        unused.sort_by_key(|(_, area)| area.span.0);
+        for (name, area) in unused {
+            let mut report = self.error(&area, format!("unused variable `{name}`"));
+            report.code = Code::UnusedVariable.as_str();
+            report.severity = Severity::Warning;
+            report.labels = vec![(area, "never read".to_owned())];
+            reports.push(report);
+        }
+    }
+
+    /// Defines a binding and warns when it shadows one from an enclosing
+    /// scope, pointing at both definition sites.
+    fn declare(
+        &mut self,
+        name: &str,
+        kind: BindKind,
+        ty: Ty,
+        area: &CodeArea,
+        reports: &mut Errors,
+    ) {
+        if let Some(outer) = self.scopes.define(name, kind, area.clone(), ty) {
+            let mut report = self.error(
+                area,
+                format!("`{name}` shadows an outer binding"),
+            );
+            report.code = Code::Shadowing.as_str();
+            report.severity = Severity::Warning;
+            report.labels = vec![
+                (area.clone(), "this binding".to_owned()),
+                (outer, "shadows this one".to_owned()),
+            ];
+            reports.push(report);
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Option<&Definition<Ty>> {
+        self.scopes.resolve(name)
+    }
+
+    /// Drops `name` from the innermost pending-declaration frame once its
+    /// `let`/`const` has actually executed.
+    fn resolve_pending(&mut self, name: &str) {
+        if let Some(frame) = self.pending_lets.last_mut() {
+            frame.retain(|(pending, _)| pending != name);
+        }
+    }
+
+    /// Checks one assignment target: it must be a place (identifier,
+    /// member, index, ...), not a const, and — when the incoming value's
+    /// type is known — compatible with the target's declared type.
+    fn check_assign_target(
+        &mut self,
+        target: &Expr,
+        value_ty: Option<&Ty>,
+        value_area: &CodeArea,
+        reports: &mut Errors,
+    ) {
+        match &target.kind {
+            ExprKind::Ident(name) => {
+                if let Some(def) = self.lookup(name) {
+                    if def.kind == BindKind::Const {
+                        reports.push(
+                            self.error(&target.area, format!("cannot reassign const `{name}`"))
+                                .with_help(format!(
+                                    "declare `{name}` with `let` to make it mutable"
+                                )),
+                        );
+                    } else if def.kind == BindKind::Param && self.check_param_reassign {
+                        let declared = def.area.clone();
+                        let mut report = self.error(
+                            &target.area,
+                            format!("parameter `{name}` is reassigned"),
+                        );
+                        report.code = Code::ParameterReassigned.as_str();
+                        report.severity = Severity::Warning;
+                        report.labels = vec![
+                            (target.area.clone(), "reassigned here".to_owned()),
+                            (declared, "declared as a parameter here".to_owned()),
+                        ];
+                        reports.push(report);
+                    } else if let Some(value_ty) = value_ty {
+                        if !def.data.compatible(value_ty) {
+                            let declared = def.data.clone();
+                            self.type_error(value_area, &declared, value_ty, reports);
+                        }
+                    }
+                } else if !is_builtin(name) {
+                    reports.push(
+                        self.error(&target.area, format!("undefined symbol `{name}`")),
+                    );
+                }
+                // Writing isn't reading: a plain identifier target doesn't
+                // mark the binding used (or it could never be "unused").
+            }
+            ExprKind::Member { .. } | ExprKind::Index { .. } | ExprKind::OptionalAccess { .. } => {
+                self.infer_expr(target, reports);
+            }
+            _ => {
+                reports.push(self.error(
+                    &target.area,
+                    "this expression is not assignable",
+                ));
+            }
+        }
+    }
+
+    fn enter_loop(&mut self, label: Option<String>) {
+        self.loop_depth += 1;
+        self.loop_break_tys.push(None);
+        if let Some(label) = label {
+            self.loop_labels.push(label);
+        }
+    }
+
+    fn exit_loop(&mut self, labeled: bool) {
+        self.loop_depth -= 1;
+        self.last_loop_break = self.loop_break_tys.pop().flatten().map(|(ty, _)| ty);
+        if labeled {
+            self.loop_labels.pop();
+        }
+    }
+
+    fn error(&self, area: &CodeArea, message: impl Into<String>) -> ErrorReport {
+        ErrorReport {
+            code: Code::SemanticError.as_str(),
+            severity: Severity::Error,
+            call_stack: vec![],
+            notes: Vec::new(),
+            help: None,
+            current_module: self.current_module.clone(),
+            position: area.clone(),
+            message: message.into(),
+            labels: vec![(area.clone(), "here".to_owned())],
+        }
+    }
+
+    fn type_error(&self, area: &CodeArea, a: &Ty, b: &Ty, reports: &mut Errors) {
+        reports.push(self.error(
+            area,
+            format!("type mismatch: `{}` is incompatible with `{}`", a.describe(), b.describe()),
+        ));
+    }
+
+    /// Runs every check over `stmts`, returning every diagnostic found
+    /// (never short-circuits on the first one, matching the parser's
+    /// error-recovery philosophy).
+    pub fn validate(&mut self, stmts: &[Statement]) -> Vec<ErrorReport> {
+        let mut reports = Errors::new();
+        self.validate_block(stmts, &mut reports);
+        self.sweep_unused(&mut reports);
+        for import in &self.imports {
+            if import.used || (self.implicit_core && import.path == "core") {
+                continue;
+            }
+            let mut report = self.error(
+                &import.area,
+                format!("unused import `{}`", import.path),
+            );
+            report.code = Code::UnusedImport.as_str();
+            report.severity = Severity::Warning;
+            report.labels = vec![(import.area.clone(), "never referenced".to_owned())];
+            reports.push(report);
+        }
+        // Honor `// conduct:ignore <rule>` comments: a warning whose rule
+        // is ignored on its own or the preceding line is dropped. Hard
+        // errors always survive.
+        reports.retain(|report| {
+            if report.severity != Severity::Warning {
+                return true;
+            }
+            let Some(rule) = rule_name(report.code) else {
+                return true;
+            };
+            let line = self.source[..report.position.span.0.min(self.source.len())]
+                .matches('\n')
+                .count();
+            !self.suppressions.iter().any(|(at, ignored)| {
+                (*at == line || *at + 1 == line) && (ignored == rule || ignored == "all")
+            })
+        });
+        if self.deny_warnings {
+            for report in reports.iter_mut() {
+                if report.severity == Severity::Warning {
+                    report.severity = Severity::Error;
+                }
+            }
+        }
+        reports.into_reports()
+    }
+
+    /// Reports names declared twice in the same block. A duplicate `fn`,
+    /// `const`, `enum`, or `struct` is always an error — the second one
+    /// would silently replace the first. A `let` redeclaring another
+    /// `let` only errors under [`Validator::check_let_redeclare`].
+    fn check_duplicates(&mut self, stmts: &[Statement], reports: &mut Errors) {
+        let mut seen: Vec<(&str, &CodeArea, bool)> = Vec::new();
+        for stmt in stmts {
+            let (name, strict) = match &stmt.kind {
+                StatementKind::Fn { name, .. }
+                | StatementKind::Const { name, .. }
+                | StatementKind::Enum { name, .. }
+                | StatementKind::Struct { name, .. } => (name, true),
+                StatementKind::Let { name, .. } => (name, false),
+                _ => continue,
+            };
+            match seen.iter().find(|(earlier, ..)| earlier == name) {
+                Some((_, first, first_strict)) => {
+                    if *first_strict || strict || self.check_let_redeclare {
+                        let mut report = self.error(
+                            &stmt.area,
+                            format!("`{name}` is defined twice in the same scope"),
+                        );
+                        report.labels = vec![
+                            (stmt.area.clone(), "redefined here".to_owned()),
+                            ((*first).clone(), "first defined here".to_owned()),
+                        ];
+                        reports.push(report);
+                    }
+                }
+                None => seen.push((name, &stmt.area, strict)),
+            }
+        }
+    }
+
+    fn validate_block(&mut self, stmts: &[Statement], reports: &mut Errors) {
+        self.check_duplicates(stmts, reports);
+        // Function declarations hoist: a call may legally run before its
+        // `fn` appears (mutual recursion depends on it). The define here
+        // is warning-free; the statement's own `declare` refines it when
+        // the walk reaches it.
+        for stmt in stmts {
+            if let StatementKind::Fn { name, params, ret, .. } = &stmt.kind {
+                let ty = fn_signature(params, ret);
+                self.scopes.define(name, BindKind::Const, stmt.area.clone(), ty);
+            }
+        }
+        // `let`/`const` bindings do NOT hoist; remember where each one is
+        // declared so an early reference gets a temporal error.
+        self.pending_lets.push(
+            stmts
+                .iter()
+                .filter_map(|stmt| match &stmt.kind {
+                    StatementKind::Let { name, .. } | StatementKind::Const { name, .. } => {
+                        Some((name.clone(), stmt.area.clone()))
+                    }
+                    _ => None,
+                })
+                .collect(),
+        );
+        // Only statements that *unconditionally* exit the block count as
+        // terminators here; a `return` buried in one branch of an `if`
+        // doesn't make what follows unreachable.
+        let mut terminator: Option<CodeArea> = None;
+        for stmt in stmts {
+            if let Some(cause) = terminator.take() {
+                // One warning per block, on the first dead statement; the
+                // rest after it are implied.
+                let mut report =
+                    self.error(&stmt.area, "unreachable code after this block exits");
+                report.code = Code::UnreachableCode.as_str();
+                report.severity = Severity::Warning;
+                report.labels = vec![
+                    (stmt.area.clone(), "this statement can never run".to_owned()),
+                    (cause, "because this always exits the block first".to_owned()),
+                ];
+                reports.push(report);
+            }
+            self.validate_statement(stmt, reports);
+            if matches!(
+                stmt.kind,
+                StatementKind::Return(_)
+                    | StatementKind::Throw(_)
+                    | StatementKind::Break { .. }
+                    | StatementKind::Continue(_)
+            ) && terminator.is_none()
+            {
+                terminator = Some(stmt.area.clone());
+            }
+        }
+        self.pending_lets.pop();
+    }
+
+    fn validate_statement(&mut self, stmt: &Statement, reports: &mut Errors) {
+        for annotation in &stmt.annotations {
+            for arg in &annotation.args {
+                self.infer_expr(arg, reports);
+            }
+            if !self.known_annotations.iter().any(|known| known == &annotation.name) {
+                let mut report = self.error(
+                    &annotation.area,
+                    format!("unknown annotation `@{}`", annotation.name),
+                );
+                report.code = Code::UnknownAnnotation.as_str();
+                report.severity = Severity::Warning;
+                reports.push(report);
+            }
+        }
+        match &stmt.kind {
+            StatementKind::Let { name, ty, value } => {
+                let declared = ty.as_ref().map(Ty::from_annotation);
+                let inferred = value.as_ref().map(|v| self.infer_expr(v, reports));
+                let resolved = match (&declared, &inferred) {
+                    (Some(declared), Some(inferred)) => {
+                        if !declared.compatible(inferred) {
+                            self.type_error(&value.as_ref().unwrap().area, declared, inferred, reports);
+                        }
+                        declared.clone()
+                    }
+                    (Some(declared), None) => declared.clone(),
+                    (None, Some(inferred)) => inferred.clone(),
+                    (None, None) => Ty::Any,
+                };
+                self.declare(name, BindKind::Let, resolved, &stmt.area, reports);
+                self.resolve_pending(name);
+            }
+            StatementKind::Const { name, ty, value, .. } => {
+                let declared = ty.as_ref().map(Ty::from_annotation);
+                let inferred = value.as_ref().map(|v| self.infer_expr(v, reports));
+                let resolved = match (&declared, &inferred) {
+                    (Some(declared), Some(inferred)) => {
+                        if !declared.compatible(inferred) {
+                            self.type_error(&value.as_ref().unwrap().area, declared, inferred, reports);
+                        }
+                        declared.clone()
+                    }
+                    (Some(declared), None) => declared.clone(),
+                    (None, Some(inferred)) => inferred.clone(),
+                    (None, None) => Ty::Any,
+                };
+                self.declare(name, BindKind::Const, resolved, &stmt.area, reports);
+                self.resolve_pending(name);
+            }
+            StatementKind::Fn {
+                name,
+                type_params,
+                params,
+                ret,
+                body,
+                ..
+            } => {
+                if self.check_type_params {
+                    for type_param in type_params {
+                        let mentioned = params
+                            .iter()
+                            .filter_map(|param| param.ty.as_ref())
+                            .chain(ret.as_ref())
+                            .any(|ty| annotation_mentions(ty, type_param));
+                        if !mentioned {
+                            self.unused_type_param(type_param, &stmt.area, reports);
+                        }
+                    }
+                }
+                let Ty::Fn(param_tys, ret_ty) = fn_signature(params, ret) else {
+                    unreachable!("fn_signature always builds a Ty::Fn");
+                };
+                self.declare(
+                    name,
+                    BindKind::Const,
+                    Ty::Fn(param_tys.clone(), ret_ty),
+                    &stmt.area,
+                    reports,
+                );
+                self.push_scope();
+                for (param, ty) in params.iter().zip(param_tys) {
+                    self.declare(&param.name, BindKind::Param, ty, &stmt.area, reports);
+                    if let Some(default) = &param.default {
+                        self.infer_expr(default, reports);
+                    }
+                }
+                let outer_depth = std::mem::replace(&mut self.loop_depth, 0);
+                let outer_labels = std::mem::take(&mut self.loop_labels);
+                self.fn_depth += 1;
+                self.validate_block(body, reports);
+                self.fn_depth -= 1;
+                self.loop_depth = outer_depth;
+                self.loop_labels = outer_labels;
+                self.pop_scope(reports);
+            }
+            StatementKind::Assign { target, value, .. } => {
+                let value_ty = self.infer_expr(value, reports);
+                self.check_assign_target(target, Some(&value_ty), &value.area, reports);
+            }
+            StatementKind::AssignChain { targets, value } => {
+                let value_ty = self.infer_expr(value, reports);
+                for target in targets {
+                    self.check_assign_target(target, Some(&value_ty), &value.area, reports);
+                }
+            }
+            StatementKind::AssignParallel { targets, values } => {
+                for value in values {
+                    self.infer_expr(value, reports);
+                }
+                for target in targets {
+                    self.check_assign_target(target, None, &stmt.area, reports);
+                }
+            }
+            StatementKind::If { branches, otherwise } => {
+                // A literal `true` condition makes every later branch
+                // dead. Only constant conditions count — anything
+                // computed might be reachable — and an `if false { .. }`
+                // body deliberately stays exempt: guarding a block out
+                // is an established idiom. Like the unreachable-code
+                // sweep, only the first dead branch of a statement
+                // warns.
+                let mut always_true: Option<CodeArea> = None;
+                let mut warned = false;
+                for (cond, body) in branches {
+                    self.infer_expr(cond, reports);
+                    self.warn_const_condition(cond, reports);
+                    if let (Some(cause), false) = (&always_true, warned) {
+                        let mut report = self.error(
+                            &cond.area,
+                            "unreachable branch after an always-true condition",
+                        );
+                        report.code = Code::UnreachableCode.as_str();
+                        report.severity = Severity::Warning;
+                        report.labels = vec![
+                            (cond.area.clone(), "this branch can never run".to_owned()),
+                            (cause.clone(), "because this condition is always true".to_owned()),
+                        ];
+                        reports.push(report);
+                        warned = true;
+                    }
+                    if matches!(cond.kind, ExprKind::Literal(Literal::Bool(true)))
+                        && always_true.is_none()
+                    {
+                        always_true = Some(cond.area.clone());
+                    }
+                    self.push_scope();
+                    self.validate_block(body, reports);
+                    self.pop_scope(reports);
+                }
+                if let Some(otherwise) = otherwise {
+                    if let (Some(cause), false) = (&always_true, warned) {
+                        let area = otherwise
+                            .first()
+                            .map(|first| first.area.clone())
+                            .unwrap_or_else(|| stmt.area.clone());
+                        let mut report = self.error(
+                            &area,
+                            "unreachable `else` after an always-true condition",
+                        );
+                        report.code = Code::UnreachableCode.as_str();
+                        report.severity = Severity::Warning;
+                        report.labels = vec![
+                            (area.clone(), "this branch can never run".to_owned()),
+                            (cause.clone(), "because this condition is always true".to_owned()),
+                        ];
+                        reports.push(report);
+                    }
+                    self.push_scope();
+                    self.validate_block(otherwise, reports);
+                    self.pop_scope(reports);
+                }
+            }
+            StatementKind::For { binding, iterable, body, label } => {
+                self.infer_expr(iterable, reports);
+                self.push_scope();
+                self.declare(binding, BindKind::Param, Ty::Any, &stmt.area, reports);
+                self.enter_loop(label.clone());
+                self.validate_block(body, reports);
+                self.exit_loop(label.is_some());
+                self.pop_scope(reports);
+            }
+            StatementKind::While { cond, body, label } => {
+                self.infer_expr(cond, reports);
+                self.warn_const_condition(cond, reports);
+                self.push_scope();
+                self.enter_loop(label.clone());
+                self.validate_block(body, reports);
+                self.exit_loop(label.is_some());
+                self.pop_scope(reports);
+            }
+            StatementKind::IfLet {
+                binding,
+                value,
+                body,
+                otherwise,
+            } => {
+                self.infer_expr(value, reports);
+                self.push_scope();
+                self.declare(binding, BindKind::Param, Ty::Any, &stmt.area, reports);
+                self.validate_block(body, reports);
+                self.pop_scope(reports);
+                if let Some(otherwise) = otherwise {
+                    self.push_scope();
+                    self.validate_block(otherwise, reports);
+                    self.pop_scope(reports);
+                }
+            }
+            StatementKind::WhileLet {
+                binding,
+                value,
+                body,
+                label,
+            } => {
+                self.infer_expr(value, reports);
+                self.push_scope();
+                self.declare(binding, BindKind::Param, Ty::Any, &stmt.area, reports);
+                self.enter_loop(label.clone());
+                self.validate_block(body, reports);
+                self.exit_loop(label.is_some());
+                self.pop_scope(reports);
+            }
+            StatementKind::DoWhile { body, cond } => {
+                self.push_scope();
+                self.enter_loop(None);
+                self.validate_block(body, reports);
+                self.exit_loop(false);
+                self.pop_scope(reports);
+                self.infer_expr(cond, reports);
+                self.warn_const_condition(cond, reports);
+            }
+            StatementKind::Return(Some(expr)) | StatementKind::Throw(expr) => {
+                self.infer_expr(expr, reports);
+            }
+            StatementKind::Assert { lhs, rhs, message } => {
+                self.infer_expr(lhs, reports);
+                if let Some(rhs) = rhs {
+                    self.infer_expr(rhs, reports);
+                }
+                if let Some(message) = message {
+                    let ty = self.infer_expr(message, reports);
+                    if !ty.compatible(&Ty::Str) {
+                        self.type_error(&message.area, &Ty::Str, &ty, reports);
+                    }
+                }
+            }
+            StatementKind::Try { body, catches } => {
+                self.push_scope();
+                self.validate_block(body, reports);
+                self.pop_scope(reports);
+                // A `catch *` swallows everything, so any arm after it
+                // can never match; warn on the first such arm only.
+                let mut catchall = false;
+                let mut warned = false;
+                for arm in catches {
+                    if catchall && !warned {
+                        let area = arm
+                            .body
+                            .first()
+                            .map(|first| first.area.clone())
+                            .unwrap_or_else(|| stmt.area.clone());
+                        let mut report =
+                            self.error(&area, "unreachable `catch` after a catch-all arm");
+                        report.code = Code::UnreachableCode.as_str();
+                        report.severity = Severity::Warning;
+                        report.labels = vec![(
+                            area.clone(),
+                            "an earlier `catch *` already swallows everything".to_owned(),
+                        )];
+                        reports.push(report);
+                        warned = true;
+                    }
+                    if matches!(arm.pattern, crate::ast::CatchPattern::Any) {
+                        catchall = true;
+                    }
+                    self.push_scope();
+                    if let Some(binding) = &arm.binding {
+                        self.declare(binding, BindKind::Param, Ty::Any, &stmt.area, reports);
+                    }
+                    self.validate_block(&arm.body, reports);
+                    self.pop_scope(reports);
+                }
+            }
+            StatementKind::Expr(expr) => {
+                self.infer_expr(expr, reports);
+            }
+            StatementKind::Import { path, alias } => {
+                self.imports.push(ImportedName {
+                    name: import_local_name(path, alias.as_ref()),
+                    path: path.clone(),
+                    area: stmt.area.clone(),
+                    used: false,
+                });
+            }
+            StatementKind::LetTuple { names, value } => {
+                self.infer_expr(value, reports);
+                for name in names {
+                    self.declare(name, BindKind::Let, Ty::Any, &stmt.area, reports);
+                }
+            }
+            StatementKind::SelectiveImport { names, path } => {
+                for (name, alias) in names {
+                    self.imports.push(ImportedName {
+                        name: alias.clone().unwrap_or_else(|| name.clone()),
+                        // Qualify with the item so the warning reads
+                        // `unused import `std.io.read``, not just the module.
+                        path: format!("{path}.{name}"),
+                        area: stmt.area.clone(),
+                        used: false,
+                    });
+                }
+            }
+            StatementKind::Enum { name, variants } => {
+                self.declare(name, BindKind::Const, Ty::Any, &stmt.area, reports);
+                let mut seen: Vec<&str> = Vec::new();
+                for variant in variants {
+                    if seen.contains(&variant.name.as_str()) {
+                        reports.push(self.error(
+                            &stmt.area,
+                            format!("duplicate variant `{}` in enum `{name}`", variant.name),
+                        ));
+                    } else {
+                        seen.push(&variant.name);
+                    }
+                }
+            }
+            StatementKind::Struct {
+                name,
+                type_params,
+                fields,
+            } => {
+                if self.check_type_params {
+                    for type_param in type_params {
+                        if !fields.iter().any(|(_, ty)| ty == type_param) {
+                            self.unused_type_param(type_param, &stmt.area, reports);
+                        }
+                    }
+                }
+                let ty = Ty::Compound(
+                    fields
+                        .iter()
+                        .map(|(field, ty)| {
+                            (
+                                field.clone(),
+                                match ty.as_str() {
+                                    "num" => Ty::Num,
+                                    "str" => Ty::Str,
+                                    "bool" => Ty::Bool,
+                                    "nil" => Ty::Nil,
+                                    _ => Ty::Any,
+                                },
+                            )
+                        })
+                        .collect(),
+                );
+                self.declare(name, BindKind::Const, ty, &stmt.area, reports);
+            }
+            StatementKind::Include(path) => {
+                let src = self.src.clone();
+                self.process_include(&src, path, &stmt.area, &mut Vec::new(), reports);
+            }
+            StatementKind::Export(path) => {
+                // Re-exporting a module is a use of its import.
+                for import in &mut self.imports {
+                    if &import.path == path {
+                        import.used = true;
+                    }
+                }
+            }
+            StatementKind::SelectiveExport { names, from } => match from {
+                // Re-exporting items of a module is a use of its import;
+                // the names live in that module, not this scope.
+                Some(path) => {
+                    for import in &mut self.imports {
+                        if &import.path == path {
+                            import.used = true;
+                        }
+                    }
+                }
+                // Exported local names must actually exist, and being
+                // exported counts as being used.
+                None => {
+                    for (name, _) in names {
+                        if let Some(def) = self.scopes.resolve_mut(name) {
+                            def.used = true;
+                        } else if let Some(import) =
+                            self.imports.iter_mut().find(|import| &import.name == name)
+                        {
+                            import.used = true;
+                        } else if !is_builtin(name) {
+                            reports.push(self.error(
+                                &stmt.area,
+                                format!("cannot export undefined symbol `{name}`"),
+                            ));
+                        }
+                    }
+                }
+            },
+            StatementKind::Break { label, value } => {
+                if self.loop_depth == 0 {
+                    reports.push(self.error(&stmt.area, "`break` outside of a loop"));
+                } else {
+                    if let Some(label) = label {
+                        if !self.loop_labels.iter().any(|l| l == label) {
+                            reports.push(self.error(
+                                &stmt.area,
+                                format!("label `{label}` is not in scope for this `break`"),
+                            ));
+                        }
+                    }
+                    if let Some(value) = value {
+                        // Every valued break of one loop must agree on
+                        // its type, or the loop's result is a coin flip.
+                        let ty = self.infer_expr(value, reports);
+                        if let Some(slot) = self.loop_break_tys.last_mut() {
+                            match slot {
+                                Some((prev, _)) if !prev.compatible(&ty) => {
+                                    let prev = prev.clone();
+                                    self.type_error(&value.area, &prev, &ty, reports);
+                                }
+                                Some((prev, _)) => {
+                                    if *prev == Ty::Any {
+                                        *prev = ty;
+                                    }
+                                }
+                                None => *slot = Some((ty, value.area.clone())),
+                            }
+                        }
+                    }
+                }
+            }
+            StatementKind::Continue(label) => {
+                if self.loop_depth == 0 {
+                    reports.push(self.error(&stmt.area, "`continue` outside of a loop"));
+                } else if let Some(label) = label {
+                    if !self.loop_labels.iter().any(|l| l == label) {
+                        reports.push(self.error(
+                            &stmt.area,
+                            format!("label `{label}` is not in scope for this `continue`"),
+                        ));
+                    }
+                }
+            }
+            StatementKind::Return(None)
+            | StatementKind::Module(_)
+            | StatementKind::Error => {}
+        }
+    }
+
+    /// Splices an included header's declarations into the current scope:
+    /// resolve, parse, and declare each top-level `fn`/`const`/`let`/
+    /// `enum`/`struct` (nested `include`s recurse, each header splicing
+    /// at most once). A name the current scope already defines is a
+    /// conflict — textual inclusion has no namespace to hide behind.
+    fn process_include(
+        &mut self,
+        from: &crate::err::CodeSource,
+        path: &str,
+        area: &CodeArea,
+        seen: &mut Vec<String>,
+        reports: &mut Errors,
+    ) {
+        let Some((src, text)) = self.include_resolver.resolve(from, path) else {
+            reports.push(self.error(area, format!("cannot resolve include `{path}`")));
+            return;
+        };
+        let name = src.name();
+        if seen.contains(&name) {
+            return;
+        }
+        seen.push(name);
+        use logos::Logos as _;
+        let mut parser = Parser::new(src.clone(), crate::tk::Token::lexer(&text));
+        let (stmts, _) = parser.parse_all_recovering();
+        for inner in &stmts {
+            match &inner.kind {
+                StatementKind::Fn { name, params, ret, .. } => {
+                    self.declare_included(name, fn_signature(params, ret), area, reports);
+                }
+                StatementKind::Const { name, .. }
+                | StatementKind::Let { name, .. }
+                | StatementKind::Enum { name, .. }
+                | StatementKind::Struct { name, .. } => {
+                    self.declare_included(name, Ty::Any, area, reports);
+                }
+                StatementKind::Include(nested) => {
+                    self.process_include(&src, nested, area, seen, reports);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// One included declaration: a conflict with anything already in the
+    /// innermost scope errors; otherwise it joins as a used `const`-like
+    /// binding (external declarations never trip the unused sweep).
+    fn declare_included(
+        &mut self,
+        name: &str,
+        ty: Ty,
+        area: &CodeArea,
+        reports: &mut Errors,
+    ) {
+        if self.scopes.innermost().any(|(existing, _)| existing == name) {
+            reports.push(self.error(
+                area,
+                format!("include conflicts with an existing definition of `{name}`"),
+            ));
+            return;
+        }
+        self.scopes.define(name, BindKind::Const, area.clone(), ty);
+        if let Some(def) = self.scopes.resolve_mut(name) {
+            def.used = true;
+        }
+    }
+
+    /// Warns when a condition folds to a constant boolean; a no-op
+    /// unless [`Validator::check_const_conditions`] opted in.
+    fn warn_const_condition(&mut self, cond: &Expr, reports: &mut Errors) {
+        if !self.check_const_conditions {
+            return;
+        }
+        if let Some(value) = crate::optimize::const_bool(cond) {
+            let mut report = self.error(
+                &cond.area,
+                format!("this condition is always {value}"),
+            );
+            report.code = Code::ConstantCondition.as_str();
+            report.severity = Severity::Warning;
+            report.labels = vec![(cond.area.clone(), "constant before the program runs".to_owned())];
+            reports.push(report);
+        }
+    }
+
+    /// The W04-family warning for a type parameter nothing in the
+    /// declaration's signature mentions.
+    fn unused_type_param(
+        &mut self,
+        name: &str,
+        area: &CodeArea,
+        reports: &mut Errors,
+    ) {
+        let mut report = self.error(area, format!("unused type parameter `{name}`"));
+        report.code = Code::UnusedVariable.as_str();
+        report.severity = Severity::Warning;
+        report.labels = vec![(area.clone(), "never mentioned in the signature".to_owned())];
+        reports.push(report);
+    }
+
+    /// Infers the [`Ty`] of `expr` bottom-up, reporting any concrete type
+    /// conflicts it finds along the way. Never hard-errors on `Ty::Any` —
+    /// only two *known* concrete types meeting incompatibly is a conflict.
+    fn infer_expr(&mut self, expr: &Expr, reports: &mut Errors) -> Ty {
+        match &expr.kind {
+            ExprKind::Literal(lit) => Ty::of_literal(lit),
+            ExprKind::Ident(name) => match self.scopes.resolve_mut(name) {
+                Some(def) => {
+                    def.used = true;
+                    def.data.clone()
+                }
+                None => {
+                    if let Some(import) =
+                        self.imports.iter_mut().find(|import| &import.name == name)
+                    {
+                        import.used = true;
+                    } else if let Some((_, declared)) = self
+                        .pending_lets
+                        .iter()
+                        .rev()
+                        .find_map(|frame| frame.iter().find(|(pending, _)| pending == name))
+                    {
+                        let declared = declared.clone();
+                        let mut report = self.error(
+                            &expr.area,
+                            format!("`{name}` is used before its declaration"),
+                        );
+                        report.labels = vec![
+                            (expr.area.clone(), "used here".to_owned()),
+                            (declared, "but only declared down here".to_owned()),
+                        ];
+                        reports.push(report);
+                    } else if !is_builtin(name) {
+                        reports.push(self.error(&expr.area, format!("undefined symbol `{name}`")));
+                    }
+                    Ty::Any
+                }
+            },
+            ExprKind::Unary(_, inner) => self.infer_expr(inner, reports),
+            ExprKind::Loop(stmt) => {
+                self.validate_statement(stmt, reports);
+                // The loop's value is whatever its breaks agreed on.
+                self.last_loop_break.take().unwrap_or(Ty::Any)
+            }
+            ExprKind::Spread(inner) => {
+                self.infer_expr(inner, reports);
+                // The spread splices an unknown number of elements; the
+                // surrounding collection decides what that means.
+                Ty::Any
+            }
+            ExprKind::PostIncrement(target) | ExprKind::PostDecrement(target) => {
+                let op = if matches!(expr.kind, ExprKind::PostIncrement(_)) {
+                    "++"
+                } else {
+                    "--"
+                };
+                match &target.kind {
+                    ExprKind::Ident(name) => {
+                        let ty = self.infer_expr(target, reports);
+                        let name = name.clone();
+                        if self
+                            .lookup(&name)
+                            .is_some_and(|def| def.kind == BindKind::Const)
+                        {
+                            reports.push(self.error(
+                                &target.area,
+                                format!("cannot apply `{op}` to const `{name}`"),
+                            ));
+                        } else if !ty.compatible(&Ty::Num) {
+                            self.type_error(&target.area, &Ty::Num, &ty, reports);
+                        }
+                    }
+                    ExprKind::Member { .. }
+                    | ExprKind::Index { .. }
+                    | ExprKind::OptionalAccess { .. } => {
+                        self.infer_expr(target, reports);
+                    }
+                    _ => {
+                        reports.push(self.error(
+                            &target.area,
+                            format!("`{op}` needs an assignable target"),
+                        ));
+                    }
+                }
+                Ty::Num
+            }
+            ExprKind::Try(inner) => {
+                let ty = self.infer_expr(inner, reports);
+                if self.fn_depth == 0 {
+                    let mut report = self.error(
+                        &expr.area,
+                        "`?` error propagation outside of a function",
+                    );
+                    report.code = Code::TopLevelPropagation.as_str();
+                    report.severity = Severity::Warning;
+                    report.labels = vec![(
+                        expr.area.clone(),
+                        "no caller to rethrow to here".to_owned(),
+                    )];
+                    reports.push(report);
+                }
+                ty
+            }
+            ExprKind::TypeOf(inner) => {
+                self.infer_expr(inner, reports);
+                Ty::Str
+            }
+            ExprKind::Binary(op, lhs, rhs) => {
+                let lhs_ty = self.infer_expr(lhs, reports);
+                let rhs_ty = self.infer_expr(rhs, reports);
+                self.infer_binary(*op, &lhs_ty, &rhs_ty, &expr.area, reports)
+            }
+            ExprKind::Ternary { cond, then, otherwise } => {
+                self.infer_expr(cond, reports);
+                self.warn_const_condition(cond, reports);
+                let then_ty = self.infer_expr(then, reports);
+                let else_ty = self.infer_expr(otherwise, reports);
+                if then_ty.compatible(&else_ty) {
+                    if then_ty == Ty::Any {
+                        else_ty
+                    } else {
+                        then_ty
+                    }
+                } else {
+                    self.type_error(&expr.area, &then_ty, &else_ty, reports);
+                    Ty::Any
+                }
+            }
+            ExprKind::Call { callee, args } => {
+                let callee_ty = self.infer_expr(callee, reports);
+                for arg in args {
+                    self.infer_expr(arg, reports);
+                }
+                match callee_ty {
+                    Ty::Fn(_, ret) => *ret,
+                    _ => Ty::Any,
+                }
+            }
+            ExprKind::Index { target, index } => {
+                let target_ty = self.infer_expr(target, reports);
+                self.infer_expr(index, reports);
+                match &target_ty {
+                    Ty::Array(elem) => (**elem).clone(),
+                    Ty::Any | Ty::Compound(_) | Ty::Str => Ty::Any,
+                    _ => {
+                        reports.push(
+                            self.error(&expr.area, format!("cannot index into `{}`", target_ty.describe())),
+                        );
+                        Ty::Any
+                    }
+                }
+            }
+            ExprKind::Member { target, .. } | ExprKind::OptionalAccess { target, .. } => {
+                self.infer_expr(target, reports);
+                Ty::Any
+            }
+            ExprKind::NullCoalesce(lhs, rhs) => {
+                let lhs_ty = self.infer_expr(lhs, reports);
+                let rhs_ty = self.infer_expr(rhs, reports);
+                // The lhs only survives when it isn't nil, so a nil lhs
+                // type means the result is whatever the fallback is.
+                if lhs_ty == Ty::Nil || lhs_ty == Ty::Any {
+                    rhs_ty
+                } else if lhs_ty == rhs_ty {
+                    lhs_ty
+                } else {
+                    Ty::Any
+                }
+            }
+            ExprKind::InterpolatedString(parts) => {
+                for part in parts {
+                    if let crate::ast::StringPart::Expr(expr) = part {
+                        self.infer_expr(expr, reports);
+                    }
+                }
+                Ty::Str
+            }
+            ExprKind::Range { start, end, step, .. } => {
+                self.infer_expr(start, reports);
+                self.infer_expr(end, reports);
+                if let Some(step) = step {
+                    self.infer_expr(step, reports);
+                    if matches!(
+                        step.kind,
+                        ExprKind::Literal(Literal::Int(0, _))
+                    ) || matches!(step.kind, ExprKind::Literal(Literal::Float(f)) if f == 0.0)
+                    {
+                        reports.push(
+                            self.error(&step.area, "a range step of zero never advances"),
+                        );
+                    }
+                }
+                Ty::Any
+            }
+            ExprKind::Tuple(elements) | ExprKind::Set(elements) => {
+                for element in elements {
+                    self.infer_expr(element, reports);
+                }
+                Ty::Any
+            }
+            ExprKind::Map(entries) => {
+                for (key, value) in entries {
+                    self.infer_expr(key, reports);
+                    self.infer_expr(value, reports);
+                }
+                Ty::Any
+            }
+            ExprKind::Array(elements) => {
+                let mut elem_ty = Ty::Any;
+                for element in elements {
+                    let ty = self.infer_expr(element, reports);
+                    if elem_ty == Ty::Any {
+                        elem_ty = ty;
+                    }
+                }
+                Ty::Array(Box::new(elem_ty))
+            }
+            ExprKind::Compound(fields) => {
+                // A computed key gives the literal no static shape: infer
+                // the parts for their own diagnostics, but the whole
+                // reads as `any`.
+                let computed = fields
+                    .iter()
+                    .any(|(key, _)| matches!(key, CompoundKey::Computed(_)));
+                let mut shape = Vec::new();
+                for (key, value) in fields {
+                    if let CompoundKey::Computed(key) = key {
+                        self.infer_expr(key, reports);
+                    }
+                    let ty = self.infer_expr(value, reports);
+                    if let CompoundKey::Static(name) = key {
+                        shape.push((name.clone(), ty));
+                    }
+                }
+                if computed {
+                    Ty::Any
+                } else {
+                    Ty::Compound(shape)
+                }
+            }
+            ExprKind::ArrowFn { params, body, .. } => {
+                self.push_scope();
+                for param in params {
+                    self.declare(
+                        &param.name,
+                        BindKind::Param,
+                        param.ty.as_ref().map(Ty::from_annotation).unwrap_or(Ty::Any),
+                        &expr.area,
+                        reports,
+                    );
+                    if let Some(default) = &param.default {
+                        self.infer_expr(default, reports);
+                    }
+                }
+                let outer_depth = std::mem::replace(&mut self.loop_depth, 0);
+                let outer_labels = std::mem::take(&mut self.loop_labels);
+                self.fn_depth += 1;
+                self.validate_block(body, reports);
+                self.fn_depth -= 1;
+                self.loop_depth = outer_depth;
+                self.loop_labels = outer_labels;
+                self.pop_scope(reports);
+                Ty::Any
+            }
+            ExprKind::Match { subject, arms } => {
+                self.infer_expr(subject, reports);
+                for arm in arms {
+                    self.push_scope();
+                    self.declare_pattern(&arm.pattern, &expr.area, reports);
+                    self.infer_expr(&arm.body, reports);
+                    self.pop_scope(reports);
+                }
+                if !arms_exhaustive(arms) {
+                    reports.push(self.error(
+                        &expr.area,
+                        "non-exhaustive match: add a `_` pattern or a plain binding arm to cover the remaining cases",
+                    ));
+                }
+                Ty::Any
+            }
+            ExprKind::Path(_) | ExprKind::TypeDef(_) | ExprKind::Garbage => Ty::Any,
+        }
+    }
+
+    /// Binds every name a [`Pattern`] introduces into the current scope,
+    /// so a match arm's body can refer to them.
+    fn declare_pattern(
+        &mut self,
+        pattern: &Pattern,
+        area: &CodeArea,
+        reports: &mut Errors,
+    ) {
+        match pattern {
+            Pattern::Wildcard | Pattern::Literal(_) => {}
+            Pattern::Binding(name) => self.declare(name, BindKind::Param, Ty::Any, area, reports),
+            Pattern::Array { elements, rest } => {
+                for element in elements {
+                    self.declare_pattern(element, area, reports);
+                }
+                if let Some(rest) = rest {
+                    self.declare(rest, BindKind::Param, Ty::Any, area, reports);
+                }
+            }
+            Pattern::Compound { fields, .. } => {
+                for (_, binding) in fields {
+                    self.declare_pattern(binding, area, reports);
+                }
+            }
+        }
+    }
+
+    fn infer_binary(
+        &mut self,
+        op: BinOp,
+        lhs: &Ty,
+        rhs: &Ty,
+        area: &CodeArea,
+        reports: &mut Errors,
+    ) -> Ty {
+        use BinOp::*;
+        match op {
+            Eq | NotEq => {
+                if !lhs.compatible(rhs) {
+                    self.type_error(area, lhs, rhs, reports);
+                }
+                Ty::Bool
+            }
+            Lt | LtEq | Gt | GtEq => {
+                if !lhs.compatible(rhs) || (*lhs != Ty::Any && *lhs != Ty::Num) {
+                    self.type_error(area, lhs, rhs, reports);
+                }
+                Ty::Bool
+            }
+            And | Or => {
+                if !lhs.compatible(rhs) {
+                    self.type_error(area, lhs, rhs, reports);
+                }
+                Ty::Bool
+            }
+            Add => match (lhs, rhs) {
+                (Ty::Str, Ty::Str) => Ty::Str,
+                (Ty::Num, Ty::Num) => Ty::Num,
+                (Ty::Any, _) | (_, Ty::Any) => Ty::Any,
+                _ => {
+                    self.type_error(area, lhs, rhs, reports);
+                    Ty::Any
+                }
+            },
+            Sub | Mul | Div | Mod | Pow => match (lhs, rhs) {
+                (Ty::Num, Ty::Num) => Ty::Num,
+                (Ty::Any, _) | (_, Ty::Any) => Ty::Any,
+                _ => {
+                    self.type_error(area, lhs, rhs, reports);
+                    Ty::Any
+                }
+            },
+            BitAnd | BitOr | BitXor | Shl | Shr => Ty::Any,
+        }
+    }
+}
+
+/// Opt-in structural type-checker over annotations alone: verifies that
+/// literal-shaped values conform to the `num`/`str`/`T[]`/`type { .. }`
+/// annotations on `let`/`const` bindings, parameter defaults, and
+/// function returns. A thin, pipeline-ready packaging of the validator's
+/// gradual [`Ty`] rules for callers that want conformance checking
+/// without the full symbol-table sweep — anything whose type isn't
+/// evident from its literal structure passes as `any`.
+pub struct TypeChecker {
+    current_module: String,
+}
+
+impl TypeChecker {
+    pub fn from(parser: &Parser) -> Self {
+        Self {
+            current_module: parser.current_module.clone(),
+        }
+    }
+
+    /// Runs the conformance sweep over `stmts`, returning every mismatch.
+    pub fn check(&self, stmts: &[Statement]) -> Vec<ErrorReport> {
+        let mut walk = ShapeWalk {
+            checker: self,
+            reports: Errors::new(),
+        };
+        let _ = crate::ast::walk_block(&mut walk, stmts);
+        walk.reports.into_reports()
+    }
+
+    fn conform(&self, value: &Expr, expected: &Ty, reports: &mut Errors) {
+        let found = literal_ty(value);
+        if !found.compatible(expected) {
+            reports.push(ErrorReport {
+                code: Code::SemanticError.as_str(),
+                severity: Severity::Error,
+                call_stack: vec![],
+                notes: Vec::new(),
+                help: None,
+                current_module: self.current_module.clone(),
+                position: value.area.clone(),
+                message: format!(
+                    "type mismatch: `{}` is incompatible with `{}`",
+                    expected.describe(),
+                    found.describe()
+                ),
+                labels: vec![(value.area.clone(), "this value doesn't conform".to_owned())],
+            });
+        }
+    }
+
+    /// Checks every `return` of `body` against `ret`, without descending
+    /// into nested functions (their returns answer to their own
+    /// annotations).
+    fn check_returns(&self, body: &[Statement], ret: &Ty, reports: &mut Errors) {
+        struct Returns<'a> {
+            checker: &'a TypeChecker,
+            ret: &'a Ty,
+            reports: &'a mut Errors,
+        }
+        impl crate::ast::Visitor for Returns<'_> {
+            fn visit_statement(&mut self, stmt: &Statement) -> std::ops::ControlFlow<()> {
+                match &stmt.kind {
+                    StatementKind::Fn { .. } => std::ops::ControlFlow::Continue(()),
+                    StatementKind::Return(Some(value)) => {
+                        self.checker.conform(value, self.ret, self.reports);
+                        std::ops::ControlFlow::Continue(())
+                    }
+                    _ => crate::ast::walk_statement(self, stmt),
+                }
+            }
+
+            fn visit_expression(&mut self, expr: &Expr) -> std::ops::ControlFlow<()> {
+                match &expr.kind {
+                    ExprKind::ArrowFn { .. } => std::ops::ControlFlow::Continue(()),
+                    _ => crate::ast::walk_expression(self, expr),
+                }
+            }
+        }
+        let mut returns = Returns {
+            checker: self,
+            ret,
+            reports,
+        };
+        let _ = crate::ast::walk_block(&mut returns, body);
+    }
+}
+
+/// The walker behind [`TypeChecker::check`].
+struct ShapeWalk<'a> {
+    checker: &'a TypeChecker,
+    reports: Errors,
+}
+
+impl crate::ast::Visitor for ShapeWalk<'_> {
+    fn visit_statement(&mut self, stmt: &Statement) -> std::ops::ControlFlow<()> {
+        match &stmt.kind {
+            StatementKind::Let {
+                ty: Some(ty),
+                value: Some(value),
+                ..
+            }
+            | StatementKind::Const {
+                ty: Some(ty),
+                value: Some(value),
+                ..
+            } => {
+                self.checker
+                    .conform(value, &Ty::from_annotation(ty), &mut self.reports);
+            }
+            StatementKind::Fn {
+                params, ret, body, ..
+            } => {
+                for param in params {
+                    if let (Some(ty), Some(default)) = (&param.ty, &param.default) {
+                        self.checker
+                            .conform(default, &Ty::from_annotation(ty), &mut self.reports);
+                    }
+                }
+                if let Some(ret) = ret {
+                    self.checker
+                        .check_returns(body, &Ty::from_annotation(ret), &mut self.reports);
+                }
+            }
+            _ => {}
+        }
+        crate::ast::walk_statement(self, stmt)
+    }
+}
+
+impl crate::parser::PipelineStage for TypeChecker {
+    fn run(&mut self, stmts: Vec<Statement>) -> (Vec<Statement>, Vec<ErrorReport>) {
+        let reports = self.check(&stmts);
+        (stmts, reports)
+    }
+}
+
+/// The type evident from a value's literal structure alone — no symbol
+/// table, so identifiers, calls, and everything computed read as `Any`.
+fn literal_ty(expr: &Expr) -> Ty {
+    match &expr.kind {
+        ExprKind::Literal(lit) => Ty::of_literal(lit),
+        ExprKind::Array(elements) => Ty::Array(Box::new(
+            elements.first().map(literal_ty).unwrap_or(Ty::Any),
+        )),
+        ExprKind::Compound(fields) => {
+            // Any computed key means the shape is unknowable statically.
+            let mut shape = Vec::new();
+            for (key, value) in fields {
+                match key.as_static() {
+                    Some(name) => shape.push((name.to_owned(), literal_ty(value))),
+                    None => return Ty::Any,
+                }
+            }
+            Ty::Compound(shape)
+        }
+        _ => Ty::Any,
+    }
+}
+
+/// Resolves an import path to a loadable module, so the module-graph
+/// checks work against whatever actually backs imports: the filesystem
+/// ([`FsResolver`]), an archive, or an in-memory map in tests. Closures
+/// of the matching shape implement it for one-off resolvers.
+pub trait ModuleResolver {
+    /// Resolves `path` as written in an `import` inside `from`, yielding
+    /// the resolved source identity and its text.
+    fn resolve(
+        &self,
+        from: &crate::err::CodeSource,
+        path: &str,
+    ) -> Option<(crate::err::CodeSource, String)>;
+}
+
+impl<F> ModuleResolver for F
+where
+    F: Fn(&crate::err::CodeSource, &str) -> Option<(crate::err::CodeSource, String)>,
+{
+    fn resolve(
+        &self,
+        from: &crate::err::CodeSource,
+        path: &str,
+    ) -> Option<(crate::err::CodeSource, String)> {
+        self(from, path)
+    }
+}
+
+/// The default resolver: quoted import paths are files relative to the
+/// importing file's directory; dotted module paths are the runtime's
+/// business and resolve to nothing here.
+pub struct FsResolver;
+
+impl ModuleResolver for FsResolver {
+    fn resolve(
+        &self,
+        from: &crate::err::CodeSource,
+        path: &str,
+    ) -> Option<(crate::err::CodeSource, String)> {
+        let trimmed = path.trim_matches(['\'', '"']);
+        if trimmed == path {
+            // Not a quoted path: a dotted module like `std.io`.
+            return None;
+        }
+        let base = match from {
+            crate::err::CodeSource::File(file) => file
+                .parent()
+                .map(|dir| dir.to_path_buf())
+                .unwrap_or_default(),
+            _ => std::path::PathBuf::new(),
+        };
+        let full = base.join(trimmed);
+        let text = std::fs::read_to_string(&full).ok()?;
+        Some((crate::err::CodeSource::File(full), text))
+    }
+}
+
+/// Walks the import graph reachable from `root` (resolving each import
+/// path through `resolver`) and reports every import cycle it finds, with
+/// the cycle spelled out (`a → b → a`) and a label on each import
+/// statement along the way. A path the resolver can't handle is skipped —
+/// unresolvable imports are a different diagnostic's job.
+pub fn check_import_cycles(
+    root: &crate::err::CodeSource,
+    text: &str,
+    resolver: &dyn ModuleResolver,
+) -> Vec<ErrorReport> {
+    let mut reports = Errors::new();
+    let mut stack: Vec<(String, Option<CodeArea>)> = vec![(root.name(), None)];
+    let mut done: Vec<String> = Vec::new();
+    visit_imports(root, text, resolver, &mut stack, &mut done, &mut reports);
+    reports.into_reports()
+}
+
+fn visit_imports(
+    source: &crate::err::CodeSource,
+    text: &str,
+    resolver: &dyn ModuleResolver,
+    stack: &mut Vec<(String, Option<CodeArea>)>,
+    done: &mut Vec<String>,
+    reports: &mut Errors,
+) {
+    use logos::Logos as _;
+
+    let mut parser = Parser::new(source.clone(), crate::tk::Token::lexer(text));
+    let (stmts, _) = parser.parse_all_recovering();
+    for stmt in &stmts {
+        let StatementKind::Import { path, .. } = &stmt.kind else {
+            continue;
+        };
+        let Some((next_src, next_text)) = resolver.resolve(source, path) else {
+            continue;
+        };
+        let name = next_src.name();
+        if let Some(at) = stack.iter().position(|(visiting, _)| visiting == &name) {
+            let mut cycle: Vec<String> =
+                stack[at..].iter().map(|(visiting, _)| visiting.clone()).collect();
+            cycle.push(name.clone());
+            let mut report = ErrorReport {
+                code: Code::ImportCycle.as_str(),
+                severity: Severity::Error,
+                call_stack: vec![],
+                notes: Vec::new(),
+                help: None,
+                current_module: source.name(),
+                position: stmt.area.clone(),
+                message: format!("circular import: {}", cycle.join(" → ")),
+                labels: vec![(stmt.area.clone(), "this import completes the cycle".to_owned())],
+            };
+            for (_, area) in &stack[at..] {
+                if let Some(area) = area {
+                    report
+                        .labels
+                        .push((area.clone(), "imported along the cycle here".to_owned()));
+                }
+            }
+            reports.push(report);
+            continue;
+        }
+        if done.contains(&name) {
+            continue;
+        }
+        stack.push((name.clone(), Some(stmt.area.clone())));
+        visit_imports(&next_src, &next_text, resolver, stack, done, reports);
+        stack.pop();
+        done.push(name);
+    }
+}
+
+/// Collects which other `const`s a const's initializer reads, by position
+/// in the block's declaration order. An arrow-function body is skipped:
+/// the closure runs later, by which time every const exists, so its
+/// references impose no evaluation ordering.
+struct ConstRefs<'a> {
+    positions: &'a AHashMap<String, usize>,
+    found: Vec<(usize, CodeArea)>,
+}
+
+impl crate::ast::Visitor for ConstRefs<'_> {
+    fn visit_expression(&mut self, expr: &Expr) -> std::ops::ControlFlow<()> {
+        match &expr.kind {
+            ExprKind::Ident(name) => {
+                if let Some(&at) = self.positions.get(name) {
+                    self.found.push((at, expr.area.clone()));
+                }
+                std::ops::ControlFlow::Continue(())
+            }
+            ExprKind::ArrowFn { .. } => std::ops::ControlFlow::Continue(()),
+            _ => crate::ast::walk_expression(self, expr),
+        }
+    }
+}
+
+/// Orders the `const` declarations of one block for evaluation, so that
+/// `const a = 10` followed by `const b = a * 2` evaluates `a` first (the
+/// groundwork constant folding needs to inline const chains in one sweep).
+/// Returns the statement indices of the block's consts in dependency order,
+/// plus an error for every self-referential initializer (`const a = a`),
+/// every reference to a const defined later in the block, and for a
+/// dependency cycle, spelled out like the import-cycle check's
+/// (`a → b → a`). A reference that's part of the reported cycle isn't
+/// also reported as a forward reference.
+pub fn check_const_order(
+    current_module: &str,
+    stmts: &[Statement],
+) -> (Vec<usize>, Vec<ErrorReport>) {
+    let consts: Vec<(usize, &str, &CodeArea, Option<&Expr>)> = stmts
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, stmt)| match &stmt.kind {
+            StatementKind::Const { name, value, .. } => {
+                Some((idx, name.as_str(), &stmt.area, value.as_ref()))
+            }
+            _ => None,
+        })
+        .collect();
+    let positions: AHashMap<String, usize> = consts
+        .iter()
+        .enumerate()
+        .map(|(pos, (_, name, ..))| ((*name).to_owned(), pos))
+        .collect();
+
+    let error = |area: &CodeArea, message: String| ErrorReport {
+        code: Code::ConstDependency.as_str(),
+        severity: Severity::Error,
+        call_stack: vec![],
+        notes: Vec::new(),
+        help: None,
+        current_module: current_module.to_owned(),
+        position: area.clone(),
+        message,
+        labels: vec![],
+    };
+
+    let mut reports = Vec::new();
+    // For each const, the positions it depends on, plus any forward
+    // references held back until we know whether a cycle explains them.
+    let mut deps: Vec<Vec<usize>> = Vec::with_capacity(consts.len());
+    let mut forward: Vec<(usize, usize, CodeArea)> = Vec::new();
+    for (pos, (_, name, _, value)) in consts.iter().enumerate() {
+        let mut refs = ConstRefs {
+            positions: &positions,
+            found: Vec::new(),
+        };
+        if let Some(value) = value {
+            let _ = crate::ast::Visitor::visit_expression(&mut refs, value);
+        }
+        let mut edges = Vec::new();
+        for (dep, use_area) in refs.found {
+            if dep == pos {
+                let mut report = error(
+                    &use_area,
+                    format!("const `{name}` refers to itself in its own initializer"),
+                );
+                report.labels = vec![(use_area, "references itself here".to_owned())];
+                reports.push(report);
+            } else {
+                if dep > pos {
+                    forward.push((pos, dep, use_area));
+                }
+                if !edges.contains(&dep) {
+                    edges.push(dep);
+                }
+            }
+        }
+        deps.push(edges);
+    }
+
+    // Kahn's algorithm, always taking the earliest declaration that's
+    // ready so the order is deterministic and as close to source order
+    // as the dependencies allow.
+    let mut order = Vec::with_capacity(consts.len());
+    let mut emitted = vec![false; consts.len()];
+    loop {
+        let next = (0..consts.len()).find(|&pos| {
+            !emitted[pos] && deps[pos].iter().all(|&dep| emitted[dep])
+        });
+        let Some(pos) = next else { break };
+        emitted[pos] = true;
+        order.push(consts[pos].0);
+    }
+
+    // Whatever Kahn couldn't emit sits on a cycle; walk the remaining
+    // edges from the earliest stuck const to spell one out.
+    if let Some(start) = (0..consts.len()).find(|&pos| !emitted[pos]) {
+        let mut path = vec![start];
+        let mut at = start;
+        let cycle_start = loop {
+            let &next = deps[at]
+                .iter()
+                .find(|&&dep| !emitted[dep])
+                .expect("a const stuck in a cycle always has an unemitted dependency");
+            if let Some(found) = path.iter().position(|&seen| seen == next) {
+                break found;
+            }
+            path.push(next);
+            at = next;
+        };
+        let mut cycle: Vec<String> = path[cycle_start..]
+            .iter()
+            .map(|&pos| consts[pos].1.to_owned())
+            .collect();
+        cycle.push(consts[path[cycle_start]].1.to_owned());
+        let first = path[cycle_start];
+        let mut report = error(
+            consts[first].2,
+            format!("const dependency cycle: {}", cycle.join(" → ")),
+        );
+        report.labels = path[cycle_start..]
+            .iter()
+            .map(|&pos| {
+                (
+                    consts[pos].2.clone(),
+                    "depends on the next const along the cycle".to_owned(),
+                )
+            })
+            .collect();
+        reports.push(report);
+    }
+
+    // A forward reference between two consts that both ordered fine is
+    // its own mistake; one inside the cycle is already explained above.
+    for (pos, dep, use_area) in forward {
+        if !emitted[pos] || !emitted[dep] {
+            continue;
+        }
+        let (_, name, _, _) = consts[pos];
+        let other = consts[dep].1;
+        let mut report = error(
+            &use_area,
+            format!("const `{name}` is initialized from `{other}` before `{other}` is defined"),
+        );
+        report.labels = vec![
+            (use_area.clone(), "used here".to_owned()),
+            (
+                consts[dep].2.clone(),
+                "but only defined down here".to_owned(),
+            ),
+        ];
+        reports.push(report);
+    }
+
+    (order, reports)
+}
+
+/// Whether a type-annotation expression mentions `name` anywhere — the
+/// "is this type parameter used" test for [`Validator::check_type_params`].
+fn annotation_mentions(ty: &Expr, name: &str) -> bool {
+    struct Finder<'a> {
+        name: &'a str,
+        found: bool,
+    }
+    impl crate::ast::Visitor for Finder<'_> {
+        fn visit_expression(&mut self, expr: &Expr) -> std::ops::ControlFlow<()> {
+            match &expr.kind {
+                ExprKind::Ident(ident) if ident == self.name => {
+                    self.found = true;
+                    std::ops::ControlFlow::Break(())
+                }
+                ExprKind::TypeDef(fields)
+                    if fields.iter().any(|(_, field_ty)| field_ty == self.name) =>
+                {
+                    self.found = true;
+                    std::ops::ControlFlow::Break(())
+                }
+                _ => crate::ast::walk_expression(self, expr),
+            }
+        }
+    }
+    let mut finder = Finder { name, found: false };
+    let _ = crate::ast::Visitor::visit_expression(&mut finder, ty);
+    finder.found
+}
+
+/// The suppression-comment rule name for a warning code, if it has one;
+/// the mapping lives in the central [`crate::err::codes`] registry.
+fn rule_name(code: &str) -> Option<&'static str> {
+    crate::err::codes::lookup(code).and_then(|code| code.rule_name())
+}
+
+/// The [`Ty::Fn`] signature a `fn` declaration advertises, shared by the
+/// hoisting pre-pass and the statement's own declaration.
+fn fn_signature(params: &[crate::ast::Param], ret: &Option<Expr>) -> Ty {
+    let param_tys: Vec<Ty> = params
+        .iter()
+        .map(|param| {
+            let ty = param
+                .ty
+                .as_ref()
+                .map(Ty::from_annotation)
+                .unwrap_or(Ty::Any);
+            if param.variadic {
+                // `...rest` collects its arguments into an array.
+                Ty::Array(Box::new(ty))
+            } else {
+                ty
+            }
+        })
+        .collect();
+    let ret_ty = ret.as_ref().map(Ty::from_annotation).unwrap_or(Ty::Any);
+    Ty::Fn(param_tys, Box::new(ret_ty))
+}
+
+/// Whether `pattern` matches any value unconditionally, making it a valid
+/// catch-all for exhaustiveness purposes. Array/compound destructures are
+/// never treated as catch-alls even with a `rest`/`..`, since they still
+/// require the subject to have that shape.
+fn is_catchall(pattern: &Pattern) -> bool {
+    matches!(pattern, Pattern::Wildcard | Pattern::Binding(_))
+}
+
+/// Whether a match's arms cover every possible value of its subject. Beyond
+/// an explicit catch-all arm, a match made up entirely of `true`/`false`
+/// literal patterns is exhaustive too, since those are the only two values
+/// a bool can take.
+fn arms_exhaustive(arms: &[MatchArm]) -> bool {
+    if arms.iter().any(|arm| is_catchall(&arm.pattern)) {
+        return true;
+    }
+    let (mut seen_true, mut seen_false) = (false, false);
+    for arm in arms {
+        match &arm.pattern {
+            Pattern::Literal(Literal::Bool(true)) => seen_true = true,
+            Pattern::Literal(Literal::Bool(false)) => seen_false = true,
+            _ => return false,
+        }
+    }
+    seen_true && seen_false
+}
+
+/// Names resolved by the runtime rather than user code (globals, native
+/// intrinsics). Kept as a short allow-list rather than a real prelude scope
+/// since the validator doesn't yet model module imports.
+fn is_builtin(name: &str) -> bool {
+    matches!(
+        name,
+        "println" | "print" | "env" | "file" | "args" | "Error" | "unreachable"
+    )
+}